@@ -0,0 +1,136 @@
+//! Drawing large synthetic datasets on a map, with and without simplification, culling and
+//! clustering.
+//!
+//! Run with `cargo bench --bench spatial_entities`.
+
+use std::rc::Rc;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use geo::{coord, Geometry, LineString, Point, Rect};
+use implicit_clone::unsync::IString;
+use kg_ui_toolkit::{
+    components::spatial_entities::{Map, RenderOptions, Scene, Simplification},
+    crs::Crs,
+    models,
+};
+
+const WIDTH: f64 = 800.0;
+
+/// A deterministic pseudo-random number generator, so that runs are comparable.
+struct Lcg(u64);
+
+impl Lcg {
+    /// A number from -0.5 to 0.5.
+    fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    }
+}
+
+/// An area off Cairns a degree across.
+fn map() -> Map {
+    Map {
+        src: IString::default(),
+        extent: Rc::new(Rect::new(
+            coord! { x: 145.5, y: -17.5 },
+            coord! { x: 146.5, y: -16.5 },
+        )),
+        crs: Crs::Crs84,
+    }
+}
+
+fn entity(i: usize, geometry: Geometry) -> (IString, models::Entity) {
+    (
+        IString::from(format!("http://example.com/entity/{i}")),
+        models::Entity {
+            label: Some(IString::from(format!("Entity {i}"))),
+            geometry: Some(Rc::new(geometry)),
+            ..models::Entity::default()
+        },
+    )
+}
+
+/// Survey tracks as random walks of many short steps.
+fn tracks(count: usize, vertices: usize) -> Vec<(IString, models::Entity)> {
+    let mut random = Lcg(1);
+    (0..count)
+        .map(|i| {
+            let mut position = coord! { x: 146.0 + random.next(), y: -17.0 + random.next() };
+            let track = (0..vertices)
+                .map(|_| {
+                    position.x += random.next() * 1e-3;
+                    position.y += random.next() * 1e-3;
+                    position
+                })
+                .collect::<LineString>();
+            entity(i, Geometry::LineString(track))
+        })
+        .collect()
+}
+
+fn points(count: usize) -> Vec<(IString, models::Entity)> {
+    let mut random = Lcg(2);
+    (0..count)
+        .map(|i| {
+            let point = Point::new(146.0 + random.next(), -17.0 + random.next());
+            entity(i, Geometry::Point(point))
+        })
+        .collect()
+}
+
+fn bench_tracks(c: &mut Criterion) {
+    let map = map();
+    let scene = Scene::new(&tracks(2000, 500), &map);
+    let whole = *map.extent;
+    let zoomed = Rect::new(
+        coord! { x: 145.95, y: -17.05 },
+        coord! { x: 146.05, y: -16.95 },
+    );
+    let unsimplified = RenderOptions {
+        simplification: Simplification::None,
+        ..RenderOptions::default()
+    };
+    let simplified = RenderOptions::default();
+
+    let mut group = c.benchmark_group("2000 tracks of 500 vertices");
+    group.sample_size(10);
+    group.bench_function("whole map, not simplified", |b| {
+        b.iter(|| scene.to_svg(black_box(&unsimplified), WIDTH, &whole))
+    });
+    group.bench_function("whole map, simplified", |b| {
+        b.iter(|| scene.to_svg(black_box(&simplified), WIDTH, &whole))
+    });
+    group.bench_function("zoomed in, not simplified", |b| {
+        b.iter(|| scene.to_svg(black_box(&unsimplified), WIDTH, &zoomed))
+    });
+    group.bench_function("zoomed in, simplified", |b| {
+        b.iter(|| scene.to_svg(black_box(&simplified), WIDTH, &zoomed))
+    });
+    group.finish();
+}
+
+fn bench_points(c: &mut Criterion) {
+    let map = map();
+    let scene = Scene::new(&points(20000), &map);
+    let whole = *map.extent;
+    let clustered = RenderOptions {
+        cluster_distance: Some(40.0),
+        ..RenderOptions::default()
+    };
+
+    let mut group = c.benchmark_group("20000 points");
+    group.sample_size(10);
+    group.bench_function("not clustered", |b| {
+        b.iter(|| scene.to_svg(black_box(&RenderOptions::default()), WIDTH, &whole))
+    });
+    group.bench_function("clustered", |b| {
+        b.iter(|| scene.to_svg(black_box(&clustered), WIDTH, &whole))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tracks, bench_points);
+criterion_main!(benches);
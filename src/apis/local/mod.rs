@@ -0,0 +1,851 @@
+//! An in-memory stand-in for the stored queries of our Stardog databases.
+//!
+//! A [`Dataset`] is loaded from Turtle files such as `sample_data/a-plus-b.ttl` and answers
+//! the stored queries used by [`crate::apis::sparql`] with SPARQL JSON results shaped like
+//! those returned by Stardog. It implements [`Transport`] so that it can stand in for the API
+//! path in tests, and the `kg-local-sparql` binary serves it over HTTP for offline demos.
+//!
+//! No reasoning is performed, so only asserted triples are returned. As our Stardog databases
+//! always have PROV-O loaded, PROV terms without a label in the dataset are labelled with
+//! their local name.
+//!
+//! The stored spatial searches test geometries with the `geo` crate after converting them to
+//! CRS84, rather than on the ellipsoid as Stardog does, which only matters near the poles or for
+//! shapes many degrees across.
+//!
+//! Assessment dimensions are described with the following vocabulary, where `dim:` is
+//! `http://demo.org/dimensions/`:
+//!
+//! ```turtle
+//! ex:pathA dim:hasDimValues ex:assessmentPathA .
+//! ex:assessmentPathA dim:hasDimValue [ dim:dimension ex:foo ; dim:value 3 ] .
+//! ex:foo dim:name "Foo" ; dim:order 1 ; dim:dimRange 5 ; dim:targetRange 5 .
+//! ```
+
+use std::{fmt, fs, io, path::Path};
+
+use geo::{Geometry, Relate};
+use geojson::GeoJson;
+use serde_json::{json, Map, Value};
+
+use super::sparql::{Body, SpatialRelation, StoredQuery, Transport, WktLiteral};
+
+mod turtle;
+
+pub use turtle::ParseError;
+
+const DIM: &str = "http://demo.org/dimensions/";
+const GEO: &str = "http://www.opengis.net/ont/geosparql#";
+const PROV: &str = "http://www.w3.org/ns/prov#";
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// The variables bound by the stored queries that return spatial entities.
+const SPATIAL_ENTITY_VARS: [&str; 5] = ["entity", "label", "geojson", "wkt", "generatedAt"];
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Term {
+    BlankNode(String),
+    Iri(String),
+    Literal {
+        value: String,
+        datatype: Option<String>,
+        language: Option<String>,
+    },
+}
+
+impl Term {
+    fn to_binding(&self) -> Value {
+        match self {
+            Term::BlankNode(id) => json!({ "type": "bnode", "value": id }),
+            Term::Iri(iri) => json!({ "type": "uri", "value": iri }),
+            Term::Literal {
+                value,
+                datatype,
+                language,
+            } => {
+                let mut binding = Map::new();
+                if let Some(datatype) = datatype {
+                    binding.insert("datatype".into(), datatype.clone().into());
+                }
+                if let Some(language) = language {
+                    binding.insert("xml:lang".into(), language.clone().into());
+                }
+                binding.insert("type".into(), "literal".into());
+                binding.insert("value".into(), value.clone().into());
+                binding.into()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triple {
+    pub s: Term,
+    pub p: String,
+    pub o: Term,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(ParseError),
+    UnknownQuery(String),
+    InvalidParameter(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Parse(e) => write!(f, "{e}"),
+            Error::UnknownQuery(name) => write!(f, "unknown stored query {name}"),
+            Error::InvalidParameter(param) => write!(f, "missing or invalid parameter ${param}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Debug, Default)]
+pub struct Dataset {
+    triples: Vec<Triple>,
+    blank_nodes: usize,
+    /// The number of documents loaded.
+    loads: usize,
+}
+
+impl Dataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_turtle(turtle: &str) -> Result<Self, Error> {
+        let mut dataset = Self::new();
+        dataset.load_turtle(turtle)?;
+        Ok(dataset)
+    }
+
+    /// Adds the triples of a Turtle document to the dataset. Its blank nodes are its own, even
+    /// when their labels are used in other documents.
+    pub fn load_turtle(&mut self, turtle: &str) -> Result<(), Error> {
+        self.loads += 1;
+        turtle::parse(turtle, self.loads, &mut self.blank_nodes, &mut self.triples)
+            .map_err(Error::Parse)
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let turtle = fs::read_to_string(path).map_err(Error::Io)?;
+        self.load_turtle(&turtle)
+    }
+
+    pub fn triples(&self) -> &[Triple] {
+        &self.triples
+    }
+
+    /// Runs a stored query, returning a SPARQL JSON results document.
+    pub fn query(&self, query: &StoredQuery) -> Result<Value, Error> {
+        match query.name.as_str() {
+            "getActivity" => Ok(self.describe("A", &param_iri(query, "activity")?)),
+            "getAgent" => Ok(self.describe("agent", &param_iri(query, "agent")?)),
+            "getDimDesc" => Ok(self.dim_desc(&param_iri(query, "object")?)),
+            "getDimValues" => Ok(self.dim_values(&param_iri(query, "object")?)),
+            "getEntities" => Ok(self.describe_all(&param_iris(query, "entities")?)),
+            "getEntity" => Ok(self.describe("object", &param_iri(query, "entity")?)),
+            "getObject" => Ok(self.describe("object", &param_iri(query, "object")?)),
+            "getObjects" => Ok(self.describe_all(&param_iris(query, "objects")?)),
+            "getSpatialEntities" => Ok(self.spatial_entities(&param_iris(query, "entities")?)),
+            "getSpatialEntity" => Ok(self.spatial_entity(&param_iri(query, "entity")?)),
+            "getSpatialEntitiesIntersecting" => {
+                self.spatial_search(SpatialRelation::Intersects, query)
+            }
+            "getSpatialEntitiesWithin" => self.spatial_search(SpatialRelation::Within, query),
+            name => Err(Error::UnknownQuery(name.into())),
+        }
+    }
+
+    fn objects<'a>(&'a self, s: &'a Term, p: &'a str) -> impl Iterator<Item = &'a Term> {
+        self.triples
+            .iter()
+            .filter(move |t| &t.s == s && t.p == p)
+            .map(|t| &t.o)
+    }
+
+    fn object(&self, s: &Term, p: &str) -> Option<&Term> {
+        self.triples
+            .iter()
+            .find(|t| &t.s == s && t.p == p)
+            .map(|t| &t.o)
+    }
+
+    fn label(&self, iri: &str) -> Option<Term> {
+        self.object(&Term::Iri(iri.into()), RDFS_LABEL)
+            .cloned()
+            .or_else(|| {
+                iri.strip_prefix(PROV).map(|local_name| Term::Literal {
+                    value: local_name.into(),
+                    datatype: None,
+                    language: None,
+                })
+            })
+    }
+
+    fn describe(&self, var: &str, subject: &Term) -> Value {
+        results(
+            &[var, "p", "plabel", "o", "olabel"],
+            self.describe_bindings(var, subject),
+        )
+    }
+
+    /// Describes each of the subjects of a batched query, binding them to `?subject`.
+    fn describe_all(&self, subjects: &[Term]) -> Value {
+        let bindings = subjects
+            .iter()
+            .flat_map(|subject| self.describe_bindings("subject", subject))
+            .collect();
+        results(&["subject", "p", "plabel", "o", "olabel"], bindings)
+    }
+
+    fn describe_bindings<'a>(
+        &self,
+        var: &'a str,
+        subject: &Term,
+    ) -> Vec<Vec<(&'a str, Option<Term>)>> {
+        self.triples
+            .iter()
+            .filter(|t| &t.s == subject)
+            .map(|t| {
+                let mut binding = vec![
+                    (var, Some(subject.clone())),
+                    ("p", Some(Term::Iri(t.p.clone()))),
+                    ("plabel", self.label(&t.p)),
+                    ("o", Some(t.o.clone())),
+                ];
+                if let Term::Iri(o) = &t.o {
+                    binding.push(("olabel", self.label(o)));
+                }
+                binding
+            })
+            .collect()
+    }
+
+    fn spatial_entity(&self, entity: &Term) -> Value {
+        self.spatial_entities(std::slice::from_ref(entity))
+    }
+
+    fn spatial_entities(&self, entities: &[Term]) -> Value {
+        let bindings = entities
+            .iter()
+            .flat_map(|entity| self.spatial_entity_bindings(entity))
+            .collect();
+        results(&SPATIAL_ENTITY_VARS, bindings)
+    }
+
+    fn spatial_entity_bindings(&self, entity: &Term) -> Vec<Vec<(&'static str, Option<Term>)>> {
+        self.objects(entity, &format!("{GEO}hasGeometry"))
+            .map(|geometry| self.spatial_binding(entity, geometry))
+            .collect()
+    }
+
+    fn spatial_binding(&self, entity: &Term, geometry: &Term) -> Vec<(&'static str, Option<Term>)> {
+        vec![
+            ("entity", Some(entity.clone())),
+            ("label", self.object(entity, RDFS_LABEL).cloned()),
+            (
+                "geojson",
+                self.object(geometry, &format!("{GEO}asGeoJSON")).cloned(),
+            ),
+            (
+                "wkt",
+                self.object(geometry, &format!("{GEO}asWKT")).cloned(),
+            ),
+            ("generatedAt", self.generated_at(entity)),
+        ]
+    }
+
+    /// The `prov:atTime` of an entity, or else the `prov:endedAtTime` of the activity that
+    /// generated it.
+    fn generated_at(&self, entity: &Term) -> Option<Term> {
+        self.object(entity, &format!("{PROV}atTime"))
+            .or_else(|| {
+                self.objects(entity, &format!("{PROV}wasGeneratedBy"))
+                    .find_map(|activity| self.object(activity, &format!("{PROV}endedAtTime")))
+            })
+            .cloned()
+    }
+
+    /// A geometry node as CRS84 coordinates, from its WKT or else its GeoJSON.
+    fn lon_lat(&self, geometry: &Term) -> Option<Geometry> {
+        let literal = |p: &str| match self.object(geometry, &format!("{GEO}{p}")) {
+            Some(Term::Literal { value, .. }) => Some(value.as_str()),
+            _ => None,
+        };
+        literal("asWKT")
+            .and_then(|wkt| wkt.parse::<WktLiteral>().ok()?.into_lon_lat())
+            .or_else(|| {
+                let geojson = literal("asGeoJSON")?.parse::<GeoJson>().ok()?;
+                geojson.try_into().ok()
+            })
+    }
+
+    /// Answers `getSpatialEntitiesIntersecting` and `getSpatialEntitiesWithin`, with a binding
+    /// for each geometry of an entity that relates to the `$geometry` parameter.
+    fn spatial_search(
+        &self,
+        relation: SpatialRelation,
+        query: &StoredQuery,
+    ) -> Result<Value, Error> {
+        let search = param_literal(query, "geometry")?
+            .parse::<WktLiteral>()
+            .ok()
+            .and_then(WktLiteral::into_lon_lat)
+            .ok_or_else(|| Error::InvalidParameter("geometry".into()))?;
+        let has_geometry = format!("{GEO}hasGeometry");
+        let bindings = self
+            .triples
+            .iter()
+            .filter(|t| t.p == has_geometry)
+            .filter(|t| {
+                self.lon_lat(&t.o).is_some_and(|geometry| {
+                    let matrix = geometry.relate(&search);
+                    match relation {
+                        SpatialRelation::Intersects => matrix.is_intersects(),
+                        SpatialRelation::Within => matrix.is_within(),
+                    }
+                })
+            })
+            .map(|t| self.spatial_binding(&t.s, &t.o))
+            .collect();
+        Ok(results(&SPATIAL_ENTITY_VARS, bindings))
+    }
+
+    /// The `(dimension, value)` pairs of every assessment of an object.
+    fn dim_value_nodes<'a>(&'a self, object: &'a Term) -> Vec<(&'a Term, &'a Term)> {
+        let has_dim_value = format!("{DIM}hasDimValue");
+        let dimension = format!("{DIM}dimension");
+        let value = format!("{DIM}value");
+        self.objects(object, &format!("{DIM}hasDimValues"))
+            .flat_map(|assessment| self.objects(assessment, &has_dim_value))
+            .filter_map(|node| Some((self.object(node, &dimension)?, self.object(node, &value)?)))
+            .collect()
+    }
+
+    fn dim_desc(&self, object: &Term) -> Value {
+        let mut dimensions = self
+            .dim_value_nodes(object)
+            .into_iter()
+            .map(|(dimension, _)| dimension)
+            .collect::<Vec<_>>();
+        dimensions.sort();
+        dimensions.dedup();
+        let bindings = dimensions
+            .into_iter()
+            .map(|dimension| {
+                vec![
+                    (
+                        "targetRange",
+                        self.object(dimension, &format!("{DIM}targetRange"))
+                            .cloned(),
+                    ),
+                    (
+                        "name",
+                        self.object(dimension, &format!("{DIM}name")).cloned(),
+                    ),
+                    (
+                        "order",
+                        self.object(dimension, &format!("{DIM}order")).cloned(),
+                    ),
+                    (
+                        "dimRange",
+                        self.object(dimension, &format!("{DIM}dimRange")).cloned(),
+                    ),
+                    (
+                        "dimMin",
+                        self.object(dimension, &format!("{DIM}dimMin")).cloned(),
+                    ),
+                    (
+                        "targetMin",
+                        self.object(dimension, &format!("{DIM}targetMin")).cloned(),
+                    ),
+                    (
+                        "inverted",
+                        self.object(dimension, &format!("{DIM}inverted")).cloned(),
+                    ),
+                ]
+            })
+            .collect();
+        results(
+            &[
+                "targetRange",
+                "name",
+                "order",
+                "dimRange",
+                "dimMin",
+                "targetMin",
+                "inverted",
+            ],
+            bindings,
+        )
+    }
+
+    fn dim_values(&self, object: &Term) -> Value {
+        let bindings = self
+            .dim_value_nodes(object)
+            .into_iter()
+            .map(|(dimension, value)| {
+                vec![
+                    ("d", self.object(dimension, &format!("{DIM}name")).cloned()),
+                    ("v", Some(value.clone())),
+                ]
+            })
+            .collect();
+        results(&["d", "v"], bindings)
+    }
+}
+
+impl Transport for Dataset {
+    fn endpoint(&self) -> &str {
+        "local"
+    }
+
+    async fn fetch(&self, query: &StoredQuery) -> Result<Body, gloo_net::Error> {
+        self.query(query)
+            .map(|results| results.to_string().into())
+            .map_err(|e| gloo_net::Error::GlooError(e.to_string()))
+    }
+}
+
+fn param_iri(query: &StoredQuery, param: &str) -> Result<Term, Error> {
+    query
+        .params
+        .iter()
+        .find(|(name, _)| name == param)
+        .and_then(|(_, value)| value.strip_prefix('<')?.strip_suffix('>'))
+        .map(|iri| Term::Iri(iri.into()))
+        .ok_or_else(|| Error::InvalidParameter(param.into()))
+}
+
+/// The value of a parameter bound to a literal, e.g. `"POINT (1 2)"^^<...#wktLiteral>`.
+fn param_literal(query: &StoredQuery, param: &str) -> Result<String, Error> {
+    let invalid = || Error::InvalidParameter(param.into());
+    let (_, value) = query
+        .params
+        .iter()
+        .find(|(name, _)| name == param)
+        .ok_or_else(invalid)?;
+    let quoted = match value.rsplit_once("^^") {
+        Some((quoted, _)) => quoted,
+        None => value,
+    };
+    let quoted = quoted
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    let mut literal = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        literal.push(match (c, c == '\\') {
+            (_, true) => match chars.next().ok_or_else(invalid)? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                escaped => escaped,
+            },
+            (c, false) => c,
+        });
+    }
+    Ok(literal)
+}
+
+/// Parses a parameter bound to a whitespace separated list of IRIs, as in a `VALUES` clause.
+fn param_iris(query: &StoredQuery, param: &str) -> Result<Vec<Term>, Error> {
+    let (_, value) = query
+        .params
+        .iter()
+        .find(|(name, _)| name == param)
+        .ok_or_else(|| Error::InvalidParameter(param.into()))?;
+    value
+        .split_whitespace()
+        .map(|iri| {
+            iri.strip_prefix('<')
+                .and_then(|iri| iri.strip_suffix('>'))
+                .map(|iri| Term::Iri(iri.into()))
+                .ok_or_else(|| Error::InvalidParameter(param.into()))
+        })
+        .collect()
+}
+
+fn results(vars: &[&str], bindings: Vec<Vec<(&str, Option<Term>)>>) -> Value {
+    let bindings = bindings
+        .into_iter()
+        .map(|binding| {
+            binding
+                .into_iter()
+                .filter_map(|(var, term)| Some((var.to_string(), term?.to_binding())))
+                .collect::<Map<_, _>>()
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "head": { "vars": vars },
+        "results": { "bindings": bindings },
+    })
+}
+
+/// Parses the query string of a stored query URL, as built by [`StoredQuery::url`].
+pub fn parse_query_string(query_string: &str) -> Option<StoredQuery> {
+    let mut name = None;
+    let mut params = Vec::new();
+    for pair in query_string.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let (key, value) = (percent_decode(key), percent_decode(value));
+        if key == "query" {
+            name = Some(value);
+        } else if let Some(param) = key.strip_prefix('$') {
+            params.push((param.to_string(), value));
+        }
+    }
+    Some(StoredQuery {
+        name: name?,
+        params,
+    })
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+//
+
+#[cfg(test)]
+mod test {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::apis::sparql::{self, Response};
+
+    /// Drives a future that never waits, as is the case for every query on a [`Dataset`].
+    fn now<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("a local query should never be pending"),
+        }
+    }
+
+    fn dataset(file: &str) -> Dataset {
+        let mut dataset = Dataset::new();
+        dataset
+            .load_file(format!("{}/sample_data/{file}", env!("CARGO_MANIFEST_DIR")))
+            .unwrap();
+        dataset
+    }
+
+    #[test]
+    fn test_parse_turtle() {
+        let dataset = Dataset::from_turtle(
+            r#"
+            PREFIX ex: <http://example.org/>
+            @base <http://example.com/> .
+            <a> a ex:Thing ; ex:p "x\ty"@en, 'z', """long
+"quoted" text""" ; ex:q 1, -2.5, 3e2, true ;
+                ex:r [ ex:s _:b ] ; .
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            format!("{:?}", dataset.triples()),
+            "[Triple { s: Iri(\"http://example.com/a\"), p: \"http://www.w3.org/1999/02/22-rdf-syntax-ns#type\", o: Iri(\"http://example.org/Thing\") }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/p\", o: Literal { value: \"x\\ty\", datatype: None, language: Some(\"en\") } }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/p\", o: Literal { value: \"z\", datatype: None, language: None } }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/p\", o: Literal { value: \"long\\n\\\"quoted\\\" text\", datatype: None, language: None } }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/q\", o: Literal { value: \"1\", datatype: Some(\"http://www.w3.org/2001/XMLSchema#integer\"), language: None } }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/q\", o: Literal { value: \"-2.5\", datatype: Some(\"http://www.w3.org/2001/XMLSchema#decimal\"), language: None } }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/q\", o: Literal { value: \"3e2\", datatype: Some(\"http://www.w3.org/2001/XMLSchema#double\"), language: None } }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/q\", o: Literal { value: \"true\", datatype: Some(\"http://www.w3.org/2001/XMLSchema#boolean\"), language: None } }, Triple { s: BlankNode(\"genid-1\"), p: \"http://example.org/s\", o: BlankNode(\"1-b\") }, Triple { s: Iri(\"http://example.com/a\"), p: \"http://example.org/r\", o: BlankNode(\"genid-1\") }]"
+        );
+    }
+
+    #[test]
+    fn test_blank_nodes_per_file() {
+        let dir = std::env::temp_dir().join(format!("kg-ui-toolkit-turtle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.ttl"), dir.join("b.ttl"));
+        fs::write(&a, "<http://example.com/a> <http://example.org/p> _:b0 .").unwrap();
+        fs::write(
+            &b,
+            "<http://example.com/b> <http://example.org/p> _:b0, _:genid-1, [] .",
+        )
+        .unwrap();
+        let mut dataset = Dataset::new();
+        dataset.load_file(&a).unwrap();
+        dataset.load_file(&b).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            dataset
+                .triples()
+                .iter()
+                .map(|triple| triple.o.clone())
+                .collect::<Vec<_>>(),
+            ["1-b0", "2-b0", "2-genid-1", "genid-1"].map(|id| Term::BlankNode(id.into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_turtle_error() {
+        assert_eq!(
+            Dataset::from_turtle("@prefix ex: <http://example.org/> .\n\nex:a ex:b nope:c .")
+                .unwrap_err()
+                .to_string(),
+            "line 3: undeclared prefix nope:"
+        );
+    }
+
+    #[test]
+    fn test_parse_query_string() {
+        assert_eq!(
+            parse_query_string(
+                "query=getEntity&%24entity=%3Chttp%3A%2F%2Fexample.com%2Fdata%2Fc%3E"
+            ),
+            Some(StoredQuery::new("getEntity").iri("entity", "http://example.com/data/c"))
+        );
+    }
+
+    #[test]
+    fn test_get_entity() {
+        let dataset = dataset("a-plus-b.ttl");
+        let response = now(sparql::get_entity(&dataset, "http://example.com/data/c")).unwrap();
+
+        assert_eq!(
+            format!("{:?}", sparql::entity_from_response(response)),
+            "Entity { crs: Crs84, generated_at: None, geometry: None, label: Some(Rc(\"C\")), properties: [], was_attributed_to: [], was_derived_from: [(Some(Rc(\"A\")), Rc(\"http://example.com/data/a\")), (Some(Rc(\"B\")), Rc(\"http://example.com/data/b\"))], was_generated_by: [(Some(Rc(\"Adder-run1\")), Rc(\"http://example.com/activities/add1\"))] }"
+        );
+    }
+
+    #[test]
+    fn test_get_activity() {
+        let dataset = dataset("a-plus-b.ttl");
+        let response = now(sparql::get_activity(
+            &dataset,
+            "http://example.com/activities/add1",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            format!("{:?}", sparql::activity_from_response(response)),
+            "Activity { ended_at: Some(2029-01-01T20:05:19Z), generated: [(Some(Rc(\"C\")), Rc(\"http://example.com/data/c\"))], influenced: [], label: Some(Rc(\"Adder-run1\")), properties: [], started_at: None, used: [(Some(Rc(\"A\")), Rc(\"http://example.com/data/a\")), (Some(Rc(\"B\")), Rc(\"http://example.com/data/b\"))], was_associated_with: [(Some(Rc(\"Add\")), Rc(\"http://example.com/agents/adder\"))], was_influenced_by: [] }"
+        );
+    }
+
+    #[test]
+    fn test_get_spatial_entity() {
+        let dataset = dataset("a-plus-b.ttl");
+        let results = dataset
+            .query(&StoredQuery::new("getSpatialEntity").iri("entity", "http://example.com/data/c"))
+            .unwrap();
+
+        assert_eq!(
+            results["head"]["vars"],
+            json!(["entity", "label", "geojson", "wkt", "generatedAt"])
+        );
+        assert_eq!(
+            results["results"]["bindings"][0]["wkt"]["datatype"],
+            json!("http://www.opengis.net/ont/geosparql#wktLiteral")
+        );
+
+        let response: Response<sparql::SpatialEntityBinding> =
+            serde_json::from_value(results).unwrap();
+        let entities = sparql::spatial_entities_from_response(response);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].0.as_str(), "http://example.com/data/c");
+        // When the activity that generated it ended.
+        assert_eq!(
+            entities[0].1.generated_at.map(|time| time.to_rfc3339()),
+            Some("2029-01-01T20:05:19+00:00".into())
+        );
+    }
+
+    #[test]
+    fn test_get_batched() {
+        let mut dataset = dataset("a-plus-b.ttl");
+        dataset
+            .load_file(format!(
+                "{}/sample_data/auv-paths.ttl",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .unwrap();
+
+        let query = StoredQuery::new("getObjects").iris(
+            "objects",
+            &["http://example.com/data/a", "http://example.com/data/b"],
+        );
+        let url = query.url("http://localhost/db");
+        let (_, query_string) = url.split_once('?').unwrap();
+        assert_eq!(parse_query_string(query_string), Some(query));
+
+        let mut ids = vec![
+            "http://example.com/data/c".to_string(),
+            "http://example.com/data/a".to_string(),
+        ];
+        ids.extend((0..sparql::BATCH_SIZE).map(|i| format!("http://example.com/data/missing{i}")));
+        let groups = now(sparql::get_entities(&dataset, &ids)).unwrap();
+        assert_eq!(groups.len(), ids.len());
+        assert_eq!(
+            format!(
+                "{:?}",
+                groups
+                    .into_iter()
+                    .take(3)
+                    .map(|(id, response)| (id, sparql::entity_from_response(response).label))
+                    .collect::<Vec<_>>()
+            ),
+            "[(Rc(\"http://example.com/data/c\"), Some(Rc(\"C\"))), (Rc(\"http://example.com/data/a\"), Some(Rc(\"A\"))), (Rc(\"http://example.com/data/missing0\"), None)]"
+        );
+
+        let response = now(sparql::get_spatial_entities(
+            &dataset,
+            &[
+                "http://example.com/data/c",
+                "http://example.com/data/pathA",
+                "http://example.com/data/pathB",
+            ],
+        ))
+        .unwrap();
+        assert_eq!(
+            format!(
+                "{:?}",
+                sparql::spatial_entities_from_response(response)
+                    .into_iter()
+                    .map(|(id, entity)| (id, entity.label))
+                    .collect::<Vec<_>>()
+            ),
+            "[(Rc(\"http://example.com/data/c\"), Some(Rc(\"C\"))), (Rc(\"http://example.com/data/pathA\"), Some(Rc(\"Path A\"))), (Rc(\"http://example.com/data/pathB\"), Some(Rc(\"Path B\")))]"
+        );
+    }
+
+    #[test]
+    fn test_spatial_search() {
+        let mut dataset = dataset("a-plus-b.ttl");
+        dataset
+            .load_file(format!(
+                "{}/sample_data/auv-paths.ttl",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .unwrap();
+        let area = Geometry::Rect(geo::Rect::new(
+            geo::coord! { x: 145.75, y: -17.05 },
+            geo::coord! { x: 145.9, y: -16.85 },
+        ));
+        let search = |relation| {
+            now(sparql::search_spatial_entities(
+                &dataset,
+                &area,
+                &crate::crs::Crs::Crs84,
+                relation,
+            ))
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id.to_string())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            search(SpatialRelation::Intersects),
+            [
+                "http://example.com/data/c",
+                "http://example.com/data/pathA",
+                "http://example.com/data/pathB",
+            ]
+        );
+        assert_eq!(
+            search(SpatialRelation::Within),
+            ["http://example.com/data/c"]
+        );
+        // The same area drawn on a map in Web Mercator.
+        let crs = crate::crs::Crs::WebMercator;
+        let projected = now(sparql::search_spatial_entities(
+            &dataset,
+            &crate::crs::Crs::Crs84.reproject(&area, &crs),
+            &crs,
+            SpatialRelation::Within,
+        ))
+        .unwrap();
+        assert_eq!(
+            projected
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            ["http://example.com/data/c"]
+        );
+
+        let query = StoredQuery::new("getSpatialEntitiesWithin").literal(
+            "geometry",
+            "<http://www.opengis.net/def/crs/EPSG/0/4326> POINT (-17 145.8)",
+            sparql::WKT_LITERAL,
+        );
+        let url = query.url("http://localhost/db");
+        assert_eq!(
+            url,
+            "http://localhost/db/query?query=getSpatialEntitiesWithin&$geometry=%22<http://www.opengis.net/def/crs/EPSG/0/4326>%20POINT%20(-17%20145.8)%22%5E%5E<http://www.opengis.net/ont/geosparql%23wktLiteral>"
+        );
+        let (_, query_string) = url.split_once('?').unwrap();
+        assert_eq!(parse_query_string(query_string), Some(query));
+    }
+
+    #[test]
+    fn test_get_dims() {
+        let dataset = dataset("auv-paths.ttl");
+
+        let response = now(sparql::get_dim_desc(
+            &dataset,
+            "http://example.com/data/pathA",
+        ))
+        .unwrap();
+        assert_eq!(
+            format!("{:?}", sparql::dim_desc_from_response(response)),
+            "[DimDesc { name: Rc(\"Bar\"), order: 3, range: 0.0..5.0, target_range: Some(2.0..4.0), inverted: false }, DimDesc { name: Rc(\"Dang\"), order: 2, range: 0.0..5.0, target_range: Some(0.0..5.0), inverted: true }, DimDesc { name: Rc(\"Foo\"), order: 1, range: 0.0..5.0, target_range: Some(0.0..5.0), inverted: false }]"
+        );
+
+        let response = now(sparql::get_dim_values(
+            &dataset,
+            "http://example.com/data/pathB",
+        ))
+        .unwrap();
+        assert_eq!(
+            format!("{:?}", sparql::dim_values_from_response(response)),
+            "[DimValue { d: Rc(\"Foo\"), v: 2.0 }, DimValue { d: Rc(\"Bar\"), v: 4.0 }, DimValue { d: Rc(\"Dang\"), v: 4.0 }]"
+        );
+    }
+
+    #[test]
+    fn test_unknown_query() {
+        let dataset = dataset("a-plus-b.ttl");
+
+        assert_eq!(
+            now(sparql::run_stored_query::<_, sparql::ObjectBinding>(
+                &dataset,
+                &StoredQuery::new("getNothing")
+            ))
+            .unwrap_err()
+            .to_string(),
+            "unknown stored query getNothing"
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Just enough of a Turtle parser to load our sample data.
+//!
+//! Prefixes, base IRIs, blank node property lists and all of the literal forms are
+//! supported. Collections are not.
+
+use std::{collections::HashMap, fmt};
+
+use super::{Term, Triple, RDF_TYPE, XSD};
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a document into triples. Labelled blank nodes are prefixed with `load`, the number of
+/// the document, so that those of different documents stay apart, while those of blank node
+/// property lists are numbered from `blank_nodes`.
+pub(super) fn parse(
+    input: &str,
+    load: usize,
+    blank_nodes: &mut usize,
+    triples: &mut Vec<Triple>,
+) -> Result<(), ParseError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+        base: None,
+        prefixes: HashMap::new(),
+        load,
+        blank_nodes,
+        triples,
+    };
+    parser.document()
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    base: Option<String>,
+    prefixes: HashMap<String, String>,
+    load: usize,
+    blank_nodes: &'a mut usize,
+    triples: &'a mut Vec<Triple>,
+}
+
+impl Parser<'_> {
+    fn document(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                None => return Ok(()),
+                Some('@') => {
+                    self.pos += 1;
+                    let keyword = self.name();
+                    self.directive(&keyword)?;
+                    self.skip_ws();
+                    self.expect('.')?;
+                }
+                Some('P' | 'p' | 'B' | 'b') if self.at_sparql_directive() => {
+                    let keyword = self.name().to_lowercase();
+                    self.directive(&keyword)?;
+                }
+                _ => {
+                    self.triples_statement()?;
+                    self.skip_ws();
+                    self.expect('.')?;
+                }
+            }
+        }
+    }
+
+    fn at_sparql_directive(&self) -> bool {
+        ["prefix", "base"].iter().any(|keyword| {
+            let end = self.pos + keyword.len();
+            end < self.chars.len()
+                && self.chars[self.pos..end]
+                    .iter()
+                    .collect::<String>()
+                    .eq_ignore_ascii_case(keyword)
+                && self.chars[end].is_whitespace()
+        })
+    }
+
+    fn directive(&mut self, keyword: &str) -> Result<(), ParseError> {
+        self.skip_ws();
+        match keyword {
+            "prefix" => {
+                let prefix = self.name();
+                self.expect(':')?;
+                self.skip_ws();
+                let iri = self.iri_ref()?;
+                self.prefixes.insert(prefix, iri);
+                Ok(())
+            }
+            "base" => {
+                self.base = Some(self.iri_ref()?);
+                Ok(())
+            }
+            _ => Err(self.error(format!("unknown directive @{keyword}"))),
+        }
+    }
+
+    fn triples_statement(&mut self) -> Result<(), ParseError> {
+        if self.peek() == Some('[') {
+            let subject = self.blank_node_property_list()?;
+            self.skip_ws();
+            if self.peek() == Some('.') {
+                return Ok(());
+            }
+            self.predicate_object_list(&subject)
+        } else {
+            let subject = self.subject()?;
+            self.skip_ws();
+            self.predicate_object_list(&subject)
+        }
+    }
+
+    fn subject(&mut self) -> Result<Term, ParseError> {
+        match self.peek() {
+            Some('_') => self.blank_node_label(),
+            Some('(') => Err(self.error("collections are not supported".into())),
+            _ => self.iri().map(Term::Iri),
+        }
+    }
+
+    fn predicate_object_list(&mut self, subject: &Term) -> Result<(), ParseError> {
+        loop {
+            self.skip_ws();
+            let predicate = self.verb()?;
+            loop {
+                self.skip_ws();
+                let object = self.object()?;
+                self.triples.push(Triple {
+                    s: subject.clone(),
+                    p: predicate.clone(),
+                    o: object,
+                });
+                self.skip_ws();
+                if self.peek() == Some(',') {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            // Any number of `;` may follow, and the last one need not be followed by a verb.
+            let mut more = false;
+            while self.peek() == Some(';') {
+                self.pos += 1;
+                self.skip_ws();
+                more = true;
+            }
+            if !more || matches!(self.peek(), Some('.' | ']') | None) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn verb(&mut self) -> Result<String, ParseError> {
+        if self.peek() == Some('a')
+            && self
+                .chars
+                .get(self.pos + 1)
+                .is_none_or(|c| c.is_whitespace() || *c == '<')
+        {
+            self.pos += 1;
+            Ok(RDF_TYPE.into())
+        } else {
+            self.iri()
+        }
+    }
+
+    fn object(&mut self) -> Result<Term, ParseError> {
+        match self.peek() {
+            Some('<') => self.iri_ref().map(Term::Iri),
+            Some('_') => self.blank_node_label(),
+            Some('[') => self.blank_node_property_list(),
+            Some('(') => Err(self.error("collections are not supported".into())),
+            Some('"' | '\'') => self.rdf_literal(),
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' || c == '.' => {
+                self.numeric_literal()
+            }
+            Some(_) if self.at_keyword("true") || self.at_keyword("false") => {
+                let value = self.name();
+                Ok(Term::Literal {
+                    value,
+                    datatype: Some(format!("{XSD}boolean")),
+                    language: None,
+                })
+            }
+            Some(_) => self.prefixed_name().map(Term::Iri),
+            None => Err(self.error("expected an object".into())),
+        }
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        let end = self.pos + keyword.len();
+        end <= self.chars.len()
+            && self.chars[self.pos..end]
+                .iter()
+                .copied()
+                .eq(keyword.chars())
+            && self
+                .chars
+                .get(end)
+                .is_none_or(|c| !(c.is_alphanumeric() || *c == ':' || *c == '_'))
+    }
+
+    fn blank_node_label(&mut self) -> Result<Term, ParseError> {
+        self.expect('_')?;
+        self.expect(':')?;
+        let label = self.name();
+        if label.is_empty() {
+            return Err(self.error("expected a blank node label".into()));
+        }
+        Ok(Term::BlankNode(format!("{}-{label}", self.load)))
+    }
+
+    fn blank_node_property_list(&mut self) -> Result<Term, ParseError> {
+        self.expect('[')?;
+        *self.blank_nodes += 1;
+        let node = Term::BlankNode(format!("genid-{}", self.blank_nodes));
+        self.skip_ws();
+        if self.peek() != Some(']') {
+            self.predicate_object_list(&node)?;
+            self.skip_ws();
+        }
+        self.expect(']')?;
+        Ok(node)
+    }
+
+    fn iri(&mut self) -> Result<String, ParseError> {
+        if self.peek() == Some('<') {
+            self.iri_ref()
+        } else {
+            self.prefixed_name()
+        }
+    }
+
+    fn iri_ref(&mut self) -> Result<String, ParseError> {
+        self.expect('<')?;
+        let mut iri = String::new();
+        loop {
+            match self.next() {
+                Some('>') => break,
+                Some('\\') => iri.push(self.unicode_escape()?),
+                Some(c) if !c.is_whitespace() => iri.push(c),
+                _ => return Err(self.error("unterminated IRI".into())),
+            }
+        }
+        match &self.base {
+            Some(base) if !iri.contains(':') => Ok(format!("{base}{iri}")),
+            _ => Ok(iri),
+        }
+    }
+
+    fn prefixed_name(&mut self) -> Result<String, ParseError> {
+        let prefix = self.name();
+        self.expect(':')?;
+        let mut local = String::new();
+        while let Some(c) = self.peek() {
+            // A local name may contain but not end with a `.`.
+            let is_inner_dot = c == '.'
+                && self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':'));
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '%') || is_inner_dot {
+                local.push(c);
+                self.pos += 1;
+            } else if c == '\\' {
+                self.pos += 1;
+                local.extend(self.next());
+            } else {
+                break;
+            }
+        }
+        self.prefixes
+            .get(&prefix)
+            .map(|namespace| format!("{namespace}{local}"))
+            .ok_or_else(|| self.error(format!("undeclared prefix {prefix}:")))
+    }
+
+    fn rdf_literal(&mut self) -> Result<Term, ParseError> {
+        let value = self.string()?;
+        let mut datatype = None;
+        let mut language = None;
+        if self.peek() == Some('@') {
+            self.pos += 1;
+            language = Some(self.name());
+        } else if self.peek() == Some('^') {
+            self.expect('^')?;
+            self.expect('^')?;
+            datatype = Some(self.iri()?);
+        }
+        Ok(Term::Literal {
+            value,
+            datatype,
+            language,
+        })
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let quote = self.next().unwrap_or('"');
+        let long = self.chars.get(self.pos) == Some(&quote)
+            && self.chars.get(self.pos + 1) == Some(&quote);
+        if long {
+            self.pos += 2;
+        }
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == quote => {
+                    if !long {
+                        return Ok(value);
+                    }
+                    if self.chars.get(self.pos) == Some(&quote)
+                        && self.chars.get(self.pos + 1) == Some(&quote)
+                        && self.chars.get(self.pos + 2) != Some(&quote)
+                    {
+                        self.pos += 2;
+                        return Ok(value);
+                    }
+                    value.push(c);
+                }
+                Some('\\') => value.push(match self.next() {
+                    Some('t') => '\t',
+                    Some('b') => '\u{8}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('f') => '\u{c}',
+                    Some('u' | 'U') => {
+                        self.pos -= 1;
+                        self.unicode_escape()?
+                    }
+                    Some(c) => c,
+                    None => return Err(self.error("unterminated string".into())),
+                }),
+                Some('\n') if !long => return Err(self.error("unterminated string".into())),
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c)
+                }
+                None => return Err(self.error("unterminated string".into())),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let len = match self.next() {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("invalid escape".into())),
+        };
+        let end = (self.pos + len).min(self.chars.len());
+        let hex = self.chars[self.pos..end].iter().collect::<String>();
+        self.pos = end;
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("invalid unicode escape {hex}")))
+    }
+
+    fn numeric_literal(&mut self) -> Result<Term, ParseError> {
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            let is_trailing_dot = c == '.'
+                && !self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_some_and(|c| c.is_ascii_digit());
+            if (c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')) && !is_trailing_dot
+            {
+                value.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let datatype = if value.contains(['e', 'E']) {
+            "double"
+        } else if value.contains('.') {
+            "decimal"
+        } else {
+            "integer"
+        };
+        if value.parse::<f64>().is_err() {
+            return Err(self.error(format!("invalid number {value}")));
+        }
+        Ok(Term::Literal {
+            value,
+            datatype: Some(format!("{XSD}{datatype}")),
+            language: None,
+        })
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || matches!(c, '_' | '-') {
+                name.push(c);
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        name
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while !matches!(self.peek(), Some('\n') | None) {
+                    self.pos += 1;
+                }
+            } else if c.is_whitespace() {
+                if c == '\n' {
+                    self.line += 1;
+                }
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!(
+                "expected '{expected}', found {}",
+                self.peek()
+                    .map_or("end of input".into(), |c| format!("'{c}'"))
+            )))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            message,
+        }
+    }
+}
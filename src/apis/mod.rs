@@ -1,3 +1,4 @@
 //! A place where we can organise our API requests and responses.
 
+pub mod local;
 pub mod sparql;
@@ -0,0 +1,698 @@
+//! An in-memory cache of stored query results, optionally backed by a persistent [`Store`].
+//!
+//! Results are keyed by endpoint, stored query and parameters. Entries expire after a
+//! configurable time to live, the least recently used entry is evicted once the cache is full,
+//! and concurrent requests for the same key share a single underlying fetch.
+//!
+//! Expired results are kept so that they can still be served, flagged as stale, when the
+//! cache is working offline or the endpoint cannot be reached.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use implicit_clone::ImplicitClone;
+
+use super::{http, percent_encode, Body, Persisted, Store, StoredQuery, Transport};
+
+type Key = (String, StoredQuery);
+
+type Fetched = (Body, DateTime<Utc>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheConfig {
+    /// How long a result is served from the cache before it is fetched again.
+    pub ttl: Duration,
+    /// The maximum number of results held in memory.
+    pub capacity: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::minutes(5),
+            capacity: 256,
+        }
+    }
+}
+
+struct Entry {
+    text: Rc<str>,
+    fetched_at: DateTime<Utc>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct InFlight {
+    result: Option<Result<Body, String>>,
+    /// Whether the fetch was dropped or cancelled by the caller that started it, leaving those
+    /// waiting on it to fetch for themselves.
+    abandoned: bool,
+    wakers: Vec<Waker>,
+}
+
+struct State {
+    config: CacheConfig,
+    entries: HashMap<Key, Entry>,
+    in_flight: HashMap<Key, Rc<RefCell<InFlight>>>,
+    offline: bool,
+    uses: u64,
+}
+
+impl State {
+    fn get(&mut self, key: &Key, allow_stale: bool) -> Option<Body> {
+        let ttl = self.config.ttl;
+        let entry = self.entries.get_mut(key)?;
+        let stale = (Utc::now() - entry.fetched_at >= ttl).then_some(entry.fetched_at);
+        if stale.is_some() && !allow_stale {
+            return None;
+        }
+        self.uses += 1;
+        entry.last_used = self.uses;
+        Some(Body {
+            text: entry.text.clone(),
+            stale,
+        })
+    }
+
+    fn insert(&mut self, key: Key, text: Rc<str>, fetched_at: DateTime<Utc>) {
+        if self.config.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.config.capacity && !self.entries.contains_key(&key) {
+            let Some(lru) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&lru);
+        }
+        self.uses += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                text,
+                fetched_at,
+                last_used: self.uses,
+            },
+        );
+    }
+}
+
+fn store_key((endpoint, query): &Key) -> String {
+    query.url(endpoint)
+}
+
+fn mentions(key: &str, iri: &str) -> bool {
+    key.contains(&percent_encode(&format!("<{iri}>")))
+}
+
+/// A shared cache of stored query results.
+///
+/// Clones share the same underlying storage, so a cache can be created once by the application
+/// and handed to whichever components need it.
+#[derive(Clone)]
+pub struct Cache {
+    state: Rc<RefCell<State>>,
+    store: Option<Rc<dyn Store>>,
+}
+
+impl ImplicitClone for Cache {}
+
+impl PartialEq for Cache {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new(CacheConfig::default())
+    }
+}
+
+impl Cache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(State {
+                config,
+                entries: HashMap::new(),
+                in_flight: HashMap::new(),
+                offline: false,
+                uses: 0,
+            })),
+            store: None,
+        }
+    }
+
+    /// Creates a cache whose results are also written to, and read back from, a persistent
+    /// store such as an [`IndexedDbStore`](super::IndexedDbStore).
+    pub fn persistent(config: CacheConfig, store: impl Store + 'static) -> Self {
+        Self {
+            store: Some(Rc::new(store)),
+            ..Self::new(config)
+        }
+    }
+
+    /// Wraps a transport so that its results are served from, and stored in, this cache.
+    pub fn with<'a, T: Transport + ?Sized>(&'a self, transport: &'a T) -> Cached<'a, T> {
+        Cached {
+            cache: self,
+            transport,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.borrow().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.state.borrow().offline
+    }
+
+    /// When working offline, only cached results are served, however old they are, and
+    /// nothing is fetched.
+    pub fn set_offline(&self, offline: bool) {
+        self.state.borrow_mut().offline = offline;
+    }
+
+    /// Returns a result held in memory that has not yet expired, without fetching it.
+    pub fn get(&self, endpoint: &str, query: &StoredQuery) -> Option<Rc<str>> {
+        self.state
+            .borrow_mut()
+            .get(&(endpoint.to_string(), query.clone()), false)
+            .map(|body| body.text)
+    }
+
+    pub async fn fetch<T: Transport + ?Sized>(
+        &self,
+        transport: &T,
+        query: &StoredQuery,
+    ) -> Result<Body, gloo_net::Error> {
+        let key = (transport.endpoint().to_string(), query.clone());
+
+        let in_flight = loop {
+            let (in_flight, is_leader) = {
+                let mut state = self.state.borrow_mut();
+                let offline = state.offline;
+                if let Some(body) = state.get(&key, offline) {
+                    return Ok(body);
+                }
+                match state.in_flight.get(&key) {
+                    Some(in_flight) => (in_flight.clone(), false),
+                    None => {
+                        let in_flight = Rc::new(RefCell::new(InFlight::default()));
+                        state.in_flight.insert(key.clone(), in_flight.clone());
+                        (in_flight, true)
+                    }
+                }
+            };
+            if is_leader {
+                break in_flight;
+            }
+            if let Some(result) = WaitFor(in_flight).await {
+                return result;
+            }
+        };
+
+        let mut guard = InFlightGuard {
+            cache: self,
+            key: &key,
+            in_flight: &in_flight,
+            result: None,
+        };
+        let result = self.fetch_uncached(transport, &key).await;
+        // A cancellation is only for the caller whose transport was aborted.
+        guard.result = match &result {
+            Ok(fetched) => Some(Ok(fetched.clone())),
+            Err(e) if http::is_cancelled(e) => None,
+            Err(e) => Some(Err(e.to_string())),
+        };
+        result.map(|(body, _)| body)
+    }
+
+    async fn fetch_uncached<T: Transport + ?Sized>(
+        &self,
+        transport: &T,
+        key: &Key,
+    ) -> Result<Fetched, gloo_net::Error> {
+        let (ttl, offline) = {
+            let state = self.state.borrow();
+            (state.config.ttl, state.offline)
+        };
+
+        let persisted = match &self.store {
+            Some(store) => store.load(&store_key(key)).await,
+            None => None,
+        };
+        let stale = persisted.map(|persisted| {
+            let stale = Utc::now() - persisted.fetched_at >= ttl;
+            let body = Body {
+                text: persisted.body.into(),
+                stale: stale.then_some(persisted.fetched_at),
+            };
+            (body, persisted.fetched_at)
+        });
+        let stale = match stale {
+            Some(fetched) if fetched.0.stale.is_none() => return Ok(fetched),
+            stale => stale.or_else(|| {
+                let mut state = self.state.borrow_mut();
+                let body = state.get(key, true)?;
+                let fetched_at = body.stale?;
+                Some((body, fetched_at))
+            }),
+        };
+
+        if offline {
+            return stale.ok_or_else(|| {
+                gloo_net::Error::GlooError(format!("{} is not available offline", store_key(key)))
+            });
+        }
+
+        match transport.fetch(&key.1).await {
+            Ok(body) => {
+                let fetched_at = body.stale.unwrap_or_else(Utc::now);
+                if let (Some(store), None) = (&self.store, body.stale) {
+                    let persisted = Persisted {
+                        fetched_at,
+                        body: body.text.to_string(),
+                    };
+                    store.save(&store_key(key), &persisted).await;
+                }
+                Ok((body, fetched_at))
+            }
+            // Better stale results than none at all.
+            Err(e) => stale.ok_or(e),
+        }
+    }
+
+    /// Removes a single result, so that it is fetched again on next use.
+    pub async fn invalidate(&self, endpoint: &str, query: &StoredQuery) {
+        let key = (endpoint.to_string(), query.clone());
+        {
+            let mut state = self.state.borrow_mut();
+            state.entries.remove(&key);
+            state.in_flight.remove(&key);
+        }
+        if let Some(store) = &self.store {
+            store.remove(&store_key(&key)).await;
+        }
+    }
+
+    /// Removes every result of a query that was passed the given IRI, e.g. after it has been
+    /// edited.
+    pub async fn invalidate_iri(&self, iri: &str) {
+        {
+            let mut state = self.state.borrow_mut();
+            state
+                .entries
+                .retain(|key, _| !mentions(&store_key(key), iri));
+            state
+                .in_flight
+                .retain(|key, _| !mentions(&store_key(key), iri));
+        }
+        if let Some(store) = &self.store {
+            for key in store.keys().await {
+                if mentions(&key, iri) {
+                    store.remove(&key).await;
+                }
+            }
+        }
+    }
+
+    pub async fn clear(&self) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.entries.clear();
+            state.in_flight.clear();
+        }
+        if let Some(store) = &self.store {
+            store.clear().await;
+        }
+    }
+}
+
+/// Completes an in-flight fetch, including when the fetching future is dropped before it
+/// finishes, so that requests waiting on it are never left hanging. Without a result, the fetch
+/// is abandoned and the first of those waiting starts it again.
+struct InFlightGuard<'a> {
+    cache: &'a Cache,
+    key: &'a Key,
+    in_flight: &'a Rc<RefCell<InFlight>>,
+    result: Option<Result<Fetched, String>>,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        let result = self.result.take();
+
+        let mut state = self.cache.state.borrow_mut();
+        // Only record the result if the cache was not invalidated while we were fetching.
+        let current = state
+            .in_flight
+            .get(self.key)
+            .is_some_and(|in_flight| Rc::ptr_eq(in_flight, self.in_flight));
+        if current {
+            state.in_flight.remove(self.key);
+            if let Some(Ok((body, fetched_at))) = &result {
+                state.insert(self.key.clone(), body.text.clone(), *fetched_at);
+            }
+        }
+        drop(state);
+
+        let mut in_flight = self.in_flight.borrow_mut();
+        match result {
+            Some(result) => in_flight.result = Some(result.map(|(body, _)| body)),
+            None => in_flight.abandoned = true,
+        }
+        for waker in in_flight.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// Waits for an in-flight fetch, with `None` if it was abandoned.
+struct WaitFor(Rc<RefCell<InFlight>>);
+
+impl Future for WaitFor {
+    type Output = Option<Result<Body, gloo_net::Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut in_flight = self.0.borrow_mut();
+        match &in_flight.result {
+            Some(result) => Poll::Ready(Some(result.clone().map_err(gloo_net::Error::GlooError))),
+            None if in_flight.abandoned => Poll::Ready(None),
+            None => {
+                in_flight.wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A transport whose results are served through a [`Cache`].
+pub struct Cached<'a, T: ?Sized> {
+    cache: &'a Cache,
+    transport: &'a T,
+}
+
+impl<T: Transport + ?Sized> Transport for Cached<'_, T> {
+    fn endpoint(&self) -> &str {
+        self.transport.endpoint()
+    }
+
+    async fn fetch(&self, query: &StoredQuery) -> Result<Body, gloo_net::Error> {
+        self.cache.fetch(self.transport, query).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::apis::{
+        local::Dataset,
+        sparql::{self, block_on, FileStore},
+    };
+
+    /// A transport that counts its fetches and makes each one wait for a single poll.
+    struct Counting {
+        dataset: Dataset,
+        fetches: Cell<usize>,
+        reachable: Cell<bool>,
+        /// Whether fetches fail as if aborted through an [`AbortHandle`](sparql::AbortHandle).
+        aborted: Cell<bool>,
+    }
+
+    impl Transport for Counting {
+        fn endpoint(&self) -> &str {
+            "counting"
+        }
+
+        async fn fetch(&self, query: &StoredQuery) -> Result<Body, gloo_net::Error> {
+            self.fetches.set(self.fetches.get() + 1);
+            YieldOnce(false).await;
+            if self.aborted.get() {
+                return Err(http::cancelled());
+            }
+            if !self.reachable.get() {
+                return Err(gloo_net::Error::GlooError("unreachable".into()));
+            }
+            self.dataset.fetch(query).await
+        }
+    }
+
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    fn counting() -> Counting {
+        let mut dataset = Dataset::new();
+        dataset
+            .load_file(format!(
+                "{}/sample_data/a-plus-b.ttl",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .unwrap();
+        Counting {
+            dataset,
+            fetches: Cell::new(0),
+            reachable: Cell::new(true),
+            aborted: Cell::new(false),
+        }
+    }
+
+    fn label(transport: &impl Transport, iri: &str) -> Option<String> {
+        block_on(sparql::get_entity(transport, iri))
+            .map(sparql::entity_from_response)
+            .unwrap()
+            .label
+            .map(|label| label.to_string())
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let transport = counting();
+        let cache = Cache::default();
+
+        assert_eq!(
+            label(&cache.with(&transport), "http://example.com/data/c").as_deref(),
+            Some("C")
+        );
+        assert_eq!(
+            label(&cache.with(&transport), "http://example.com/data/c").as_deref(),
+            Some("C")
+        );
+        assert_eq!(transport.fetches.get(), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_ttl() {
+        let transport = counting();
+        let cache = Cache::new(CacheConfig {
+            ttl: Duration::zero(),
+            ..CacheConfig::default()
+        });
+
+        label(&cache.with(&transport), "http://example.com/data/c");
+        label(&cache.with(&transport), "http://example.com/data/c");
+        assert_eq!(transport.fetches.get(), 2);
+    }
+
+    #[test]
+    fn test_cache_lru() {
+        let transport = counting();
+        let cache = Cache::new(CacheConfig {
+            capacity: 2,
+            ..CacheConfig::default()
+        });
+
+        label(&cache.with(&transport), "http://example.com/data/a");
+        label(&cache.with(&transport), "http://example.com/data/b");
+        label(&cache.with(&transport), "http://example.com/data/a");
+        label(&cache.with(&transport), "http://example.com/data/c");
+        assert_eq!(transport.fetches.get(), 3);
+        assert_eq!(cache.len(), 2);
+
+        // b was the least recently used, so it was evicted to make room for c.
+        label(&cache.with(&transport), "http://example.com/data/a");
+        label(&cache.with(&transport), "http://example.com/data/b");
+        assert_eq!(transport.fetches.get(), 4);
+    }
+
+    #[test]
+    fn test_cache_invalidate() {
+        let transport = counting();
+        let cache = Cache::default();
+
+        label(&cache.with(&transport), "http://example.com/data/a");
+        label(&cache.with(&transport), "http://example.com/data/b");
+        block_on(cache.invalidate_iri("http://example.com/data/a"));
+        assert_eq!(cache.len(), 1);
+
+        label(&cache.with(&transport), "http://example.com/data/a");
+        assert_eq!(transport.fetches.get(), 3);
+
+        block_on(cache.invalidate(
+            "counting",
+            &StoredQuery::new("getEntity").iri("entity", "http://example.com/data/b"),
+        ));
+        assert_eq!(cache.len(), 1);
+        block_on(cache.clear());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_coalesces_in_flight_requests() {
+        let transport = counting();
+        let cache = Cache::default();
+        let cached = cache.with(&transport);
+
+        let (a, b) = block_on(async {
+            let mut a = pin!(sparql::get_entity(&cached, "http://example.com/data/c"));
+            let mut b = pin!(sparql::get_entity(&cached, "http://example.com/data/c"));
+            let (mut a_result, mut b_result) = (None, None);
+            std::future::poll_fn(|cx| {
+                if a_result.is_none() {
+                    if let Poll::Ready(r) = a.as_mut().poll(cx) {
+                        a_result = Some(r);
+                    }
+                }
+                if b_result.is_none() {
+                    if let Poll::Ready(r) = b.as_mut().poll(cx) {
+                        b_result = Some(r);
+                    }
+                }
+                if a_result.is_some() && b_result.is_some() {
+                    Poll::Ready((a_result.take().unwrap(), b_result.take().unwrap()))
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await
+        });
+
+        assert_eq!(
+            sparql::entity_from_response(a.unwrap()),
+            sparql::entity_from_response(b.unwrap())
+        );
+        assert_eq!(transport.fetches.get(), 1);
+    }
+
+    #[test]
+    fn test_cache_refetches_abandoned_requests() {
+        let (leader, waiter) = (counting(), counting());
+        let cache = Cache::default();
+        let iri = "http://example.com/data/c";
+        fn pending<F: Future>(future: Pin<&mut F>) -> bool {
+            future
+                .poll(&mut Context::from_waker(Waker::noop()))
+                .is_pending()
+        }
+
+        // The caller that started the fetch goes away before it finishes.
+        let (leading, waiting) = (cache.with(&leader), cache.with(&waiter));
+        let mut first = Box::pin(sparql::get_entity(&leading, iri));
+        let mut second = Box::pin(sparql::get_entity(&waiting, iri));
+        assert!(pending(first.as_mut()));
+        assert!(pending(second.as_mut()));
+        drop(first);
+        assert!(block_on(second).is_ok());
+        assert_eq!((leader.fetches.get(), waiter.fetches.get()), (1, 1));
+
+        // The caller that started the fetch aborts it.
+        block_on(cache.clear());
+        leader.aborted.set(true);
+        let mut first = Box::pin(sparql::get_entity(&leading, iri));
+        let mut second = Box::pin(sparql::get_entity(&waiting, iri));
+        assert!(pending(first.as_mut()));
+        assert!(pending(second.as_mut()));
+        assert!(block_on(first).is_err_and(|e| sparql::is_cancelled(&e)));
+        assert!(block_on(second).is_ok());
+        assert_eq!((leader.fetches.get(), waiter.fetches.get()), (2, 2));
+    }
+
+    fn stale(transport: &impl Transport, iri: &str) -> Result<bool, gloo_net::Error> {
+        block_on(sparql::get_entity(transport, iri)).map(|response| response.stale.is_some())
+    }
+
+    #[test]
+    fn test_cache_serves_stale_results_when_unreachable() {
+        let transport = counting();
+        let cache = Cache::new(CacheConfig {
+            ttl: Duration::zero(),
+            ..CacheConfig::default()
+        });
+
+        assert!(!stale(&cache.with(&transport), "http://example.com/data/c").unwrap());
+        transport.reachable.set(false);
+        assert!(stale(&cache.with(&transport), "http://example.com/data/c").unwrap());
+        assert!(stale(&cache.with(&transport), "http://example.com/data/a").is_err());
+    }
+
+    #[test]
+    fn test_persistent_cache_offline() {
+        let dir = std::env::temp_dir().join(format!("kg-ui-toolkit-cache-{}", std::process::id()));
+        let transport = counting();
+        let config = CacheConfig {
+            ttl: Duration::zero(),
+            ..CacheConfig::default()
+        };
+
+        let cache = Cache::persistent(config.clone(), FileStore::new(&dir).unwrap());
+        assert!(!stale(&cache.with(&transport), "http://example.com/data/c").unwrap());
+        assert_eq!(transport.fetches.get(), 1);
+
+        // A new session, e.g. after a reload, working offline.
+        let cache = Cache::persistent(config, FileStore::new(&dir).unwrap());
+        cache.set_offline(true);
+        assert!(stale(&cache.with(&transport), "http://example.com/data/c").unwrap());
+        assert_eq!(
+            label(&cache.with(&transport), "http://example.com/data/c").as_deref(),
+            Some("C")
+        );
+        assert_eq!(
+            stale(&cache.with(&transport), "http://example.com/data/a")
+                .unwrap_err()
+                .to_string(),
+            "counting/query?query=getEntity&$entity=<http://example.com/data/a> is not available offline"
+        );
+        assert_eq!(transport.fetches.get(), 1);
+
+        block_on(cache.invalidate_iri("http://example.com/data/c"));
+        assert!(stale(&cache.with(&transport), "http://example.com/data/c").is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,367 @@
+//! Running stored queries over HTTP with timeouts, retries and cancellation.
+//!
+//! Each attempt is given its own `AbortController`, so that a timed out attempt is abandoned by
+//! the browser too, and an [`AbortHandle`] aborts whichever attempt is running when it is used.
+
+use std::{
+    cell::{Cell, RefCell},
+    future::{poll_fn, Future},
+    pin::pin,
+    rc::Rc,
+    task::Poll,
+    time::Duration,
+};
+
+use gloo_net::http::Request;
+use implicit_clone::ImplicitClone;
+use web_sys::AbortController;
+use yew::platform::time::sleep;
+
+use super::{Body, StoredQuery, Transport};
+
+const CANCELLED: &str = "The request was cancelled";
+
+/// The error of a request cancelled through its [`AbortHandle`].
+pub(crate) fn cancelled() -> gloo_net::Error {
+    gloo_net::Error::GlooError(CANCELLED.into())
+}
+
+/// Whether a request failed because it was cancelled through its [`AbortHandle`], in which case
+/// there is usually nothing to report.
+pub fn is_cancelled(error: &gloo_net::Error) -> bool {
+    matches!(error, gloo_net::Error::GlooError(message) if message == CANCELLED)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestPolicy {
+    /// How long to wait for each attempt before abandoning it, if at all.
+    pub timeout: Option<Duration>,
+    /// How many times to retry after a timeout or a 429 or 5xx response.
+    pub retries: u32,
+    /// The delay before the first retry, doubled for each retry after it.
+    pub backoff: Duration,
+    /// The longest delay between retries, including one asked for with `Retry-After`.
+    pub max_backoff: Duration,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+/// The outcome of a single attempt at a request.
+pub(crate) enum Attempt {
+    Done(Result<Body, gloo_net::Error>),
+    /// The attempt failed in a way that may succeed later, possibly after a delay asked for by
+    /// the server.
+    Retry(gloo_net::Error, Option<Duration>),
+}
+
+impl RequestPolicy {
+    /// The delay before the given retry, counting from zero.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    pub(crate) async fn run<F: Future<Output = Attempt>>(
+        &self,
+        mut attempt: impl FnMut() -> F,
+    ) -> Result<Body, gloo_net::Error> {
+        let mut retry = 0;
+        loop {
+            match attempt().await {
+                Attempt::Done(result) => return result,
+                Attempt::Retry(e, _) if retry >= self.retries => return Err(e),
+                Attempt::Retry(e, retry_after) => {
+                    let delay = retry_after
+                        .unwrap_or_else(|| self.delay(retry))
+                        .min(self.max_backoff);
+                    log::warn!("{e}, retrying in {}ms", delay.as_millis());
+                    if !delay.is_zero() {
+                        sleep(delay).await;
+                    }
+                    retry += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Waits for a future for at most the given time, if any.
+async fn timeout<F: Future>(duration: Option<Duration>, future: F) -> Option<F::Output> {
+    let Some(duration) = duration else {
+        return Some(future.await);
+    };
+    let mut future = pin!(future);
+    let mut timer = pin!(sleep(duration));
+    poll_fn(|cx| match future.as_mut().poll(cx) {
+        Poll::Ready(output) => Poll::Ready(Some(output)),
+        Poll::Pending => timer.as_mut().poll(cx).map(|()| None),
+    })
+    .await
+}
+
+#[derive(Default)]
+struct AbortState {
+    aborted: Cell<bool>,
+    controllers: RefCell<Vec<AbortController>>,
+}
+
+/// Cancels the requests it is given to, e.g. when a view is about to show something else.
+///
+/// Clones share their state, so a component can keep one clone and hand another to the request.
+/// Once aborted, a handle stays aborted; use a new handle for the next request.
+#[derive(Clone, Default)]
+pub struct AbortHandle(Rc<AbortState>);
+
+impl ImplicitClone for AbortHandle {}
+
+impl PartialEq for AbortHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl AbortHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn abort(&self) {
+        self.0.aborted.set(true);
+        for controller in self.0.controllers.take() {
+            controller.abort();
+        }
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.0.aborted.get()
+    }
+}
+
+/// Removes an attempt's controller from its handle once the attempt is over.
+struct Registration<'a> {
+    handle: &'a AbortHandle,
+    controller: AbortController,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.handle
+            .0
+            .controllers
+            .borrow_mut()
+            .retain(|controller| controller != &self.controller);
+    }
+}
+
+/// Queries a Stardog database at the given API path, following a [`RequestPolicy`].
+///
+/// A bare `str` transport does the same with the default policy and no [`AbortHandle`].
+pub struct Http<'a> {
+    api_path: &'a str,
+    policy: RequestPolicy,
+    abort: Option<AbortHandle>,
+}
+
+impl<'a> Http<'a> {
+    pub fn new(api_path: &'a str) -> Self {
+        Self {
+            api_path,
+            policy: RequestPolicy::default(),
+            abort: None,
+        }
+    }
+
+    pub fn policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Makes requests fail with an error for which [`is_cancelled`] holds once `handle` is
+    /// aborted, even if a response has already arrived. Through a [`Cache`](super::Cache), only
+    /// the caller using this transport sees the cancellation; others waiting on the same request
+    /// fetch it themselves.
+    pub fn abort_with(mut self, handle: &AbortHandle) -> Self {
+        self.abort = Some(handle.clone());
+        self
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.abort.as_ref().is_some_and(AbortHandle::is_aborted)
+    }
+
+    async fn attempt(&self, url: &str) -> Attempt {
+        if self.is_aborted() {
+            return Attempt::Done(Err(cancelled()));
+        }
+
+        let controller = match AbortController::new() {
+            Ok(controller) => controller,
+            Err(e) => return Attempt::Done(Err(gloo_net::Error::GlooError(format!("{e:?}")))),
+        };
+        let _registration = self.abort.as_ref().map(|handle| {
+            handle.0.controllers.borrow_mut().push(controller.clone());
+            Registration {
+                handle,
+                controller: controller.clone(),
+            }
+        });
+
+        let response = timeout(self.policy.timeout, async {
+            let response = Request::get(url)
+                .header("Accept", "application/sparql-results+json")
+                .abort_signal(Some(&controller.signal()))
+                .send()
+                .await?;
+            let text = response.text().await?;
+            Ok::<_, gloo_net::Error>((response, text))
+        })
+        .await;
+
+        if self.is_aborted() {
+            return Attempt::Done(Err(cancelled()));
+        }
+        let Some(response) = response else {
+            controller.abort();
+            return Attempt::Retry(gloo_net::Error::GlooError(format!("{url} timed out")), None);
+        };
+
+        match response {
+            Ok((response, text)) if response.ok() => Attempt::Done(Ok(Body::from(text))),
+            Ok((response, _)) => {
+                let status = response.status();
+                let e = gloo_net::Error::GlooError(format!(
+                    "{url} returned {status} {}",
+                    response.status_text()
+                ));
+                if status == 429 || (500..600).contains(&status) {
+                    let retry_after = response
+                        .headers()
+                        .get("Retry-After")
+                        .and_then(|seconds| seconds.trim().parse().ok())
+                        .map(Duration::from_secs);
+                    Attempt::Retry(e, retry_after)
+                } else {
+                    Attempt::Done(Err(e))
+                }
+            }
+            Err(e) => Attempt::Done(Err(e)),
+        }
+    }
+}
+
+impl Transport for Http<'_> {
+    fn endpoint(&self) -> &str {
+        self.api_path
+    }
+
+    async fn fetch(&self, query: &StoredQuery) -> Result<Body, gloo_net::Error> {
+        let url = query.url(self.api_path);
+        self.policy.run(|| self.attempt(&url)).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::apis::sparql::block_on;
+
+    fn error(message: &str) -> gloo_net::Error {
+        gloo_net::Error::GlooError(message.into())
+    }
+
+    /// Runs a policy against canned attempts, returning the outcome and the number of attempts.
+    fn run(policy: &RequestPolicy, attempts: Vec<Attempt>) -> (String, usize) {
+        let attempts = RefCell::new(attempts.into_iter());
+        let count = Cell::new(0);
+        let result = block_on(policy.run(|| {
+            count.set(count.get() + 1);
+            let attempt = attempts.borrow_mut().next().unwrap();
+            async { attempt }
+        }));
+        let outcome = match result {
+            Ok(body) => body.text.to_string(),
+            Err(e) => e.to_string(),
+        };
+        (outcome, count.get())
+    }
+
+    fn immediate() -> RequestPolicy {
+        RequestPolicy {
+            backoff: Duration::ZERO,
+            ..RequestPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RequestPolicy::default();
+        assert_eq!(
+            format!("{:?}", (0..6).map(|r| policy.delay(r)).collect::<Vec<_>>()),
+            "[500ms, 1s, 2s, 4s, 8s, 8s]"
+        );
+    }
+
+    #[test]
+    fn test_retries() {
+        let (outcome, attempts) = run(
+            &immediate(),
+            vec![
+                Attempt::Retry(error("503"), None),
+                Attempt::Retry(error("429"), Some(Duration::ZERO)),
+                Attempt::Done(Ok(Body::from("{}".to_string()))),
+            ],
+        );
+        assert_eq!((outcome.as_str(), attempts), ("{}", 3));
+    }
+
+    #[test]
+    fn test_gives_up() {
+        let policy = RequestPolicy {
+            retries: 1,
+            ..immediate()
+        };
+        let (outcome, attempts) = run(
+            &policy,
+            vec![
+                Attempt::Retry(error("first"), None),
+                Attempt::Retry(error("second"), None),
+            ],
+        );
+        assert_eq!((outcome.as_str(), attempts), ("second", 2));
+
+        let (outcome, attempts) = run(&policy, vec![Attempt::Done(Err(error("404")))]);
+        assert_eq!((outcome.as_str(), attempts), ("404", 1));
+    }
+
+    #[test]
+    fn test_abort_handle() {
+        let handle = AbortHandle::new();
+        let shared = handle.clone();
+        assert!(!Http::new("http://localhost")
+            .abort_with(&shared)
+            .is_aborted());
+
+        handle.abort();
+        assert!(shared.is_aborted());
+        assert!(Http::new("http://localhost")
+            .abort_with(&shared)
+            .is_aborted());
+        let attempt = block_on(
+            Http::new("http://localhost")
+                .abort_with(&shared)
+                .attempt(""),
+        );
+        assert!(matches!(attempt, Attempt::Done(Err(e)) if is_cancelled(&e)));
+        assert!(!is_cancelled(&error("503")));
+    }
+}
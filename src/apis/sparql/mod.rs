@@ -0,0 +1,1036 @@
+use chrono::{DateTime, Utc};
+use geo::Geometry;
+use implicit_clone::unsync::IString;
+use std::{collections::HashMap, future::Future, ops::Range, rc::Rc};
+
+use geojson::GeoJson;
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{crs::Crs, models};
+
+mod cache;
+mod http;
+mod store;
+mod wkt;
+
+pub use cache::{Cache, CacheConfig, Cached};
+pub use http::{is_cancelled, AbortHandle, Http, RequestPolicy};
+pub use store::{FileStore, IndexedDbStore, Persisted, Store, StoreFuture};
+pub use wkt::{WktError, WktLiteral, CRS84, EPSG_4326};
+
+/// The datatype of WKT geometries in GeoSPARQL.
+pub const WKT_LITERAL: &str = "http://www.opengis.net/ont/geosparql#wktLiteral";
+
+// Base types
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub enum BindingType {
+    #[serde(rename = "bnode")]
+    Bnode,
+    #[serde(rename = "literal")]
+    Literal,
+    #[serde(rename = "uri")]
+    Uri,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeoJsonBinding {
+    #[serde(rename = "type")]
+    pub binding_type: BindingType,
+    pub datatype: String,
+    pub value: String,
+}
+
+/// A `geo:wktLiteral`, shaped like any other typed literal.
+pub type WktBinding = GeoJsonBinding;
+
+#[derive(Debug, Deserialize)]
+pub struct ObjectPropertyBinding {
+    #[serde(rename = "type")]
+    pub binding_type: BindingType,
+    pub value: String,
+}
+
+// High level types
+
+#[derive(Debug, Deserialize)]
+pub struct DimDescBinding {
+    pub name: ObjectPropertyBinding,
+    pub order: ObjectPropertyBinding,
+    /// The end of the range of the dimension.
+    #[serde(rename = "dimRange")]
+    pub range: ObjectPropertyBinding,
+    /// The start of the range, which is 0 if not bound.
+    #[serde(rename = "dimMin")]
+    pub range_start: Option<ObjectPropertyBinding>,
+    /// The end of the target range.
+    #[serde(rename = "targetRange")]
+    pub target_range: Option<ObjectPropertyBinding>,
+    /// The start of the target range, which is 0 if not bound.
+    #[serde(rename = "targetMin")]
+    pub target_range_start: Option<ObjectPropertyBinding>,
+    /// Whether lower values are better, as an `xsd:boolean`.
+    pub inverted: Option<ObjectPropertyBinding>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DimValueBinding {
+    pub d: ObjectPropertyBinding,
+    pub v: ObjectPropertyBinding,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ObjectBinding {
+    pub p: ObjectPropertyBinding,
+    pub plabel: Option<ObjectPropertyBinding>,
+    pub o: ObjectPropertyBinding,
+    pub olabel: Option<ObjectPropertyBinding>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpatialEntityBinding {
+    pub entity: ObjectPropertyBinding,
+    pub label: Option<ObjectPropertyBinding>,
+    pub geojson: Option<GeoJsonBinding>,
+    pub wkt: Option<WktBinding>,
+    /// The `prov:atTime` of the entity, or the `prov:endedAtTime` of the activity that
+    /// generated it.
+    #[serde(rename = "generatedAt")]
+    pub generated_at: Option<ObjectPropertyBinding>,
+}
+
+/// A binding of a batched query, tagged with the IRI it was asked about.
+#[derive(Debug, Deserialize)]
+pub struct SubjectBinding<B> {
+    pub subject: ObjectPropertyBinding,
+    #[serde(flatten)]
+    pub binding: B,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Results<B> {
+    pub bindings: Vec<B>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Response<B> {
+    pub results: Results<B>,
+    /// See [`Body::stale`].
+    #[serde(skip)]
+    pub stale: Option<DateTime<Utc>>,
+}
+
+// Requests
+
+/// A named query stored on the SPARQL server, along with its parameter bindings.
+///
+/// Parameter names are given without their leading `$` and values are given in SPARQL
+/// syntax, e.g. `("entity", "<http://example.com/data/c>")`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StoredQuery {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl StoredQuery {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Binds a parameter to an IRI.
+    pub fn iri(mut self, param: &str, iri: &str) -> Self {
+        self.params.push((param.into(), format!("<{iri}>")));
+        self
+    }
+
+    /// A query given as SPARQL rather than by name. Stardog accepts the text of a query in
+    /// place of the name of a stored one, which suits queries built by the application.
+    pub fn ad_hoc(sparql: &str) -> Self {
+        Self::new(sparql)
+    }
+
+    /// Binds a parameter to a typed literal.
+    pub fn literal(mut self, param: &str, value: &str, datatype: &str) -> Self {
+        self.params
+            .push((param.into(), format!("{}^^<{datatype}>", quote(value))));
+        self
+    }
+
+    /// Binds a parameter to a list of IRIs, as used in a `VALUES` clause.
+    pub fn iris(mut self, param: &str, iris: &[impl AsRef<str>]) -> Self {
+        let iris = iris
+            .iter()
+            .map(|iri| format!("<{}>", iri.as_ref()))
+            .collect::<Vec<_>>();
+        self.params.push((param.into(), iris.join(" ")));
+        self
+    }
+
+    pub fn url(&self, api_path: &str) -> String {
+        self.params.iter().fold(
+            format!("{api_path}/query?query={}", percent_encode(&self.name)),
+            |url, (param, value)| format!("{url}&${param}={}", percent_encode(value)),
+        )
+    }
+}
+
+/// Encodes the characters that would end or change the meaning of a URL query component,
+/// leaving IRIs in angle brackets readable.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b' ' | b'"' | b'#' | b'%' | b'&' | b'+' | b'[' | b'\\' | b']' | b'^' | b'`' | b'{'
+            | b'|' | b'}' => format!("%{byte:02X}"),
+            byte if byte.is_ascii_control() || !byte.is_ascii() => format!("%{byte:02X}"),
+            byte => (byte as char).to_string(),
+        })
+        .collect()
+}
+
+/// A string as a quoted SPARQL literal.
+fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\"{escaped}\"")
+}
+
+/// How the entities found by a spatial search relate to the geometry searched with.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SpatialRelation {
+    /// Entities that touch or overlap the geometry, by `geof:sfIntersects`.
+    #[default]
+    Intersects,
+    /// Entities entirely inside the geometry, by `geof:sfWithin`.
+    Within,
+}
+
+impl SpatialRelation {
+    /// The GeoSPARQL function that tests the relation.
+    pub fn function(&self) -> &'static str {
+        match self {
+            Self::Intersects => "http://www.opengis.net/def/function/geosparql/sfIntersects",
+            Self::Within => "http://www.opengis.net/def/function/geosparql/sfWithin",
+        }
+    }
+
+    /// The name of the stored query that searches by the relation.
+    pub fn stored_query(&self) -> &'static str {
+        match self {
+            Self::Intersects => "getSpatialEntitiesIntersecting",
+            Self::Within => "getSpatialEntitiesWithin",
+        }
+    }
+}
+
+/// SPARQL that finds spatial entities related to a geometry, with the same bindings as
+/// `getSpatialEntity`. This is the query behind the stored spatial searches, with the geometry
+/// in place of their `$geometry` parameter.
+pub fn spatial_search_sparql(relation: SpatialRelation, geometry: &WktLiteral) -> String {
+    let function = relation.function();
+    let geometry = quote(&geometry.to_string());
+    format!(
+        "PREFIX geo: <http://www.opengis.net/ont/geosparql#>
+PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+PREFIX prov: <http://www.w3.org/ns/prov#>
+SELECT ?entity ?label ?geojson ?wkt ?generatedAt WHERE {{
+  ?entity geo:hasGeometry ?geometry .
+  ?geometry geo:asWKT ?wkt .
+  OPTIONAL {{ ?geometry geo:asGeoJSON ?geojson }}
+  OPTIONAL {{ ?entity rdfs:label ?label }}
+  OPTIONAL {{ ?entity prov:atTime ?atTime }}
+  OPTIONAL {{ ?entity prov:wasGeneratedBy/prov:endedAtTime ?endedAt }}
+  BIND(COALESCE(?atTime, ?endedAt) AS ?generatedAt)
+  FILTER(<{function}>(?wkt, {geometry}^^geo:wktLiteral))
+}}"
+    )
+}
+
+/// A SPARQL JSON results document as returned by a [`Transport`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    pub text: Rc<str>,
+    /// When the results are older than the cache allows, e.g. because we are working offline,
+    /// the time at which they were fetched.
+    pub stale: Option<DateTime<Utc>>,
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Self {
+            text: text.into(),
+            stale: None,
+        }
+    }
+}
+
+/// Something that can run stored queries and return SPARQL JSON results.
+///
+/// A `str` is taken to be the API path of a Stardog database and is queried over HTTP with the
+/// default [`RequestPolicy`]; use [`Http`] to choose another policy or to cancel requests.
+pub trait Transport {
+    /// Identifies the database that queries are run against, e.g. for use in cache keys.
+    fn endpoint(&self) -> &str;
+
+    fn fetch(&self, query: &StoredQuery) -> impl Future<Output = Result<Body, gloo_net::Error>>;
+}
+
+impl Transport for str {
+    fn endpoint(&self) -> &str {
+        self
+    }
+
+    async fn fetch(&self, query: &StoredQuery) -> Result<Body, gloo_net::Error> {
+        Http::new(self).fetch(query).await
+    }
+}
+
+impl Transport for String {
+    fn endpoint(&self) -> &str {
+        self
+    }
+
+    async fn fetch(&self, query: &StoredQuery) -> Result<Body, gloo_net::Error> {
+        self.as_str().fetch(query).await
+    }
+}
+
+pub async fn run_stored_query<T: Transport + ?Sized, B: DeserializeOwned>(
+    transport: &T,
+    query: &StoredQuery,
+) -> Result<Response<B>, gloo_net::Error> {
+    let body = transport.fetch(query).await?;
+    let mut response: Response<B> = serde_json::from_str(&body.text)?;
+    response.stale = body.stale;
+    Ok(response)
+}
+
+pub async fn get_activity<T: Transport + ?Sized>(
+    transport: &T,
+    activity_id: &str,
+) -> Result<Response<ObjectBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getActivity").iri("activity", activity_id),
+    )
+    .await
+}
+
+pub async fn get_agent<T: Transport + ?Sized>(
+    transport: &T,
+    agent_id: &str,
+) -> Result<Response<ObjectBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getAgent").iri("agent", agent_id),
+    )
+    .await
+}
+
+pub async fn get_dim_desc<T: Transport + ?Sized>(
+    transport: &T,
+    entity_id: &str,
+) -> Result<Response<DimDescBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getDimDesc").iri("object", entity_id),
+    )
+    .await
+}
+
+pub async fn get_dim_values<T: Transport + ?Sized>(
+    transport: &T,
+    entity_id: &str,
+) -> Result<Response<DimValueBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getDimValues").iri("object", entity_id),
+    )
+    .await
+}
+
+pub async fn get_entity<T: Transport + ?Sized>(
+    transport: &T,
+    entity_id: &str,
+) -> Result<Response<ObjectBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getEntity").iri("entity", entity_id),
+    )
+    .await
+}
+
+pub async fn get_object<T: Transport + ?Sized>(
+    transport: &T,
+    object_id: &str,
+) -> Result<Response<ObjectBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getObject").iri("object", object_id),
+    )
+    .await
+}
+
+pub async fn get_spatial_entity<T: Transport + ?Sized>(
+    transport: &T,
+    entity_id: &str,
+) -> Result<Response<SpatialEntityBinding>, gloo_net::Error> {
+    run_stored_query(
+        transport,
+        &StoredQuery::new("getSpatialEntity").iri("entity", entity_id),
+    )
+    .await
+}
+
+/// Finds the spatial entities related to a geometry in a CRS, e.g. one drawn on a map, with the
+/// stored query for the relation.
+pub async fn search_spatial_entities<T: Transport + ?Sized>(
+    transport: &T,
+    geometry: &Geometry,
+    crs: &Crs,
+    relation: SpatialRelation,
+) -> Result<Vec<(IString, models::Entity)>, gloo_net::Error> {
+    let query = StoredQuery::new(relation.stored_query()).literal(
+        "geometry",
+        &search_geometry(geometry, crs).to_string(),
+        WKT_LITERAL,
+    );
+    let response = run_stored_query(transport, &query).await?;
+    Ok(spatial_entities_from_response(response))
+}
+
+/// Like [`search_spatial_entities`], with the query sent as SPARQL for databases without the
+/// stored spatial searches.
+pub async fn search_spatial_entities_ad_hoc<T: Transport + ?Sized>(
+    transport: &T,
+    geometry: &Geometry,
+    crs: &Crs,
+    relation: SpatialRelation,
+) -> Result<Vec<(IString, models::Entity)>, gloo_net::Error> {
+    let sparql = spatial_search_sparql(relation, &search_geometry(geometry, crs));
+    let response = run_stored_query(transport, &StoredQuery::ad_hoc(&sparql)).await?;
+    Ok(spatial_entities_from_response(response))
+}
+
+/// A geometry in a CRS as a `wktLiteral` in CRS84, the default and the only CRS that stores
+/// such as Stardog reliably evaluate spatial functions in.
+fn search_geometry(geometry: &Geometry, crs: &Crs) -> WktLiteral {
+    // A rectangle in one CRS is not aligned with the axes of another, so a `Rect` cannot hold
+    // its reprojection. Its corners are reprojected as a polygon instead.
+    let geometry = match geometry {
+        Geometry::Rect(rect) => Geometry::Polygon(rect.to_polygon()),
+        geometry => geometry.clone(),
+    };
+    WktLiteral {
+        crs: None,
+        geometry: crs.reproject(&geometry, &Crs::Crs84),
+    }
+}
+
+// Batched requests
+
+/// The most IRIs passed to a single batched query, which keeps URLs to a few kilobytes.
+pub const BATCH_SIZE: usize = 50;
+
+/// Runs a batched stored query over as many requests as needed, concatenating the results.
+async fn run_batched_query<T: Transport + ?Sized, B: DeserializeOwned>(
+    transport: &T,
+    name: &str,
+    param: &str,
+    ids: &[impl AsRef<str>],
+) -> Result<Response<B>, gloo_net::Error> {
+    let mut merged = Response {
+        results: Results {
+            bindings: Vec::new(),
+        },
+        stale: None,
+    };
+    for chunk in ids.chunks(BATCH_SIZE) {
+        let response: Response<B> =
+            run_stored_query(transport, &StoredQuery::new(name).iris(param, chunk)).await?;
+        merged.results.bindings.extend(response.results.bindings);
+        merged.stale = match (merged.stale, response.stale) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+    Ok(merged)
+}
+
+/// Splits the results of a batched query into a response for each of the given IRIs, in order.
+pub fn group_by_subject<B>(
+    ids: &[impl AsRef<str>],
+    response: Response<SubjectBinding<B>>,
+) -> Vec<(IString, Response<B>)> {
+    let mut groups = ids
+        .iter()
+        .map(|id| {
+            (
+                IString::from(id.as_ref().to_string()),
+                Response {
+                    results: Results {
+                        bindings: Vec::new(),
+                    },
+                    stale: response.stale,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    // The first group of each IRI, should any be given twice.
+    let mut positions = HashMap::with_capacity(ids.len());
+    for (i, id) in ids.iter().enumerate() {
+        positions.entry(id.as_ref()).or_insert(i);
+    }
+    for b in response.results.bindings {
+        if let Some(&i) = positions.get(b.subject.value.as_str()) {
+            groups[i].1.results.bindings.push(b.binding);
+        }
+    }
+    groups
+}
+
+/// Like [`get_entity`] for many entities, grouped by entity.
+pub async fn get_entities<T: Transport + ?Sized>(
+    transport: &T,
+    entity_ids: &[impl AsRef<str>],
+) -> Result<Vec<(IString, Response<ObjectBinding>)>, gloo_net::Error> {
+    let response = run_batched_query(transport, "getEntities", "entities", entity_ids).await?;
+    Ok(group_by_subject(entity_ids, response))
+}
+
+/// Like [`get_object`] for many objects, grouped by object.
+pub async fn get_objects<T: Transport + ?Sized>(
+    transport: &T,
+    object_ids: &[impl AsRef<str>],
+) -> Result<Vec<(IString, Response<ObjectBinding>)>, gloo_net::Error> {
+    let response = run_batched_query(transport, "getObjects", "objects", object_ids).await?;
+    Ok(group_by_subject(object_ids, response))
+}
+
+/// Like [`get_spatial_entity`] for many entities. As each binding names its entity, the
+/// response can be passed straight to [`spatial_entities_from_response`].
+pub async fn get_spatial_entities<T: Transport + ?Sized>(
+    transport: &T,
+    entity_ids: &[impl AsRef<str>],
+) -> Result<Response<SpatialEntityBinding>, gloo_net::Error> {
+    run_batched_query(transport, "getSpatialEntities", "entities", entity_ids).await
+}
+
+// Response processing
+
+fn extract_label(label: Option<ObjectPropertyBinding>) -> Option<IString> {
+    label.and_then(|l| {
+        if l.binding_type == BindingType::Literal {
+            Some(l.value.into())
+        } else {
+            None
+        }
+    })
+}
+
+pub fn activity_from_response(response: Response<ObjectBinding>) -> models::Activity {
+    #[derive(Default)]
+    struct State {
+        ended_at: Option<DateTime<Utc>>,
+        generated: Vec<models::EntityLink>,
+        influenced: Vec<models::ActivityLink>,
+        label: Option<IString>,
+        properties: Vec<(IString, IString)>,
+        started_at: Option<DateTime<Utc>>,
+        used: Vec<models::EntityLink>,
+        was_associated_with: Vec<models::AgentLink>,
+        was_influenced_by: Vec<models::ActivityLink>,
+    }
+
+    let s = response
+        .results
+        .bindings
+        .into_iter()
+        .fold(State::default(), |mut s, o| {
+            if o.p.binding_type == BindingType::Uri {
+                if o.p.value == "http://www.w3.org/ns/prov#endedAtTime" {
+                    s.ended_at = o.o.value.parse().ok();
+                } else if o.p.value == "http://www.w3.org/2000/01/rdf-schema#label" {
+                    s.label = Some(o.o.value.into());
+                } else if o.p.value == "http://www.w3.org/ns/prov#generated" {
+                    s.generated
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#influenced" {
+                    s.influenced
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#startedAtTime" {
+                    s.started_at = o.o.value.parse().ok();
+                } else if o.p.value == "http://www.w3.org/ns/prov#used" {
+                    s.used.push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#wasAssociatedWith" {
+                    s.was_associated_with
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#wasInfluencedBy" {
+                    s.was_influenced_by
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.o.binding_type == BindingType::Literal {
+                    if let Some(label) = extract_label(o.plabel) {
+                        s.properties.push((label, o.o.value.into()));
+                    }
+                }
+            }
+            s
+        });
+
+    models::Activity {
+        ended_at: s.ended_at,
+        label: s.label,
+        generated: s.generated.into(),
+        influenced: s.influenced.into(),
+        properties: s.properties.into(),
+        started_at: s.started_at,
+        used: s.used.into(),
+        was_associated_with: s.was_associated_with.into(),
+        was_influenced_by: s.was_influenced_by.into(),
+    }
+}
+
+pub fn agent_from_response(response: Response<ObjectBinding>) -> models::Agent {
+    #[derive(Default)]
+    struct State {
+        influenced: Vec<models::ActivityLink>,
+        label: Option<IString>,
+        properties: Vec<(IString, IString)>,
+    }
+
+    let s = response
+        .results
+        .bindings
+        .into_iter()
+        .fold(State::default(), |mut s, o| {
+            if o.p.binding_type == BindingType::Uri {
+                if o.p.value == "http://www.w3.org/ns/prov#influenced" {
+                    s.influenced
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/2000/01/rdf-schema#label" {
+                    s.label = Some(o.o.value.into());
+                } else if o.o.binding_type == BindingType::Literal {
+                    if let Some(label) = extract_label(o.plabel) {
+                        s.properties.push((label, o.o.value.into()));
+                    }
+                }
+            }
+            s
+        });
+
+    models::Agent {
+        influenced: s.influenced.into(),
+        label: s.label,
+        properties: s.properties.into(),
+    }
+}
+
+/// A range from literals for its start, which defaults to `default_start`, and its end.
+fn literal_range(
+    start: Option<ObjectPropertyBinding>,
+    end: ObjectPropertyBinding,
+    default_start: f32,
+) -> Option<Range<f32>> {
+    let number = |binding: ObjectPropertyBinding| {
+        if binding.binding_type != BindingType::Literal {
+            return None;
+        }
+        binding.value.parse::<f32>().ok()
+    };
+    let start = match start {
+        Some(start) => number(start)?,
+        None => default_start,
+    };
+    Some(start..number(end)?)
+}
+
+pub fn dim_desc_from_response(response: Response<DimDescBinding>) -> Vec<models::DimDesc> {
+    response
+        .results
+        .bindings
+        .into_iter()
+        .filter_map(|dd| {
+            if dd.name.binding_type != BindingType::Literal {
+                return None;
+            }
+            let name = dd.name.value.into();
+
+            if dd.order.binding_type != BindingType::Literal {
+                return None;
+            }
+            let order = dd.order.value.parse().ok()?;
+
+            let range = literal_range(dd.range_start, dd.range, 0.0)?;
+            if range.is_empty() {
+                log::warn!("Dimension {name} has an empty range {range:?}");
+                return None;
+            }
+
+            let target_range = dd
+                .target_range
+                .and_then(|end| literal_range(dd.target_range_start, end, range.start))
+                .filter(|target| {
+                    let valid = target.start <= target.end
+                        && range.start <= target.start
+                        && target.end <= range.end;
+                    if !valid {
+                        log::warn!("Dimension {name} has a target {target:?} not within {range:?}");
+                    }
+                    valid
+                });
+
+            let inverted = dd.inverted.is_some_and(|inverted| {
+                inverted.binding_type == BindingType::Literal
+                    && matches!(inverted.value.as_str(), "true" | "1")
+            });
+
+            Some(models::DimDesc {
+                name,
+                order,
+                range,
+                target_range,
+                inverted,
+            })
+        })
+        .collect()
+}
+
+pub fn dim_values_from_response(response: Response<DimValueBinding>) -> Vec<models::DimValue> {
+    response
+        .results
+        .bindings
+        .into_iter()
+        .filter_map(|dv| {
+            if dv.d.binding_type != BindingType::Literal {
+                return None;
+            }
+            let d = dv.d.value.into();
+
+            if dv.v.binding_type != BindingType::Literal {
+                return None;
+            }
+            let v = dv.v.value.parse().ok()?;
+
+            Some(models::DimValue { d, v })
+        })
+        .collect()
+}
+
+pub fn entity_from_response(response: Response<ObjectBinding>) -> models::Entity {
+    #[derive(Default)]
+    struct State {
+        generated_at: Option<DateTime<Utc>>,
+        label: Option<IString>,
+        properties: Vec<(IString, IString)>,
+        was_derived_from: Vec<models::EntityLink>,
+        was_generated_by: Vec<models::ActivityLink>,
+        was_attributed_to: Vec<models::ActivityLink>,
+    }
+
+    let s = response
+        .results
+        .bindings
+        .into_iter()
+        .fold(State::default(), |mut s, o| {
+            if o.p.binding_type == BindingType::Uri {
+                if o.p.value == "http://www.w3.org/2000/01/rdf-schema#label" {
+                    s.label = Some(o.o.value.into());
+                } else if o.p.value == "http://www.w3.org/ns/prov#wasAttributedTo" {
+                    s.was_attributed_to
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#wasDerivedFrom" {
+                    s.was_derived_from
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#wasGeneratedBy" {
+                    s.was_generated_by
+                        .push((extract_label(o.olabel), o.o.value.into()));
+                } else if o.p.value == "http://www.w3.org/ns/prov#atTime" {
+                    s.generated_at = o.o.value.parse().ok();
+                } else if o.o.binding_type == BindingType::Literal {
+                    if let Some(label) = extract_label(o.plabel) {
+                        s.properties.push((label, o.o.value.into()));
+                    }
+                }
+            }
+            s
+        });
+
+    models::Entity {
+        crs: Crs::default(),
+        generated_at: s.generated_at,
+        geometry: None,
+        label: s.label,
+        properties: s.properties.into(),
+        was_attributed_to: s.was_attributed_to.into(),
+        was_derived_from: s.was_derived_from.into(),
+        was_generated_by: s.was_generated_by.into(),
+    }
+}
+
+pub fn spatial_entities_from_response(
+    response: Response<SpatialEntityBinding>,
+) -> Vec<(IString, models::Entity)> {
+    response
+        .results
+        .bindings
+        .into_iter()
+        .filter_map(|b| {
+            if b.entity.binding_type != BindingType::Uri {
+                return None;
+            }
+            let id = b.entity.value.into();
+
+            // Prefer GeoJSON, which is always CRS84, falling back to WKT when it is missing or
+            // unusable.
+            let from_geojson = b.geojson.and_then(|geojson| {
+                if geojson.binding_type != BindingType::Literal
+                    || &geojson.datatype != "http://www.opengis.net/ont/geosparql#geoJSONLiteral"
+                {
+                    return None;
+                }
+                geojson
+                    .value
+                    .parse::<GeoJson>()
+                    .ok()
+                    .and_then(|g| g.try_into().ok())
+                    .map(|geometry| (Crs::Crs84, geometry))
+            });
+            let (crs, geometry) = from_geojson.or_else(|| {
+                let wkt = b.wkt?;
+                if wkt.binding_type != BindingType::Literal || wkt.datatype != WKT_LITERAL {
+                    return None;
+                }
+                let literal = wkt
+                    .value
+                    .parse::<WktLiteral>()
+                    .map_err(|e| log::warn!("{e}"))
+                    .ok()?;
+                Some((literal.crs()?, literal.geometry))
+            })?;
+
+            let label = extract_label(b.label);
+            let generated_at = b.generated_at.and_then(|time| time.value.parse().ok());
+
+            Some((
+                id,
+                models::Entity {
+                    crs,
+                    generated_at,
+                    geometry: Some(Rc::new(geometry)),
+                    label,
+                    ..models::Entity::default()
+                },
+            ))
+        })
+        .collect()
+}
+
+//
+
+/// Runs a future to completion in tests by polling it until it is ready, without a runtime.
+///
+/// Nothing wakes it, so it spins forever on a future that waits for a real timer, e.g. a
+/// [`RequestPolicy`] with a backoff: tests of such futures must use zero durations.
+#[cfg(test)]
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let std::task::Poll::Ready(output) = future
+            .as_mut()
+            .poll(&mut std::task::Context::from_waker(std::task::Waker::noop()))
+        {
+            return output;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::Read};
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_search_geometry() {
+        let point = Geometry::Point(geo::Point::new(145.8, -16.9));
+        let literal = search_geometry(&Crs::Crs84.reproject(&point, &Crs::mga(55)), &Crs::mga(55));
+        assert_eq!(literal.crs, None);
+        let Geometry::Point(searched) = literal.geometry else {
+            panic!("expected a point, not {:?}", literal.geometry);
+        };
+        assert!((searched.x() - 145.8).abs() < 1e-6 && (searched.y() + 16.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_deser_q1() {
+        let mut f = fs::File::open(format!(
+            "{}/sample_data/q1.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let mut raw_response = String::new();
+        f.read_to_string(&mut raw_response).unwrap();
+        let response = serde_json::from_str(&raw_response).unwrap();
+
+        assert_eq!(
+            format!("{:?}", spatial_entities_from_response(response)),
+            "[(Rc(\"http://example.com/data/c\"), Entity { crs: Crs84, generated_at: None, geometry: Some(Polygon(Polygon { exterior: LineString([Coord { x: 150.5, y: -34.0 }, Coord { x: 150.502, y: -34.0005 }, Coord { x: 150.504, y: -34.001 }, Coord { x: 150.506, y: -34.0015 }, Coord { x: 150.508, y: -34.002 }, Coord { x: 150.51, y: -34.0025 }, Coord { x: 150.512, y: -34.003 }, Coord { x: 150.514, y: -34.0035 }, Coord { x: 150.516, y: -34.004 }, Coord { x: 150.518, y: -34.0045 }, Coord { x: 150.52, y: -34.005 }, Coord { x: 150.522, y: -34.0045 }, Coord { x: 150.524, y: -34.004 }, Coord { x: 150.526, y: -34.0035 }, Coord { x: 150.528, y: -34.003 }, Coord { x: 150.53, y: -34.0025 }, Coord { x: 150.528, y: -34.002 }, Coord { x: 150.526, y: -34.0015 }, Coord { x: 150.524, y: -34.001 }, Coord { x: 150.522, y: -34.0005 }, Coord { x: 150.52, y: -34.0 }, Coord { x: 150.518, y: -34.0005 }, Coord { x: 150.516, y: -34.001 }, Coord { x: 150.514, y: -34.0015 }, Coord { x: 150.512, y: -34.002 }, Coord { x: 150.51, y: -34.0025 }, Coord { x: 150.508, y: -34.003 }, Coord { x: 150.506, y: -34.0025 }, Coord { x: 150.504, y: -34.002 }, Coord { x: 150.502, y: -34.0015 }, Coord { x: 150.5, y: -34.001 }, Coord { x: 150.5, y: -34.0 }]), interiors: [] })), label: Some(Rc(\"C\")), properties: [], was_attributed_to: [], was_derived_from: [], was_generated_by: [] })]"
+        );
+    }
+
+    #[test]
+    fn test_deser_q1_wkt_only() {
+        let raw_response = fs::read_to_string(format!(
+            "{}/sample_data/q1.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+        let mut wkt_only: serde_json::Value = serde_json::from_str(&raw_response).unwrap();
+        for binding in wkt_only["results"]["bindings"].as_array_mut().unwrap() {
+            binding.as_object_mut().unwrap().remove("geojson");
+        }
+
+        assert_eq!(
+            format!(
+                "{:?}",
+                spatial_entities_from_response(serde_json::from_value(wkt_only).unwrap())
+            ),
+            format!(
+                "{:?}",
+                spatial_entities_from_response(serde_json::from_str(&raw_response).unwrap())
+            )
+        );
+    }
+
+    #[test]
+    fn test_deser_q2() {
+        let mut f = fs::File::open(format!(
+            "{}/sample_data/q2.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let mut raw_response = String::new();
+        f.read_to_string(&mut raw_response).unwrap();
+        let response = serde_json::from_str(&raw_response).unwrap();
+
+        assert_eq!(
+            format!("{:?}", entity_from_response(response)),
+            "Entity { crs: Crs84, generated_at: None, geometry: None, label: Some(Rc(\"C\")), properties: [], was_attributed_to: [(Some(Rc(\"Adder-run1\")), Rc(\"http://example.com/activities/add1\")), (Some(Rc(\"A\")), Rc(\"http://example.com/data/a\")), (Some(Rc(\"B\")), Rc(\"http://example.com/data/b\"))], was_derived_from: [(Some(Rc(\"A\")), Rc(\"http://example.com/data/a\")), (Some(Rc(\"B\")), Rc(\"http://example.com/data/b\"))], was_generated_by: [(Some(Rc(\"Adder-run1\")), Rc(\"http://example.com/activities/add1\"))] }"
+        );
+    }
+
+    #[test]
+    fn test_deser_q3() {
+        let mut f = fs::File::open(format!(
+            "{}/sample_data/q3.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let mut raw_response = String::new();
+        f.read_to_string(&mut raw_response).unwrap();
+        let response = serde_json::from_str(&raw_response).unwrap();
+
+        assert_eq!(
+            format!("{:?}", activity_from_response(response)),
+            "Activity { ended_at: Some(2029-01-01T20:05:19Z), generated: [(Some(Rc(\"C\")), Rc(\"http://example.com/data/c\"))], influenced: [(Some(Rc(\"C\")), Rc(\"http://example.com/data/c\"))], label: Some(Rc(\"Adder-run1\")), properties: [], started_at: None, used: [(Some(Rc(\"A\")), Rc(\"http://example.com/data/a\")), (Some(Rc(\"B\")), Rc(\"http://example.com/data/b\"))], was_associated_with: [(Some(Rc(\"Add\")), Rc(\"http://example.com/agents/adder\"))], was_influenced_by: [(Some(Rc(\"A\")), Rc(\"http://example.com/data/a\")), (Some(Rc(\"B\")), Rc(\"http://example.com/data/b\")), (Some(Rc(\"Add\")), Rc(\"http://example.com/agents/adder\"))] }"
+        );
+    }
+
+    #[test]
+    fn test_deser_agent() {
+        let mut f = fs::File::open(format!(
+            "{}/sample_data/agent.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let mut raw_response = String::new();
+        f.read_to_string(&mut raw_response).unwrap();
+        let response = serde_json::from_str(&raw_response).unwrap();
+
+        assert_eq!(
+            format!("{:?}", agent_from_response(response)),
+            "Agent { influenced: [(Some(Rc(\"Route Geometry Extraction\")), Rc(\"http://example.com/activities/router-q2\"))], label: Some(Rc(\"ChatGPT (OpenAI) generic model\")), properties: [] }"
+        );
+    }
+
+    #[test]
+    fn test_deser_dimdescs() {
+        let mut f = fs::File::open(format!(
+            "{}/sample_data/path_q3.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let mut raw_response = String::new();
+        f.read_to_string(&mut raw_response).unwrap();
+        let response = serde_json::from_str(&raw_response).unwrap();
+
+        assert_eq!(
+            format!("{:?}", dim_desc_from_response(response)),
+            "[DimDesc { name: Rc(\"Foo\"), order: 1, range: 0.0..5.0, target_range: Some(0.0..5.0), inverted: false }, DimDesc { name: Rc(\"Bar\"), order: 3, range: 0.0..5.0, target_range: Some(0.0..5.0), inverted: false }, DimDesc { name: Rc(\"Dang\"), order: 2, range: 0.0..5.0, target_range: Some(0.0..5.0), inverted: false }]"
+        );
+
+        let mut bounded: serde_json::Value = serde_json::from_str(&raw_response).unwrap();
+        let literal = |value: &str| json!({ "type": "literal", "value": value });
+        let bindings = &mut bounded["results"]["bindings"];
+        bindings[0]["dimMin"] = literal("1");
+        bindings[0]["targetMin"] = literal("2");
+        bindings[0]["inverted"] = literal("true");
+        // An empty range is skipped.
+        bindings[1]["dimMin"] = literal("5");
+        // The target starts with the range by default.
+        bindings[2]["dimMin"] = literal("2");
+        bindings[2]["targetRange"] = literal("4");
+        assert_eq!(
+            format!(
+                "{:?}",
+                dim_desc_from_response(serde_json::from_value(bounded).unwrap())
+            ),
+            "[DimDesc { name: Rc(\"Foo\"), order: 1, range: 1.0..5.0, target_range: Some(2.0..5.0), inverted: true }, DimDesc { name: Rc(\"Dang\"), order: 2, range: 2.0..5.0, target_range: Some(2.0..4.0), inverted: false }]"
+        );
+
+        // Targets that are backwards or outside the range are dropped.
+        let mut outside: serde_json::Value = serde_json::from_str(&raw_response).unwrap();
+        let bindings = &mut outside["results"]["bindings"];
+        bindings[0]["targetMin"] = literal("4");
+        bindings[0]["targetRange"] = literal("3");
+        bindings[1]["targetRange"] = literal("6");
+        assert_eq!(
+            dim_desc_from_response(serde_json::from_value(outside).unwrap())
+                .into_iter()
+                .map(|dd| dd.target_range)
+                .collect::<Vec<_>>(),
+            [None, None, Some(0.0..5.0)]
+        );
+    }
+
+    #[test]
+    fn test_deser_dimvalues() {
+        let mut f = fs::File::open(format!(
+            "{}/sample_data/path_q4.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let mut raw_response = String::new();
+        f.read_to_string(&mut raw_response).unwrap();
+        let response = serde_json::from_str(&raw_response).unwrap();
+
+        assert_eq!(
+            format!("{:?}", dim_values_from_response(response)),
+            "[DimValue { d: Rc(\"Foo\"), v: 3.0 }, DimValue { d: Rc(\"Bar\"), v: 1.0 }, DimValue { d: Rc(\"Dang\"), v: 2.0 }]"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Persistent storage for cached stored query results, so that they survive a reload and can
+//! be used while working offline.
+
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    future::Future,
+    io,
+    path::PathBuf,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use web_sys::{
+    js_sys::Array,
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode,
+};
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Persisted {
+    pub fetched_at: DateTime<Utc>,
+    pub body: String,
+}
+
+/// A persistent key-value store for SPARQL responses.
+///
+/// Persistence is best effort: failures are logged and otherwise treated as a missing entry.
+pub trait Store {
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<Persisted>>;
+
+    fn save<'a>(&'a self, key: &'a str, entry: &'a Persisted) -> StoreFuture<'a, ()>;
+
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()>;
+
+    fn keys(&self) -> StoreFuture<'_, Vec<String>>;
+
+    fn clear(&self) -> StoreFuture<'_, ()>;
+}
+
+// Native
+
+/// Stores each response as a JSON file in a directory, for native applications and tests.
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FileRecord {
+    key: String,
+    #[serde(flatten)]
+    entry: Persisted,
+}
+
+impl FileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        // FNV-1a, which unlike the std hasher is stable between builds.
+        let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        self.dir.join(format!("{hash:016x}.json"))
+    }
+
+    fn records(&self) -> impl Iterator<Item = (PathBuf, FileRecord)> {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|dir_entry| dir_entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .filter_map(|path| {
+                let record = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+                Some((path, record))
+            })
+    }
+}
+
+impl Store for FileStore {
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<Persisted>> {
+        let record = fs::read_to_string(self.path(key))
+            .ok()
+            .and_then(|json| serde_json::from_str::<FileRecord>(&json).ok())
+            .filter(|record| record.key == key);
+        Box::pin(async move { record.map(|record| record.entry) })
+    }
+
+    fn save<'a>(&'a self, key: &'a str, entry: &'a Persisted) -> StoreFuture<'a, ()> {
+        let record = FileRecord {
+            key: key.into(),
+            entry: entry.clone(),
+        };
+        let result = serde_json::to_string(&record)
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(self.path(key), json));
+        if let Err(e) = result {
+            log::warn!("Cannot persist {key}: {e}");
+        }
+        Box::pin(async {})
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()> {
+        let _ = fs::remove_file(self.path(key));
+        Box::pin(async {})
+    }
+
+    fn keys(&self) -> StoreFuture<'_, Vec<String>> {
+        let keys = self.records().map(|(_, record)| record.key).collect();
+        Box::pin(async { keys })
+    }
+
+    fn clear(&self) -> StoreFuture<'_, ()> {
+        for (path, _) in self.records() {
+            let _ = fs::remove_file(path);
+        }
+        Box::pin(async {})
+    }
+}
+
+// Browser
+
+const OBJECT_STORE: &str = "responses";
+
+/// Stores responses in an IndexedDB database of the given name.
+pub struct IndexedDbStore {
+    name: String,
+    db: RefCell<Option<IdbDatabase>>,
+}
+
+impl IndexedDbStore {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            db: RefCell::new(None),
+        }
+    }
+
+    async fn database(&self) -> Result<IdbDatabase, JsValue> {
+        let db = self.db.borrow().clone();
+        if let Some(db) = db {
+            return Ok(db);
+        }
+
+        let factory = web_sys::window()
+            .ok_or("There is no window")?
+            .indexed_db()?
+            .ok_or("IndexedDB is unavailable")?;
+        let request = factory.open_with_u32(&self.name, 1)?;
+        let upgrade_request = request.clone();
+        let on_upgrade_needed = Closure::<dyn FnMut()>::new(move || {
+            if let Ok(db) = upgrade_request
+                .result()
+                .and_then(|db| db.dyn_into::<IdbDatabase>())
+            {
+                let _ = db.create_object_store(OBJECT_STORE);
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade_needed.as_ref().unchecked_ref()));
+        let db = Settle::new(&request)
+            .await
+            .and_then(|db| db.dyn_into::<IdbDatabase>());
+        request.set_onupgradeneeded(None);
+
+        let db = db?;
+        *self.db.borrow_mut() = Some(db.clone());
+        Ok(db)
+    }
+
+    async fn object_store(&self, mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+        self.database()
+            .await?
+            .transaction_with_str_and_mode(OBJECT_STORE, mode)?
+            .object_store(OBJECT_STORE)
+    }
+
+    async fn run(
+        &self,
+        mode: IdbTransactionMode,
+        request: impl FnOnce(&IdbObjectStore) -> Result<IdbRequest, JsValue>,
+    ) -> Result<JsValue, JsValue> {
+        let store = self.object_store(mode).await?;
+        Settle::new(&request(&store)?).await
+    }
+}
+
+fn log_error<T>(action: &str, result: Result<T, JsValue>) -> Option<T> {
+    result
+        .map_err(|e| log::warn!("Cannot {action} IndexedDB: {e:?}"))
+        .ok()
+}
+
+impl Store for IndexedDbStore {
+    fn load<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<Persisted>> {
+        Box::pin(async move {
+            let value = self
+                .run(IdbTransactionMode::Readonly, |store| {
+                    store.get(&JsValue::from_str(key))
+                })
+                .await;
+            log_error("read from", value)?
+                .as_string()
+                .and_then(|json| serde_json::from_str(&json).ok())
+        })
+    }
+
+    fn save<'a>(&'a self, key: &'a str, entry: &'a Persisted) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let Ok(json) = serde_json::to_string(entry) else {
+                return;
+            };
+            let result = self
+                .run(IdbTransactionMode::Readwrite, |store| {
+                    store.put_with_key(&JsValue::from_str(&json), &JsValue::from_str(key))
+                })
+                .await;
+            log_error("write to", result);
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()> {
+        Box::pin(async move {
+            let result = self
+                .run(IdbTransactionMode::Readwrite, |store| {
+                    store.delete(&JsValue::from_str(key))
+                })
+                .await;
+            log_error("delete from", result);
+        })
+    }
+
+    fn keys(&self) -> StoreFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let keys = self
+                .run(IdbTransactionMode::Readonly, |store| store.get_all_keys())
+                .await;
+            log_error("read from", keys)
+                .map(|keys| {
+                    Array::from(&keys)
+                        .iter()
+                        .filter_map(|key| key.as_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    fn clear(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async move {
+            let result = self
+                .run(IdbTransactionMode::Readwrite, |store| store.clear())
+                .await;
+            log_error("clear", result);
+        })
+    }
+}
+
+#[derive(Default)]
+struct Settled {
+    succeeded: Cell<Option<bool>>,
+    waker: RefCell<Option<Waker>>,
+}
+
+/// Waits for an IndexedDB request to succeed or fail, detaching its handlers when dropped.
+struct Settle<'a> {
+    request: &'a IdbRequest,
+    settled: Rc<Settled>,
+    _on_success: Closure<dyn FnMut()>,
+    _on_error: Closure<dyn FnMut()>,
+}
+
+impl<'a> Settle<'a> {
+    fn new(request: &'a IdbRequest) -> Self {
+        let settled = Rc::new(Settled::default());
+        let handler = |succeeded| {
+            let settled = settled.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                settled.succeeded.set(Some(succeeded));
+                if let Some(waker) = settled.waker.take() {
+                    waker.wake();
+                }
+            })
+        };
+        let on_success = handler(true);
+        let on_error = handler(false);
+        request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+        request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        Self {
+            request,
+            settled,
+            _on_success: on_success,
+            _on_error: on_error,
+        }
+    }
+}
+
+impl Future for Settle<'_> {
+    type Output = Result<JsValue, JsValue>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.settled.succeeded.get() {
+            Some(true) => Poll::Ready(self.request.result()),
+            Some(false) => Poll::Ready(Err(JsValue::from_str("The request failed"))),
+            None => {
+                self.settled.waker.replace(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Settle<'_> {
+    fn drop(&mut self) {
+        self.request.set_onsuccess(None);
+        self.request.set_onerror(None);
+    }
+}
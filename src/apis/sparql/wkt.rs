@@ -0,0 +1,445 @@
+//! Parsing and writing of GeoSPARQL `wktLiteral`s.
+//!
+//! A literal is Well-Known Text, optionally preceded by the IRI of its coordinate reference
+//! system, e.g. `<http://www.opengis.net/def/crs/EPSG/0/4326> POINT(-34.0 150.5)`. Z and M
+//! values are accepted and dropped.
+
+use std::{fmt, str::FromStr};
+
+use geo::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+
+use crate::crs::Crs;
+
+/// The default CRS of a `wktLiteral`: WGS 84 with longitude before latitude.
+pub const CRS84: &str = "http://www.opengis.net/def/crs/OGC/1.3/CRS84";
+
+/// WGS 84 with latitude before longitude.
+pub const EPSG_4326: &str = "http://www.opengis.net/def/crs/EPSG/0/4326";
+
+#[derive(Debug, PartialEq)]
+pub struct WktError {
+    /// The byte offset in the literal at which parsing failed.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for WktError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid WKT at offset {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for WktError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WktLiteral {
+    /// The CRS IRI given with the geometry, if any.
+    pub crs: Option<String>,
+    pub geometry: Geometry<f64>,
+}
+
+impl WktLiteral {
+    /// The CRS of the geometry, defaulting to CRS84, or `None` if it is not supported.
+    pub fn crs(&self) -> Option<Crs> {
+        match self.crs.as_deref() {
+            None => Some(Crs::Crs84),
+            Some(iri) => Crs::from_iri(iri).or_else(|| {
+                log::warn!("Unsupported CRS {iri}");
+                None
+            }),
+        }
+    }
+
+    /// The geometry with longitude as x and latitude as y, or `None` if its CRS is not
+    /// supported.
+    pub fn into_lon_lat(self) -> Option<Geometry<f64>> {
+        Some(self.crs()?.reproject(&self.geometry, &Crs::Crs84))
+    }
+}
+
+impl From<Geometry<f64>> for WktLiteral {
+    fn from(geometry: Geometry<f64>) -> Self {
+        Self {
+            crs: None,
+            geometry,
+        }
+    }
+}
+
+/// Writes the literal as WKT, preceded by its CRS IRI if any.
+impl fmt::Display for WktLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(crs) = &self.crs {
+            write!(f, "<{crs}> ")?;
+        }
+        write_geometry(f, &self.geometry)
+    }
+}
+
+fn write_geometry(f: &mut fmt::Formatter<'_>, geometry: &Geometry<f64>) -> fmt::Result {
+    match geometry {
+        Geometry::Point(point) => write!(f, "POINT ({} {})", point.x(), point.y()),
+        Geometry::Line(line) => {
+            write!(f, "LINESTRING ")?;
+            write_coords(f, [line.start, line.end].iter())
+        }
+        Geometry::LineString(line) => {
+            write!(f, "LINESTRING ")?;
+            write_coords(f, line.0.iter())
+        }
+        Geometry::Polygon(polygon) => {
+            write!(f, "POLYGON ")?;
+            write_polygon(f, polygon)
+        }
+        Geometry::MultiPoint(points) => {
+            write!(f, "MULTIPOINT ")?;
+            write_coords(f, points.iter().map(|point| &point.0))
+        }
+        Geometry::MultiLineString(lines) => {
+            write!(f, "MULTILINESTRING ")?;
+            write_list(f, &lines.0, |f, line| write_coords(f, line.0.iter()))
+        }
+        Geometry::MultiPolygon(polygons) => {
+            write!(f, "MULTIPOLYGON ")?;
+            write_list(f, &polygons.0, write_polygon)
+        }
+        Geometry::GeometryCollection(collection) => {
+            write!(f, "GEOMETRYCOLLECTION ")?;
+            write_list(f, &collection.0, write_geometry)
+        }
+        Geometry::Rect(rect) => write_geometry(f, &rect.to_polygon().into()),
+        Geometry::Triangle(triangle) => write_geometry(f, &triangle.to_polygon().into()),
+    }
+}
+
+/// Writes a parenthesised, comma separated list, or `EMPTY` for none.
+fn write_list<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    mut item: impl FnMut(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    if items.is_empty() {
+        return write!(f, "EMPTY");
+    }
+    write!(f, "(")?;
+    for (i, value) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        item(f, value)?;
+    }
+    write!(f, ")")
+}
+
+fn write_coords<'a>(
+    f: &mut fmt::Formatter<'_>,
+    coords: impl Iterator<Item = &'a Coord<f64>>,
+) -> fmt::Result {
+    let coords = coords.collect::<Vec<_>>();
+    write_list(f, &coords, |f, coord| write!(f, "{} {}", coord.x, coord.y))
+}
+
+fn write_polygon(f: &mut fmt::Formatter<'_>, polygon: &Polygon<f64>) -> fmt::Result {
+    if polygon.exterior().0.is_empty() {
+        return write!(f, "EMPTY");
+    }
+    let rings = std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .collect::<Vec<_>>();
+    write_list(f, &rings, |f, ring| write_coords(f, ring.0.iter()))
+}
+
+impl FromStr for WktLiteral {
+    type Err = WktError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: s, pos: 0 };
+        parser.skip_ws();
+        let crs = if parser.eat('<') {
+            let end = parser.input[parser.pos..]
+                .find('>')
+                .ok_or_else(|| parser.error("unterminated CRS IRI"))?;
+            let crs = parser.input[parser.pos..parser.pos + end].to_string();
+            parser.pos += end + 1;
+            Some(crs)
+        } else {
+            None
+        };
+        let geometry = parser.geometry()?;
+        parser.skip_ws();
+        if parser.pos < parser.input.len() {
+            return Err(parser.error("unexpected text after the geometry"));
+        }
+        Ok(Self { crs, geometry })
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> WktError {
+        WktError {
+            offset: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), WktError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    fn word(&mut self) -> String {
+        self.skip_ws();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        self.input[start..self.pos].to_uppercase()
+    }
+
+    /// Skips an optional dimension tag, then consumes `EMPTY` if it follows, returning whether
+    /// the geometry is empty.
+    fn empty(&mut self) -> bool {
+        let start = self.pos;
+        if !matches!(self.word().as_str(), "Z" | "M" | "ZM") {
+            self.pos = start;
+        }
+        let tagged = self.pos;
+        if self.word() == "EMPTY" {
+            true
+        } else {
+            self.pos = tagged;
+            false
+        }
+    }
+
+    fn geometry(&mut self) -> Result<Geometry<f64>, WktError> {
+        let start = self.pos;
+        let tag = self.word();
+        let empty = self.empty();
+        Ok(match tag.as_str() {
+            "POINT" if empty => Geometry::GeometryCollection(GeometryCollection::default()),
+            "POINT" => {
+                self.expect('(')?;
+                let coord = self.coord()?;
+                self.expect(')')?;
+                Point::from(coord).into()
+            }
+            "LINESTRING" if empty => LineString::new(Vec::new()).into(),
+            "LINESTRING" => self.line_string()?.into(),
+            "POLYGON" if empty => Polygon::new(LineString::new(Vec::new()), Vec::new()).into(),
+            "POLYGON" => self.polygon()?.into(),
+            "MULTIPOINT" if empty => MultiPoint::new(Vec::new()).into(),
+            "MULTIPOINT" => MultiPoint::new(self.list(|p| {
+                // Both `MULTIPOINT (1 2, 3 4)` and `MULTIPOINT ((1 2), (3 4))` are common.
+                let parenthesised = p.eat('(');
+                let coord = p.coord()?;
+                if parenthesised {
+                    p.expect(')')?;
+                }
+                Ok(Point::from(coord))
+            })?)
+            .into(),
+            "MULTILINESTRING" if empty => MultiLineString::new(Vec::new()).into(),
+            "MULTILINESTRING" => MultiLineString::new(self.list(Self::line_string)?).into(),
+            "MULTIPOLYGON" if empty => MultiPolygon::new(Vec::new()).into(),
+            "MULTIPOLYGON" => MultiPolygon::new(self.list(Self::polygon)?).into(),
+            "GEOMETRYCOLLECTION" if empty => {
+                Geometry::GeometryCollection(GeometryCollection::default())
+            }
+            "GEOMETRYCOLLECTION" => Geometry::GeometryCollection(GeometryCollection::new_from(
+                self.list(Self::geometry)?,
+            )),
+            _ => {
+                self.pos = start;
+                return Err(self.error("expected a geometry type"));
+            }
+        })
+    }
+
+    /// Parses a parenthesised, comma separated list.
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, WktError>,
+    ) -> Result<Vec<T>, WktError> {
+        self.expect('(')?;
+        let mut items = vec![item(self)?];
+        while self.eat(',') {
+            items.push(item(self)?);
+        }
+        self.expect(')')?;
+        Ok(items)
+    }
+
+    fn line_string(&mut self) -> Result<LineString<f64>, WktError> {
+        Ok(LineString::new(self.list(Self::coord)?))
+    }
+
+    fn polygon(&mut self) -> Result<Polygon<f64>, WktError> {
+        let mut rings = self.list(Self::line_string)?.into_iter();
+        let exterior = rings.next().unwrap_or_else(|| LineString::new(Vec::new()));
+        Ok(Polygon::new(exterior, rings.collect()))
+    }
+
+    /// Parses a position, keeping only its first two ordinates.
+    fn coord(&mut self) -> Result<Coord<f64>, WktError> {
+        let x = self.number()?;
+        let y = self.number()?;
+        // Z and M
+        for _ in 0..2 {
+            self.skip_ws();
+            if self.peek().is_some_and(|c| c != ',' && c != ')') {
+                self.number()?;
+            }
+        }
+        Ok(Coord { x, y })
+    }
+
+    fn number(&mut self) -> Result<f64, WktError> {
+        self.skip_ws();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'))
+        {
+            self.pos += 1;
+        }
+        self.input[start..self.pos].parse().map_err(|_| {
+            self.pos = start;
+            self.error("expected a number")
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_wkt() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                "<http://www.opengis.net/def/crs/EPSG/0/4326> point z (-34 150.5 10)"
+                    .parse::<WktLiteral>()
+                    .unwrap()
+            ),
+            "WktLiteral { crs: Some(\"http://www.opengis.net/def/crs/EPSG/0/4326\"), geometry: Point(Point(Coord { x: -34.0, y: 150.5 })) }"
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                "MULTIPOINT ((1 2), (3 4))"
+                    .parse::<WktLiteral>()
+                    .unwrap()
+                    .geometry
+            ),
+            format!(
+                "{:?}",
+                "MULTIPOINT (1 2, 3 4)"
+                    .parse::<WktLiteral>()
+                    .unwrap()
+                    .geometry
+            )
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                "GEOMETRYCOLLECTION (POLYGON ((0 0, 1 0, 1 1, 0 0), (0.2 0.1, 0.8 0.1, 0.8 0.7, 0.2 0.1)), LINESTRING EMPTY)"
+                    .parse::<WktLiteral>()
+                    .unwrap()
+                    .geometry
+            ),
+            "GeometryCollection(GeometryCollection([Polygon(Polygon { exterior: LineString([Coord { x: 0.0, y: 0.0 }, Coord { x: 1.0, y: 0.0 }, Coord { x: 1.0, y: 1.0 }, Coord { x: 0.0, y: 0.0 }]), interiors: [LineString([Coord { x: 0.2, y: 0.1 }, Coord { x: 0.8, y: 0.1 }, Coord { x: 0.8, y: 0.7 }, Coord { x: 0.2, y: 0.1 }])] }), LineString(LineString([]))]))"
+        );
+        // Spaces beyond ASCII, e.g. from copying and pasting, are whitespace too.
+        assert_eq!(
+            "POINT\u{a0}(1\u{2003}2)"
+                .parse::<WktLiteral>()
+                .unwrap()
+                .geometry,
+            Point::new(1.0, 2.0).into()
+        );
+        assert_eq!(
+            "LINESTRING (1 2, 3)"
+                .parse::<WktLiteral>()
+                .unwrap_err()
+                .to_string(),
+            "invalid WKT at offset 18: expected a number"
+        );
+    }
+
+    #[test]
+    fn test_write_wkt() {
+        for wkt in [
+            "<http://www.opengis.net/def/crs/EPSG/0/4326> POINT (-34 150.5)",
+            "MULTIPOINT ((1 2), (3 4))",
+            "GEOMETRYCOLLECTION (POLYGON ((0 0, 1 0, 1 1, 0 0), (0.2 0.1, 0.8 0.1, 0.8 0.7, 0.2 0.1)), LINESTRING EMPTY)",
+        ] {
+            let literal = wkt.parse::<WktLiteral>().unwrap();
+            assert_eq!(literal.to_string().parse::<WktLiteral>(), Ok(literal));
+        }
+        assert_eq!(
+            WktLiteral::from(Geometry::Rect(geo::Rect::new(
+                Coord { x: 145.0, y: -17.5 },
+                Coord { x: 146.0, y: -16.5 },
+            )))
+            .to_string(),
+            "POLYGON ((145 -17.5, 145 -16.5, 146 -16.5, 146 -17.5, 145 -17.5))"
+        );
+    }
+
+    #[test]
+    fn test_into_lon_lat() {
+        let swapped = "<http://www.opengis.net/def/crs/EPSG/0/4326> POINT (-34 150.5)"
+            .parse::<WktLiteral>()
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", swapped.into_lon_lat()),
+            "Some(Point(Point(Coord { x: 150.5, y: -34.0 })))"
+        );
+
+        let web_mercator = "<http://www.opengis.net/def/crs/EPSG/0/3857> POINT (0 0)"
+            .parse::<WktLiteral>()
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", web_mercator.into_lon_lat()),
+            "Some(Point(Point(Coord { x: 0.0, y: 0.0 })))"
+        );
+
+        let unsupported = "<http://www.opengis.net/def/crs/EPSG/0/2193> POINT (0 0)"
+            .parse::<WktLiteral>()
+            .unwrap();
+        assert_eq!(unsupported.into_lon_lat(), None);
+    }
+}
@@ -0,0 +1,94 @@
+//! Serves the stored queries of `kg_ui_toolkit::apis::local` over HTTP for offline demos.
+//!
+//! Usage: `kg-local-sparql [--port <port>] <file.ttl>...`
+//!
+//! Point the application's API path at `http://localhost:<port>/<database>`; the database
+//! name is ignored.
+
+use std::{
+    env,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    process,
+};
+
+use kg_ui_toolkit::apis::local::{self, Dataset};
+
+const DEFAULT_PORT: u16 = 5820;
+
+fn main() {
+    let mut port = DEFAULT_PORT;
+    let mut dataset = Dataset::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--port" {
+            port = args.next().and_then(|p| p.parse().ok()).unwrap_or_else(|| {
+                eprintln!("--port requires a port number");
+                process::exit(2);
+            });
+        } else if let Err(e) = dataset.load_file(&arg) {
+            eprintln!("{arg}: {e}");
+            process::exit(1);
+        }
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|e| {
+        eprintln!("Cannot listen on port {port}: {e}");
+        process::exit(1);
+    });
+    eprintln!(
+        "Serving {} triples on http://localhost:{port}/",
+        dataset.triples().len()
+    );
+
+    for stream in listener.incoming().flatten() {
+        if let Err(e) = handle(&dataset, stream) {
+            eprintln!("{e}");
+        }
+    }
+}
+
+fn handle(dataset: &Dataset, mut stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers; we only ever answer GET requests.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    eprintln!("{method} {target}");
+
+    let (status, body) = match method {
+        "OPTIONS" => ("204 No Content", String::new()),
+        "GET" => match target
+            .split_once('?')
+            .filter(|(path, _)| path.ends_with("/query"))
+            .and_then(|(_, query_string)| local::parse_query_string(query_string))
+        {
+            Some(query) => match dataset.query(&query) {
+                Ok(results) => ("200 OK", results.to_string()),
+                Err(e) => ("400 Bad Request", e.to_string()),
+            },
+            None => ("404 Not Found", String::new()),
+        },
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Headers: Accept, Authorization\r\n\
+         Content-Type: application/sparql-results+json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {body}",
+        body.len()
+    )
+}
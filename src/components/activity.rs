@@ -2,7 +2,9 @@ use implicit_clone::unsync::IString;
 use yew::prelude::*;
 
 use crate::{
-    components::{onclick_anchor_handler, DateTime, GenericProperties, ProvenanceLinks},
+    components::{
+        onclick_anchor_handler, DateTime, GenericProperties, ProvenanceLinks, StaleNotice,
+    },
     models,
 };
 
@@ -15,6 +17,9 @@ pub enum Message {
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub activity: models::Activity,
+    /// When the activity was served stale from the cache, the time at which it was fetched.
+    #[prop_or_default]
+    pub stale: Option<chrono::DateTime<chrono::Utc>>,
     #[prop_or_default]
     pub on_activity_click: Option<Callback<IString>>,
     #[prop_or_default]
@@ -62,6 +67,7 @@ impl Component for Activity {
             <form>
                 <fieldset>
                     <legend>{format!("Activity: {}", ctx.props().activity.label.clone().unwrap_or(AttrValue::from("<unknown>")))}</legend>
+                    <StaleNotice fetched_at={ctx.props().stale} />
                     <DateTime id="started-at" label="Started at" value={ctx.props().activity.started_at} />
                     <DateTime id="ended-at" label="Ended at" value={ctx.props().activity.ended_at} />
                     <GenericProperties properties={ctx.props().activity.properties.clone()} />
@@ -2,7 +2,7 @@ use implicit_clone::unsync::IString;
 use yew::prelude::*;
 
 use crate::{
-    components::{onclick_anchor_handler, GenericProperties, ProvenanceLinks},
+    components::{onclick_anchor_handler, GenericProperties, ProvenanceLinks, StaleNotice},
     models,
 };
 
@@ -15,6 +15,9 @@ pub enum Message {
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub agent: models::Agent,
+    /// When the agent was served stale from the cache, the time at which it was fetched.
+    #[prop_or_default]
+    pub stale: Option<chrono::DateTime<chrono::Utc>>,
     #[prop_or_default]
     pub on_activity_click: Option<Callback<IString>>,
     #[prop_or_default]
@@ -62,6 +65,7 @@ impl Component for Agent {
             <form>
                 <fieldset>
                     <legend>{format!("Agent: {}", ctx.props().agent.label.clone().unwrap_or(AttrValue::from("<unknown>")))}</legend>
+                    <StaleNotice fetched_at={ctx.props().stale} />
                     <GenericProperties properties={ctx.props().agent.properties.clone()} />
                     <ProvenanceLinks id_prefix="influenced" label="Influenced" links={ctx.props().agent.influenced.clone()} onclick={onclick_anchor_handler(ctx.link(), Message::ActivityClicked)} />
                 </fieldset>
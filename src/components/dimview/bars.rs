@@ -0,0 +1,188 @@
+//! Bar charts of the values of dimensions, with a group of bars for each dimension or a stack.
+
+use yew::prelude::*;
+
+use super::{describe, dimensions, legend, normalised, series, DimSeries, Props};
+use crate::models;
+
+const WIDTH: f32 = 400.0;
+const HEIGHT: f32 = 200.0;
+const MARGIN: f32 = 20.0;
+/// Room beneath the bars for the names of the dimensions.
+const LABEL_HEIGHT: f32 = 20.0;
+const PLOT_HEIGHT: f32 = HEIGHT - MARGIN - LABEL_HEIGHT;
+
+/// The value of a series in a dimension, as drawn.
+#[derive(Clone, Debug, PartialEq)]
+struct Bar {
+    series: usize,
+    dimension: usize,
+    value: f32,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Where the bars go. Values are normalised to their ranges, so that better values are taller
+/// whatever their units. Stacks are as tall as the chart when every series is at its best.
+fn bars(dim_desc: &[models::DimDesc], series: &[DimSeries], stacked: bool) -> Vec<Bar> {
+    let group_width = (WIDTH - MARGIN * 2.0) / dim_desc.len().max(1) as f32;
+    let count = series.len().max(1) as f32;
+    let mut bars = Vec::new();
+    for (j, dd) in dim_desc.iter().enumerate() {
+        let group_x = MARGIN + group_width * j as f32;
+        let mut stack_height = 0.0;
+        for (i, s) in series.iter().enumerate() {
+            let Some(value) = s.values[j] else {
+                continue;
+            };
+            let fraction = normalised(dd, value);
+            let (x, width, height, bottom) = if stacked {
+                let height = fraction * PLOT_HEIGHT / count;
+                stack_height += height;
+                (
+                    group_x + group_width * 0.2,
+                    group_width * 0.6,
+                    height,
+                    stack_height,
+                )
+            } else {
+                let width = group_width * 0.8 / count;
+                let height = fraction * PLOT_HEIGHT;
+                (
+                    group_x + group_width * 0.1 + width * i as f32,
+                    width,
+                    height,
+                    height,
+                )
+            };
+            bars.push(Bar {
+                series: i,
+                dimension: j,
+                value,
+                x,
+                y: MARGIN + PLOT_HEIGHT - bottom,
+                width,
+                height,
+            });
+        }
+    }
+    bars
+}
+
+fn bar_chart(props: &Props, stacked: bool) -> Html {
+    if props.dim_desc.is_empty() {
+        return html!();
+    }
+    let dim_desc = dimensions(props);
+    let series = series(props, &dim_desc);
+    let group_width = (WIDTH - MARGIN * 2.0) / dim_desc.len() as f32;
+    let baseline = (MARGIN + PLOT_HEIGHT).to_string();
+    html! {
+        <div class={if stacked { "dimview-bars stacked" } else { "dimview-bars" }}>
+            <svg class="chart" viewBox={format!("0 0 {WIDTH} {HEIGHT}")} font-size="10">
+                // The target range of each dimension, behind its bars. Stacks have no target.
+                if !stacked {
+                    { dim_desc.iter().enumerate().filter_map(|(j, dd)| {
+                        let target_range = dd.target_range.as_ref()?;
+                        let (start, end) = (normalised(dd, target_range.start), normalised(dd, target_range.end));
+                        let top = MARGIN + PLOT_HEIGHT * (1.0 - start.max(end));
+                        Some(html! {
+                            <rect
+                                class="dimview-target"
+                                x={(MARGIN + group_width * j as f32).to_string()}
+                                y={top.to_string()}
+                                width={group_width.to_string()}
+                                height={(PLOT_HEIGHT * (start - end).abs()).to_string()}
+                                fill="#4daf4a"
+                                fill-opacity="0.2"
+                            />
+                        })
+                    }).collect::<Html>() }
+                }
+                { bars(&dim_desc, &series, stacked).into_iter().map(|bar| html! {
+                    <rect
+                        x={bar.x.to_string()}
+                        y={bar.y.to_string()}
+                        width={bar.width.to_string()}
+                        height={bar.height.to_string()}
+                        fill={series[bar.series].color.clone()}
+                        stroke="white"
+                        stroke-width="0.5"
+                    >
+                        <title>{describe(&series[bar.series], &dim_desc[bar.dimension], bar.value)}</title>
+                    </rect>
+                }).collect::<Html>() }
+                <line x1={MARGIN.to_string()} y1={baseline.clone()} x2={(WIDTH - MARGIN).to_string()} y2={baseline} stroke="black" />
+                { dim_desc.iter().enumerate().map(|(j, dd)| html! {
+                    <text
+                        x={(MARGIN + group_width * (j as f32 + 0.5)).to_string()}
+                        y={(HEIGHT - LABEL_HEIGHT / 2.0).to_string()}
+                        text-anchor="middle"
+                        dominant-baseline="middle"
+                    >
+                        {dd.name.clone()}
+                    </text>
+                }).collect::<Html>() }
+            </svg>
+            if props.legend {
+                {legend(&series)}
+            }
+        </div>
+    }
+}
+
+/// The values of each dimension as bars side by side, one for each series.
+#[function_component]
+pub fn GroupedBars(props: &Props) -> Html {
+    bar_chart(props, false)
+}
+
+/// The values of each dimension as one bar, with a segment for each series.
+#[function_component]
+pub fn StackedBars(props: &Props) -> Html {
+    bar_chart(props, true)
+}
+
+#[cfg(test)]
+mod test {
+    use implicit_clone::unsync::IString;
+
+    use super::*;
+
+    #[test]
+    fn test_bars() {
+        let dd = |name: &'static str, inverted| models::DimDesc {
+            name: name.into(),
+            order: 1,
+            range: 0.0..4.0,
+            target_range: None,
+            inverted,
+        };
+        let dim_desc = [dd("Cost", true), dd("Coverage", false)];
+        let series = [
+            DimSeries {
+                name: IString::from("Path A"),
+                color: IString::from("red"),
+                values: vec![Some(1.0), Some(4.0)],
+            },
+            DimSeries {
+                name: IString::from("Path B"),
+                color: IString::from("blue"),
+                values: vec![None, Some(2.0)],
+            },
+        ];
+
+        let grouped = bars(&dim_desc, &series, false);
+        assert_eq!(grouped.len(), 3);
+        // A low cost is good, so the bar is three quarters of the height.
+        assert_eq!(grouped[0].height, PLOT_HEIGHT * 0.75);
+        assert_eq!(grouped[1].y, MARGIN);
+        assert_eq!(grouped[2].x - grouped[1].x, grouped[1].width);
+
+        let stacked = bars(&dim_desc, &series, true);
+        assert_eq!(stacked[1].height + stacked[2].height, PLOT_HEIGHT * 0.75);
+        assert_eq!(stacked[2].y + stacked[2].height, stacked[1].y);
+    }
+}
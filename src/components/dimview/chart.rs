@@ -0,0 +1,160 @@
+//! A chart of the values of dimensions that can be switched between the kinds of chart.
+
+use implicit_clone::unsync::{IArray, IString};
+use yew::prelude::*;
+
+use super::{DimHeatmap, DimView, GroupedBars, ParallelCoordinates, Props, StackedBars};
+use crate::models;
+
+/// The radar chart gets hard to read with more dimensions than this.
+const MAX_RADAR_DIMENSIONS: usize = 6;
+/// The radar chart gets hard to read with more series than this.
+const MAX_RADAR_SERIES: usize = 3;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChartType {
+    Radar,
+    ParallelCoordinates,
+    GroupedBars,
+    StackedBars,
+    Heatmap,
+}
+
+impl ChartType {
+    pub const ALL: [ChartType; 5] = [
+        ChartType::Radar,
+        ChartType::ParallelCoordinates,
+        ChartType::GroupedBars,
+        ChartType::StackedBars,
+        ChartType::Heatmap,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChartType::Radar => "Radar",
+            ChartType::ParallelCoordinates => "Parallel coordinates",
+            ChartType::GroupedBars => "Grouped bars",
+            ChartType::StackedBars => "Stacked bars",
+            ChartType::Heatmap => "Heatmap",
+        }
+    }
+
+    /// The radar chart while it stays readable, and parallel coordinates otherwise.
+    pub fn suggested(dimensions: usize, series: usize) -> Self {
+        if dimensions <= MAX_RADAR_DIMENSIONS && series <= MAX_RADAR_SERIES {
+            ChartType::Radar
+        } else {
+            ChartType::ParallelCoordinates
+        }
+    }
+}
+
+pub enum Message {
+    Show(ChartType),
+}
+
+/// The props of [`DimView`], with the chart to show.
+#[derive(Properties, PartialEq)]
+pub struct DimChartProps {
+    pub dim_desc: IArray<models::DimDesc>,
+    pub dim_values: IArray<(IString, IArray<models::DimValue>)>,
+    #[prop_or_default]
+    pub palette: Option<IArray<IString>>,
+    #[prop_or(true)]
+    pub legend: bool,
+    #[prop_or_default]
+    pub on_brush: Option<Callback<IArray<IString>>>,
+    /// The kind of chart shown. When given, it only changes through this prop, so
+    /// `on_chart_change` should be used to keep it up to date. Otherwise it is suggested from
+    /// the number of dimensions and series at first.
+    #[prop_or_default]
+    pub chart: Option<ChartType>,
+    /// Called with the kind of chart chosen by the user.
+    #[prop_or_default]
+    pub on_chart_change: Option<Callback<ChartType>>,
+}
+
+/// Buttons for each kind of chart above the chart chosen.
+pub struct DimChart {
+    /// The chart chosen when it is not controlled by the `chart` prop.
+    chart: ChartType,
+}
+
+impl DimChart {
+    fn chart(&self, ctx: &Context<Self>) -> ChartType {
+        ctx.props().chart.unwrap_or(self.chart)
+    }
+}
+
+impl Component for DimChart {
+    type Message = Message;
+
+    type Properties = DimChartProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let props = ctx.props();
+        Self {
+            chart: ChartType::suggested(props.dim_desc.len(), props.dim_values.len()),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::Show(chart) => {
+                if let Some(on_chart_change) = &ctx.props().on_chart_change {
+                    on_chart_change.emit(chart);
+                }
+                self.chart = chart;
+                ctx.props().chart.is_none()
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let current = self.chart(ctx);
+        let chart_props = Props {
+            dim_desc: props.dim_desc.clone(),
+            dim_values: props.dim_values.clone(),
+            palette: props.palette.clone(),
+            legend: props.legend,
+            on_brush: props.on_brush.clone(),
+        };
+        let chart = match current {
+            ChartType::Radar => html! { <DimView ..chart_props /> },
+            ChartType::ParallelCoordinates => html! { <ParallelCoordinates ..chart_props /> },
+            ChartType::GroupedBars => html! { <GroupedBars ..chart_props /> },
+            ChartType::StackedBars => html! { <StackedBars ..chart_props /> },
+            ChartType::Heatmap => html! { <DimHeatmap ..chart_props /> },
+        };
+        html! {
+            <div class="dimchart">
+                <div class="chart-types" role="group" aria-label="Chart type">
+                    { ChartType::ALL.into_iter().map(|chart| html! {
+                        <button
+                            type="button"
+                            aria-pressed={(chart == current).to_string()}
+                            onclick={ctx.link().callback(move |_| Message::Show(chart))}
+                        >
+                            {chart.name()}
+                        </button>
+                    }).collect::<Html>() }
+                </div>
+                {chart}
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_suggested() {
+        assert_eq!(ChartType::suggested(3, 2), ChartType::Radar);
+        assert_eq!(ChartType::suggested(6, 3), ChartType::Radar);
+        assert_eq!(ChartType::suggested(7, 2), ChartType::ParallelCoordinates);
+        assert_eq!(ChartType::suggested(3, 4), ChartType::ParallelCoordinates);
+    }
+}
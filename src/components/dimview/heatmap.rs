@@ -0,0 +1,98 @@
+//! A table of the values of dimensions, with a row for each series and cells shaded by how good
+//! the values are.
+
+use yew::prelude::*;
+
+use super::{describe, dimensions, format_range, normalised, series, Props};
+use crate::{components::spatial_entities::Rgb, models};
+
+/// The shade of the best values, from ColorBrewer's Blues.
+const BEST: Rgb = Rgb(0x08, 0x51, 0x9c);
+const WORST: Rgb = Rgb(0xff, 0xff, 0xff);
+
+/// The background and text colours of a cell with a normalised value.
+fn cell_colors(fraction: f32) -> (Rgb, &'static str) {
+    let background = WORST.lerp(BEST, fraction as f64);
+    (background, if fraction > 0.5 { "white" } else { "black" })
+}
+
+fn on_target(dd: &models::DimDesc, value: f32) -> bool {
+    dd.target_range
+        .as_ref()
+        .is_some_and(|target_range| target_range.start <= value && value <= target_range.end)
+}
+
+/// The values of each series in a row, with a column for each dimension. Values within their
+/// target range are outlined.
+#[function_component]
+pub fn DimHeatmap(props: &Props) -> Html {
+    if props.dim_desc.is_empty() {
+        return html!();
+    }
+    let dim_desc = dimensions(props);
+    let series = series(props, &dim_desc);
+    html! {
+        <table class="dimview-heatmap">
+            <thead>
+                <tr>
+                    <td />
+                    { dim_desc.iter().map(|dd| html! {
+                        <th scope="col" title={format_range(&dd.range)}>{dd.name.clone()}</th>
+                    }).collect::<Html>() }
+                </tr>
+            </thead>
+            <tbody>
+                { series.iter().map(|s| html! {
+                    <tr>
+                        <th scope="row">
+                            if props.legend {
+                                <svg width="12" height="12" aria-hidden="true">
+                                    <rect width="12" height="12" fill={s.color.clone()} />
+                                </svg>
+                            }
+                            {s.name.clone()}
+                        </th>
+                        { s.values.iter().zip(&dim_desc).map(|(value, dd)| match value {
+                            Some(value) => {
+                                let (background, color) = cell_colors(normalised(dd, *value));
+                                let on_target = on_target(dd, *value);
+                                let outline = if on_target { "; outline: 2px solid #4daf4a; outline-offset: -2px" } else { "" };
+                                html! {
+                                    <td
+                                        class={classes!(on_target.then_some("on-target"))}
+                                        style={format!("background-color: {background}; color: {color}{outline}")}
+                                        title={describe(s, dd, *value)}
+                                    >
+                                        {value.to_string()}
+                                    </td>
+                                }
+                            }
+                            None => html! { <td class="missing">{"–"}</td> },
+                        }).collect::<Html>() }
+                    </tr>
+                }).collect::<Html>() }
+            </tbody>
+        </table>
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cells() {
+        assert_eq!(cell_colors(0.0), (WORST, "black"));
+        assert_eq!(cell_colors(1.0), (BEST, "white"));
+
+        let dd = models::DimDesc {
+            name: "Depth".into(),
+            order: 1,
+            range: 0.0..5.0,
+            target_range: Some(2.0..4.0),
+            inverted: false,
+        };
+        assert!(on_target(&dd, 4.0));
+        assert!(!on_target(&dd, 1.5));
+    }
+}
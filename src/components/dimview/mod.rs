@@ -0,0 +1,395 @@
+use std::{f32::consts::PI, ops::Range, rc::Rc};
+
+use implicit_clone::unsync::{IArray, IString};
+use yew::prelude::*;
+use yew_chart::{
+    axis::Scale,
+    linear_axis_scale::LinearScale,
+    series::{self, Data, Labeller, Series, Type},
+};
+
+use crate::{components::spatial_entities::SET1, models, scoring::normalised};
+
+mod bars;
+mod chart;
+mod heatmap;
+mod parallel;
+mod ranking;
+
+pub use bars::{GroupedBars, StackedBars};
+pub use chart::{ChartType, DimChart, DimChartProps};
+pub use heatmap::DimHeatmap;
+pub use parallel::ParallelCoordinates;
+pub use ranking::DimRanking;
+
+pub enum Message {
+    /// The series and dimension of the point under the pointer or with focus, if any.
+    Hovered(Option<(usize, usize)>),
+}
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub dim_desc: IArray<models::DimDesc>,
+    pub dim_values: IArray<(IString, IArray<models::DimValue>)>,
+    /// The colours of the series in order of their names, repeated if there are more series.
+    /// Defaults to [`SET1`].
+    #[prop_or_default]
+    pub palette: Option<IArray<IString>>,
+    /// Whether to list the series with their colours beneath the chart.
+    #[prop_or(true)]
+    pub legend: bool,
+    /// Called with the names of the series within the ranges brushed on the axes of
+    /// [`ParallelCoordinates`], or of every series when none are.
+    #[prop_or_default]
+    pub on_brush: Option<Callback<IArray<IString>>>,
+}
+
+/// The values of an entity, as drawn.
+struct DimSeries {
+    name: IString,
+    color: IString,
+    /// The value of each dimension in order, if it has one.
+    values: Vec<Option<f32>>,
+}
+
+pub struct DimView {
+    hovered: Option<(usize, usize)>,
+}
+
+const WIDTH: f32 = 200.0;
+const HEIGHT: f32 = 200.0;
+const MARGIN: f32 = 20.0;
+/// The radius of the chart at the end of the range of each dimension.
+const RADIUS: f32 = (WIDTH - MARGIN * 2.0) / 2.0;
+
+/// The path of a segment of a ring around an axis at an angle, anticlockwise from the x axis,
+/// spanning `half_width` radians either side and from `inner` to `outer` as fractions of the
+/// radius.
+fn ring_segment(angle: f32, half_width: f32, inner: f32, outer: f32) -> String {
+    let point = |radius: f32, angle: f32| {
+        format!(
+            "{} {}",
+            WIDTH / 2.0 + radius * RADIUS * angle.cos(),
+            HEIGHT / 2.0 - radius * RADIUS * angle.sin()
+        )
+    };
+    let (start, end) = (angle - half_width, angle + half_width);
+    let large_arc = u8::from(half_width * 2.0 > PI);
+    format!(
+        "M {} A {outer_radius} {outer_radius} 0 {large_arc} 0 {} L {} A {inner_radius} {inner_radius} 0 {large_arc} 1 {} Z",
+        point(outer, start),
+        point(outer, end),
+        point(inner, end),
+        point(inner, start),
+        outer_radius = outer * RADIUS,
+        inner_radius = inner * RADIUS,
+    )
+}
+
+fn format_range(range: &Range<f32>) -> String {
+    format!("{} to {}", range.start, range.end)
+}
+
+/// What the tooltip of a value shows after the names of its series and dimension.
+fn tooltip_rows(dd: &models::DimDesc, value: f32) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Value", value.to_string()),
+        (
+            "Range",
+            if dd.inverted {
+                format!("{} (lower is better)", format_range(&dd.range))
+            } else {
+                format_range(&dd.range)
+            },
+        ),
+    ];
+    if let Some(target_range) = &dd.target_range {
+        rows.push(("Target", format_range(target_range)));
+    }
+    rows
+}
+
+/// The dimensions in order.
+fn dimensions(props: &Props) -> Vec<models::DimDesc> {
+    let mut dim_desc = props.dim_desc.to_vec();
+    dim_desc.sort_by_key(|dd| dd.order);
+    dim_desc
+}
+
+/// The series sorted by name, each with a colour from the palette and its values of the
+/// dimensions.
+fn series(props: &Props, dim_desc: &[models::DimDesc]) -> Vec<DimSeries> {
+    let palette = match &props.palette {
+        Some(palette) if !palette.is_empty() => palette.to_vec(),
+        _ => SET1.iter().map(|color| IString::from(*color)).collect(),
+    };
+    let mut dim_values = props.dim_values.to_vec();
+    dim_values.sort_by_key(|(series_name, _)| series_name.clone());
+    dim_values
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, dvs))| DimSeries {
+            name,
+            color: palette[i % palette.len()].clone(),
+            values: dim_desc
+                .iter()
+                .map(|dd| dvs.iter().find_map(|dv| (dv.d == dd.name).then_some(dv.v)))
+                .collect(),
+        })
+        .collect()
+}
+
+/// The series with their colours, as listed beneath a chart.
+fn legend(series: &[DimSeries]) -> Html {
+    html! {
+        <ul class="dimview-legend">
+            { series.iter().map(|series| html! {
+                <li>
+                    <svg width="12" height="12" aria-hidden="true">
+                        <rect width="12" height="12" fill={series.color.clone()} />
+                    </svg>
+                    {series.name.clone()}
+                </li>
+            }).collect::<Html>() }
+        </ul>
+    }
+}
+
+/// A value with the names of its series and dimension, its range and its target range, e.g. for
+/// an SVG `<title>`.
+fn describe(series: &DimSeries, dd: &models::DimDesc, value: f32) -> String {
+    std::iter::once(format!("{}: {}", series.name, dd.name))
+        .chain(
+            tooltip_rows(dd, value)
+                .into_iter()
+                .map(|(label, value)| format!("{label}: {value}")),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Component for DimView {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self { hovered: None }
+    }
+
+    fn changed(&mut self, _ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.hovered = None;
+        true
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::Hovered(hovered) => {
+                let changed = hovered != self.hovered;
+                self.hovered = hovered;
+                changed
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if !ctx.props().dim_desc.is_empty() {
+            let dim_desc = dimensions(ctx.props());
+
+            let labels = dim_desc.iter().map(|dd| dd.name.clone());
+
+            let data_labels = Rc::new(
+                series::to_radial(vec![1.0; dim_desc.len()])
+                    .into_iter()
+                    .zip(labels)
+                    .map(|((x, y, _), label)| {
+                        (
+                            x,
+                            y,
+                            Some(Rc::from(series::circle_text_label(label.to_string()))
+                                as Rc<dyn Labeller>),
+                        )
+                    })
+                    .flat_map(|d| [d.clone(), (0.0, 0.0, None), d])
+                    .collect(),
+            );
+
+            let data_by_series = series(ctx.props(), &dim_desc);
+            // Missing values are drawn at the centre, so that the others stay on their axes.
+            let radial = data_by_series
+                .iter()
+                .map(|series| {
+                    let scaled = series
+                        .values
+                        .iter()
+                        .zip(&dim_desc)
+                        .map(|(value, dd)| value.map_or(0.0, |value| normalised(dd, value)))
+                        .collect();
+                    Rc::new(series::to_radial(scaled)) as Rc<Data<f32, f32>>
+                })
+                .collect::<Vec<_>>();
+
+            let scale = Rc::new(LinearScale::new(-1.0..1.0, 1.0)) as Rc<dyn Scale<Scalar = _>>;
+
+            let cx = IString::from((WIDTH / 2.0).to_string());
+            let cy = IString::from((HEIGHT / 2.0).to_string());
+
+            // Where a point of a series is drawn, in the coordinates of the SVG.
+            let position = |x: f32, y: f32| {
+                (
+                    MARGIN + scale.normalise(x).0 * (WIDTH - MARGIN * 2.0),
+                    MARGIN + (1.0 - scale.normalise(y).0) * (HEIGHT - MARGIN * 2.0),
+                )
+            };
+
+            let tooltip = self.hovered.and_then(|(i, j)| {
+                let series = data_by_series.get(i)?;
+                let value = (*series.values.get(j)?)?;
+                let dd = &dim_desc[j];
+                let (x, y, _) = radial[i].get(j)?;
+                let (x, y) = position(*x, *y);
+                let style = format!(
+                    "position: absolute; left: {}%; top: {}%; pointer-events: none",
+                    x / WIDTH * 100.0,
+                    y / HEIGHT * 100.0
+                );
+                Some(html! {
+                    <div class="dimview-tooltip" role="tooltip" {style}>
+                        <div class="dimview-tooltip-label">{format!("{}: {}", series.name, dd.name)}</div>
+                        {
+                            tooltip_rows(dd, value).into_iter().map(|(label, value)| html! {
+                                <div>{label}{": "}{value}</div>
+                            }).collect::<Html>()
+                        }
+                    </div>
+                })
+            });
+
+            let legend = ctx.props().legend.then(|| legend(&data_by_series));
+
+            html! {
+                <div class="dimview" style="position: relative">
+                <svg class="chart" viewBox={format!("0 0 {} {}", WIDTH, HEIGHT)} preserveAspectRatio="none">
+                    {
+                        series::to_radial(vec![0.8; dim_desc.len()])
+                            .into_iter().map(|(x2, y2, _)| {
+                                html! {
+                                    <line x1={cx.clone()} y1={cy.clone()} x2={(scale.normalise(x2).0 * WIDTH).to_string()} y2={(scale.normalise(-y2).0 * HEIGHT).to_string()} />
+                                }
+                            }).collect::<Html>()
+                    }
+                    {
+                        (2..=4).map(|i| {
+                            let r = ((WIDTH / 2.0) - (MARGIN * i as f32)).to_string();
+                            html! {
+                                <circle cx={cx.clone()} cy={cy.clone()} r={r} />
+                            }
+                    }).collect::<Html>() }
+
+                    // The target range of each dimension, as a band across its axis.
+                    { dim_desc.iter().zip(series::to_radial(vec![1.0; dim_desc.len()])).filter_map(|(dd, (x, y, _))| {
+                        let target_range = dd.target_range.as_ref()?;
+                        let (start, end) = (normalised(dd, target_range.start), normalised(dd, target_range.end));
+                        let d = ring_segment(y.atan2(x), PI / dim_desc.len() as f32 * 0.6, start.min(end), start.max(end));
+                        Some(html! {
+                            <path class="dimview-target" {d} fill="#4daf4a" fill-opacity="0.2" stroke="none" />
+                        })
+                    }).collect::<Html>() }
+
+                    <Series<f32, f32>
+                        series_type={Type::Area}
+                        name="labels"
+                        data={data_labels}
+                        horizontal_scale={scale.clone()}
+                        vertical_scale={scale.clone()}
+                        x={MARGIN} y={MARGIN} width={WIDTH - (MARGIN * 2.0)} height={HEIGHT - (MARGIN * 2.0)} />
+
+                    { data_by_series.iter().enumerate().map(|(i, series)| {
+                        html! {
+                            <g fill={series.color.clone()} fill-opacity="0.25" stroke={series.color.clone()}>
+                                <Series<f32, f32>
+                                    series_type={Type::Area}
+                                    name={format!("series-name-{i}")}
+                                    data={radial[i].clone()}
+                                    horizontal_scale={scale.clone()}
+                                    vertical_scale={scale.clone()}
+                                    x={MARGIN} y={MARGIN} width={WIDTH - (MARGIN * 2.0)} height={HEIGHT - (MARGIN * 2.0)} />
+                            </g>
+                        }
+                    }).collect::<Html>() }
+
+                    // Points to hover or focus for the value of each dimension.
+                    { data_by_series.iter().enumerate().flat_map(|(i, series)| {
+                        radial[i].iter().zip(&series.values).enumerate().filter(|(_, (_, value))| value.is_some()).map(|(j, ((x, y, _), _))| {
+                            let (x, y) = position(*x, *y);
+                            let hovered = Some((i, j));
+                            let onmouseenter = ctx.link().callback(move |_: MouseEvent| Message::Hovered(hovered));
+                            let onfocus = ctx.link().callback(move |_: FocusEvent| Message::Hovered(hovered));
+                            let onmouseleave = ctx.link().callback(|_: MouseEvent| Message::Hovered(None));
+                            let onblur = ctx.link().callback(|_: FocusEvent| Message::Hovered(None));
+                            html! {
+                                <circle
+                                    class="dimview-point"
+                                    cx={x.to_string()}
+                                    cy={y.to_string()}
+                                    r="3"
+                                    fill={series.color.clone()}
+                                    tabindex="0"
+                                    aria-label={format!("{}: {}", series.name, dim_desc[j].name)}
+                                    {onmouseenter}
+                                    {onfocus}
+                                    {onmouseleave}
+                                    {onblur}
+                                />
+                            }
+                        }).collect::<Vec<_>>()
+                    }).collect::<Html>() }
+
+                </svg>
+                {tooltip}
+                {legend}
+                </div>
+            }
+        } else {
+            html!()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dimensions() {
+        let dd = models::DimDesc {
+            name: "Risk".into(),
+            order: 1,
+            range: 0.0..5.0,
+            target_range: Some(1.0..3.5),
+            inverted: false,
+        };
+        assert_eq!(
+            tooltip_rows(&dd, 2.5),
+            [
+                ("Value", "2.5".to_string()),
+                ("Range", "0 to 5".to_string()),
+                ("Target", "1 to 3.5".to_string())
+            ]
+        );
+
+        let dd = models::DimDesc {
+            range: 2.0..6.0,
+            ..dd
+        };
+        assert_eq!(normalised(&dd, 3.0), 0.25);
+        assert_eq!(normalised(&dd, 7.0), 1.0);
+        // Lower is better, so towards the edge.
+        let dd = models::DimDesc {
+            inverted: true,
+            ..dd
+        };
+        assert_eq!(normalised(&dd, 3.0), 0.75);
+        assert_eq!(tooltip_rows(&dd, 3.0)[1].1, "2 to 6 (lower is better)");
+    }
+}
@@ -0,0 +1,351 @@
+//! Parallel coordinates, with an axis for each dimension and a line for each series across
+//! them. Dragging along axes brushes ranges of values, fading the series outside them.
+
+use std::collections::BTreeMap;
+
+use implicit_clone::unsync::IArray;
+use web_sys::{wasm_bindgen::JsCast, Element};
+use yew::prelude::*;
+
+use super::{describe, dimensions, legend, normalised, series, DimSeries, Props};
+use crate::models;
+
+const WIDTH: f32 = 400.0;
+const HEIGHT: f32 = 200.0;
+const MARGIN: f32 = 30.0;
+/// Room above and below the axes for their names and the ends of their ranges.
+const LABEL_HEIGHT: f32 = 20.0;
+const TOP: f32 = LABEL_HEIGHT;
+const PLOT_HEIGHT: f32 = HEIGHT - LABEL_HEIGHT * 2.0;
+/// How far from an axis, in SVG units, a press starts brushing it.
+const AXIS_SLOP: f32 = 12.0;
+/// Brushes shorter than this fraction of an axis are taken as clicks, which clear the brush.
+const MIN_BRUSH: f32 = 0.02;
+
+/// The ranges brushed on each axis, as normalised values from low to high.
+type Brushes = BTreeMap<usize, (f32, f32)>;
+
+fn axis_x(dimension: usize, count: usize) -> f32 {
+    if count < 2 {
+        WIDTH / 2.0
+    } else {
+        MARGIN + (WIDTH - MARGIN * 2.0) * dimension as f32 / (count - 1) as f32
+    }
+}
+
+/// The height in the SVG of a normalised value, with better values higher.
+fn value_y(fraction: f32) -> f32 {
+    TOP + (1.0 - fraction) * PLOT_HEIGHT
+}
+
+/// Whether each series has a value within every brushed range.
+fn brushed(dim_desc: &[models::DimDesc], series: &[DimSeries], brushes: &Brushes) -> Vec<bool> {
+    series
+        .iter()
+        .map(|s| {
+            brushes.iter().all(|(j, (low, high))| {
+                s.values[*j].is_some_and(|value| {
+                    let fraction = normalised(&dim_desc[*j], value);
+                    *low <= fraction && fraction <= *high
+                })
+            })
+        })
+        .collect()
+}
+
+/// The line of a series through its values, broken where it has none.
+fn path(dim_desc: &[models::DimDesc], series: &DimSeries) -> String {
+    let mut path = String::new();
+    let mut pen_down = false;
+    for (j, value) in series.values.iter().enumerate() {
+        match value {
+            Some(value) => {
+                let command = if pen_down { 'L' } else { 'M' };
+                let y = value_y(normalised(&dim_desc[j], *value));
+                path.push_str(&format!("{command} {} {y} ", axis_x(j, dim_desc.len())));
+                pen_down = true;
+            }
+            None => pen_down = false,
+        }
+    }
+    path.trim_end().to_string()
+}
+
+/// The position of a pointer in the coordinates of the SVG it is over.
+fn pointer_position(event: &PointerEvent) -> Option<(f32, f32)> {
+    let element = event.current_target()?.dyn_into::<Element>().ok()?;
+    let rect = element.get_bounding_client_rect();
+    if rect.width() <= 0.0 || rect.height() <= 0.0 {
+        return None;
+    }
+    Some((
+        ((event.client_x() as f64 - rect.left()) / rect.width()) as f32 * WIDTH,
+        ((event.client_y() as f64 - rect.top()) / rect.height()) as f32 * HEIGHT,
+    ))
+}
+
+pub enum Message {
+    /// A press at a position in the SVG, which starts a brush if it is near an axis.
+    BrushStart(f32, f32),
+    /// The pointer moved to a height in the SVG.
+    BrushMove(f32),
+    BrushEnd,
+    ClearBrushes,
+}
+
+pub struct ParallelCoordinates {
+    brushes: Brushes,
+    /// The axis being brushed and the normalised value where the brush started.
+    brushing: Option<(usize, f32)>,
+}
+
+impl ParallelCoordinates {
+    fn emit_brushed(&self, ctx: &Context<Self>) {
+        let Some(on_brush) = &ctx.props().on_brush else {
+            return;
+        };
+        let dim_desc = dimensions(ctx.props());
+        let series = series(ctx.props(), &dim_desc);
+        let brushed = brushed(&dim_desc, &series, &self.brushes);
+        on_brush.emit(IArray::from(
+            series
+                .into_iter()
+                .zip(brushed)
+                .filter(|(_, brushed)| *brushed)
+                .map(|(s, _)| s.name)
+                .collect::<Vec<_>>(),
+        ));
+    }
+}
+
+impl Component for ParallelCoordinates {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            brushes: Brushes::new(),
+            brushing: None,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().dim_desc != old_props.dim_desc {
+            self.brushes.clear();
+            self.brushing = None;
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let fraction = |y: f32| (1.0 - (y - TOP) / PLOT_HEIGHT).clamp(0.0, 1.0);
+        match msg {
+            Message::BrushStart(x, y) => {
+                let count = ctx.props().dim_desc.len();
+                self.brushing = (0..count)
+                    .find(|j| (axis_x(*j, count) - x).abs() <= AXIS_SLOP)
+                    .map(|j| (j, fraction(y)));
+                false
+            }
+            Message::BrushMove(y) => {
+                let Some((j, start)) = self.brushing else {
+                    return false;
+                };
+                let end = fraction(y);
+                self.brushes.insert(j, (start.min(end), start.max(end)));
+                true
+            }
+            Message::BrushEnd => {
+                let Some((j, _)) = self.brushing.take() else {
+                    return false;
+                };
+                if self
+                    .brushes
+                    .get(&j)
+                    .is_none_or(|(low, high)| high - low < MIN_BRUSH)
+                {
+                    self.brushes.remove(&j);
+                }
+                self.emit_brushed(ctx);
+                true
+            }
+            Message::ClearBrushes => {
+                self.brushes.clear();
+                self.emit_brushed(ctx);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        if props.dim_desc.is_empty() {
+            return html!();
+        }
+        let dim_desc = dimensions(props);
+        let series = series(props, &dim_desc);
+        let brushed = brushed(&dim_desc, &series, &self.brushes);
+        let count = dim_desc.len();
+
+        let onpointerdown = ctx.link().batch_callback(|event: PointerEvent| {
+            if event.button() > 0 {
+                return None;
+            }
+            if let Some(element) = event
+                .current_target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+            {
+                let _ = element.set_pointer_capture(event.pointer_id());
+            }
+            let (x, y) = pointer_position(&event)?;
+            Some(Message::BrushStart(x, y))
+        });
+        let onpointermove = ctx.link().batch_callback(|event: PointerEvent| {
+            let (_, y) = pointer_position(&event)?;
+            Some(Message::BrushMove(y))
+        });
+        let onpointerup = ctx.link().callback(|_: PointerEvent| Message::BrushEnd);
+
+        let axes = dim_desc.iter().enumerate().map(|(j, dd)| {
+            let x = axis_x(j, count).to_string();
+            // The better end of the range is at the top.
+            let (best, worst) = if dd.inverted {
+                (dd.range.start, dd.range.end)
+            } else {
+                (dd.range.end, dd.range.start)
+            };
+            let target = dd.target_range.as_ref().map(|target_range| {
+                let (start, end) = (
+                    normalised(dd, target_range.start),
+                    normalised(dd, target_range.end),
+                );
+                html! {
+                    <rect
+                        class="dimview-target"
+                        x={(axis_x(j, count) - 4.0).to_string()}
+                        y={value_y(start.max(end)).to_string()}
+                        width="8"
+                        height={(PLOT_HEIGHT * (start - end).abs()).to_string()}
+                        fill="#4daf4a"
+                        fill-opacity="0.3"
+                    />
+                }
+            });
+            let brush = self.brushes.get(&j).map(|(low, high)| {
+                html! {
+                    <rect
+                        class="dimview-brush"
+                        x={(axis_x(j, count) - 8.0).to_string()}
+                        y={value_y(*high).to_string()}
+                        width="16"
+                        height={(PLOT_HEIGHT * (high - low)).to_string()}
+                        fill="black"
+                        fill-opacity="0.1"
+                        stroke="#333"
+                    />
+                }
+            });
+            html! {
+                <g class="dimview-axis">
+                    {target}
+                    <line x1={x.clone()} y1={TOP.to_string()} x2={x.clone()} y2={(TOP + PLOT_HEIGHT).to_string()} stroke="black" />
+                    <text x={x.clone()} y={(TOP - 8.0).to_string()} text-anchor="middle">{dd.name.clone()}</text>
+                    <text x={x.clone()} y={(TOP - 1.0).to_string()} text-anchor="middle" font-size="8">{best.to_string()}</text>
+                    <text x={x} y={(TOP + PLOT_HEIGHT + 9.0).to_string()} text-anchor="middle" font-size="8">{worst.to_string()}</text>
+                    {brush}
+                </g>
+            }
+        });
+
+        let lines = series.iter().zip(&brushed).map(|(s, brushed)| {
+            let title = s
+                .values
+                .iter()
+                .zip(&dim_desc)
+                .filter_map(|(value, dd)| Some(describe(s, dd, (*value)?)))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            html! {
+                <path
+                    class={classes!("dimview-series", (!brushed).then_some("faded"))}
+                    d={path(&dim_desc, s)}
+                    fill="none"
+                    stroke={s.color.clone()}
+                    stroke-width="2"
+                    stroke-opacity={if *brushed { "1" } else { "0.15" }}
+                >
+                    <title>{title}</title>
+                </path>
+            }
+        });
+
+        html! {
+            <div class="dimview-parallel">
+                <svg
+                    class="chart"
+                    viewBox={format!("0 0 {WIDTH} {HEIGHT}")}
+                    font-size="10"
+                    style="touch-action: none; cursor: crosshair"
+                    aria-label="Drag along an axis to filter by its values"
+                    {onpointerdown}
+                    {onpointermove}
+                    {onpointerup}
+                >
+                    { axes.collect::<Html>() }
+                    { lines.collect::<Html>() }
+                </svg>
+                if !self.brushes.is_empty() {
+                    <button type="button" class="clear-brushes" onclick={ctx.link().callback(|_| Message::ClearBrushes)}>
+                        {"Clear filters"}
+                    </button>
+                }
+                if props.legend {
+                    {legend(&series)}
+                }
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use implicit_clone::unsync::IString;
+
+    use super::*;
+
+    #[test]
+    fn test_brushed() {
+        let dim_desc = ["Foo", "Bar"].map(|name| models::DimDesc {
+            name: name.into(),
+            order: 1,
+            range: 0.0..5.0,
+            target_range: None,
+            inverted: false,
+        });
+        let series = [
+            DimSeries {
+                name: IString::from("Path A"),
+                color: IString::from("red"),
+                values: vec![Some(2.5), Some(1.25)],
+            },
+            DimSeries {
+                name: IString::from("Path B"),
+                color: IString::from("blue"),
+                values: vec![Some(0.0), None],
+            },
+        ];
+        assert_eq!(
+            path(&dim_desc, &series[0]),
+            format!("M {MARGIN} 100 L {} 140", WIDTH - MARGIN)
+        );
+        assert_eq!(path(&dim_desc, &series[1]), format!("M {MARGIN} 180"));
+
+        assert_eq!(brushed(&dim_desc, &series, &Brushes::new()), [true, true]);
+        let brushes = Brushes::from([(0, (0.5, 1.0))]);
+        assert_eq!(brushed(&dim_desc, &series, &brushes), [true, false]);
+        // A series without a value is outside any brush on its axis.
+        let brushes = Brushes::from([(1, (0.0, 1.0))]);
+        assert_eq!(brushed(&dim_desc, &series, &brushes), [true, false]);
+    }
+}
@@ -0,0 +1,182 @@
+//! Sliders for the weight of each dimension, with the alternatives ranked by their scores as the
+//! weights change.
+
+use std::collections::HashMap;
+
+use implicit_clone::unsync::{IArray, IString};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{
+    models,
+    scoring::{self, Method},
+};
+
+/// The weight of a dimension until it is changed.
+const DEFAULT_WEIGHT: f32 = 5.0;
+const MAX_WEIGHT: f32 = 10.0;
+
+pub enum Message {
+    /// Sets the weight of the dimension at a position, in order.
+    SetWeight(usize, f32),
+    SetMethod(Method),
+}
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub dim_desc: IArray<models::DimDesc>,
+    pub dim_values: IArray<(IString, IArray<models::DimValue>)>,
+    /// The weight of each dimension by name, from 0 to 10, with 5 for those missing. When
+    /// given, the weights only change through this prop, so `on_weights_change` should be used
+    /// to keep it up to date.
+    #[prop_or_default]
+    pub weights: Option<IArray<(IString, f32)>>,
+    /// Called with the weight of every dimension whenever one is changed by the user.
+    #[prop_or_default]
+    pub on_weights_change: Option<Callback<IArray<(IString, f32)>>>,
+    /// The method scored with at first, or whenever this prop changes, until the user picks
+    /// another.
+    #[prop_or_default]
+    pub method: Method,
+    /// Called with the names of the alternatives from best to worst whenever the user changes a
+    /// weight or the method.
+    #[prop_or_default]
+    pub on_rank: Option<Callback<IArray<IString>>>,
+}
+
+pub struct DimRanking {
+    /// The weights changed by the user when they are not controlled by the `weights` prop.
+    weights: HashMap<IString, f32>,
+    method: Method,
+}
+
+impl DimRanking {
+    /// The dimensions in order, with their weights.
+    fn weighted(&self, ctx: &Context<Self>) -> (Vec<models::DimDesc>, Vec<f32>) {
+        let props = ctx.props();
+        let mut dim_desc = props.dim_desc.to_vec();
+        dim_desc.sort_by_key(|dd| dd.order);
+        let weights = dim_desc
+            .iter()
+            .map(|dd| {
+                let weight = match &props.weights {
+                    Some(weights) => weights
+                        .iter()
+                        .find_map(|(name, weight)| (name == dd.name).then_some(weight)),
+                    None => self.weights.get(&dd.name).copied(),
+                };
+                weight.unwrap_or(DEFAULT_WEIGHT)
+            })
+            .collect();
+        (dim_desc, weights)
+    }
+
+    fn emit_rank(&self, ctx: &Context<Self>, dim_desc: &[models::DimDesc], weights: &[f32]) {
+        if let Some(on_rank) = &ctx.props().on_rank {
+            let ranked = scoring::rank(dim_desc, &ctx.props().dim_values, weights, self.method);
+            on_rank.emit(ranked.into_iter().map(|ranked| ranked.name).collect());
+        }
+    }
+}
+
+impl Component for DimRanking {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            weights: HashMap::new(),
+            method: ctx.props().method,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().method != old_props.method {
+            self.method = ctx.props().method;
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let (dim_desc, mut weights) = self.weighted(ctx);
+        match msg {
+            Message::SetWeight(j, weight) => {
+                let (Some(dd), Some(old_weight)) = (dim_desc.get(j), weights.get_mut(j)) else {
+                    return false;
+                };
+                *old_weight = weight.clamp(0.0, MAX_WEIGHT);
+                if let Some(on_weights_change) = &ctx.props().on_weights_change {
+                    on_weights_change.emit(
+                        dim_desc
+                            .iter()
+                            .zip(&weights)
+                            .map(|(dd, weight)| (dd.name.clone(), *weight))
+                            .collect(),
+                    );
+                }
+                self.weights.insert(dd.name.clone(), weights[j]);
+            }
+            Message::SetMethod(method) => self.method = method,
+        }
+        self.emit_rank(ctx, &dim_desc, &weights);
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        if props.dim_desc.is_empty() {
+            return html!();
+        }
+        let (dim_desc, weights) = self.weighted(ctx);
+        let ranked = scoring::rank(&dim_desc, &props.dim_values, &weights, self.method);
+        html! {
+            <div class="dimview-ranking">
+                <fieldset class="weights">
+                    <legend>{"Weights"}</legend>
+                    { dim_desc.iter().zip(&weights).enumerate().map(|(j, (dd, weight))| {
+                        let oninput = ctx.link().batch_callback(move |event: InputEvent| {
+                            event
+                                .target_dyn_into::<HtmlInputElement>()
+                                .map(|input| Message::SetWeight(j, input.value_as_number() as f32))
+                        });
+                        html! {
+                            <label>
+                                {dd.name.clone()}
+                                <input
+                                    type="range"
+                                    min="0"
+                                    max={MAX_WEIGHT.to_string()}
+                                    step="1"
+                                    value={weight.to_string()}
+                                    {oninput}
+                                />
+                                <output>{weight.to_string()}</output>
+                            </label>
+                        }
+                    }).collect::<Html>() }
+                </fieldset>
+                <div class="methods" role="group" aria-label="Scoring method">
+                    { Method::ALL.into_iter().map(|method| html! {
+                        <button
+                            type="button"
+                            aria-pressed={(method == self.method).to_string()}
+                            onclick={ctx.link().callback(move |_| Message::SetMethod(method))}
+                        >
+                            {method.name()}
+                        </button>
+                    }).collect::<Html>() }
+                </div>
+                <ol class="ranking" aria-live="polite">
+                    { ranked.into_iter().map(|ranked| html! {
+                        <li>
+                            <span class="name">{ranked.name}</span>
+                            <meter min="0" max="1" value={ranked.score.to_string()} />
+                            <span class="score">{format!("{:.2}", ranked.score)}</span>
+                        </li>
+                    }).collect::<Html>() }
+                </ol>
+            </div>
+        }
+    }
+}
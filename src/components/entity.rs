@@ -2,7 +2,10 @@ use implicit_clone::unsync::IString;
 use yew::prelude::*;
 
 use crate::{
-    components::{onclick_anchor_handler, GenericProperties, ProvenanceLinks},
+    components::{
+        onclick_anchor_handler, DateTime, GenericProperties, ProvenanceLinks, StaleNotice,
+    },
+    measure::{LengthUnit, Measurements},
     models,
 };
 
@@ -15,12 +18,18 @@ pub enum Message {
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub entity: models::Entity,
+    /// When the entity was served stale from the cache, the time at which it was fetched.
+    #[prop_or_default]
+    pub stale: Option<chrono::DateTime<chrono::Utc>>,
     #[prop_or_default]
     pub on_activity_click: Option<Callback<IString>>,
     #[prop_or_default]
     pub on_agent_click: Option<Callback<IString>>,
     #[prop_or_default]
     pub on_entity_click: Option<Callback<IString>>,
+    /// The units that the length and perimeter of the geometry are shown in.
+    #[prop_or_default]
+    pub length_unit: LengthUnit,
 }
 
 pub struct Entity;
@@ -58,11 +67,29 @@ impl Component for Entity {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let measurements = Measurements::of_entity(&ctx.props().entity)
+            .map(|measurements| measurements.rows(ctx.props().length_unit))
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let id = format!("measurements-{i}");
+                html! {
+                    <>
+                    <label for={id.clone()}>{label}</label>
+                    <input id={id} type="text" readonly=true {value} />
+                    </>
+                }
+            })
+            .collect::<Html>();
         html! {
             <form>
                 <fieldset>
                     <legend>{format!("Entity: {}", ctx.props().entity.label.clone().unwrap_or(AttrValue::from("<unknown>")))}</legend>
+                    <StaleNotice fetched_at={ctx.props().stale} />
                     <GenericProperties properties={ctx.props().entity.properties.clone()} />
+                    <DateTime id="generated-at" label="Generated at" value={ctx.props().entity.generated_at} />
+                    {measurements}
                     <ProvenanceLinks id_prefix="was-attributed-to" label="Attributed to" links={ctx.props().entity.was_attributed_to.clone()} onclick={onclick_anchor_handler(ctx.link(), Message::AgentClicked)} />
                     <ProvenanceLinks id_prefix="was-derived-from" label="Derived from" links={ctx.props().entity.was_derived_from.clone()} onclick={onclick_anchor_handler(ctx.link(), Message::EntityClicked)} />
                     <ProvenanceLinks id_prefix="was-generated-by" label="Generated by" links={ctx.props().entity.was_generated_by.clone()} onclick={onclick_anchor_handler(ctx.link(), Message::ActivityClicked)} />
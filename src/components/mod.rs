@@ -9,7 +9,12 @@ pub mod activity;
 pub mod agent;
 pub mod dimview;
 pub mod entity;
+pub mod results_table;
 pub mod spatial_entities;
+pub mod time_slider;
+
+pub use results_table::ResultsTable;
+pub use time_slider::TimeSlider;
 
 pub fn onclick_anchor_handler<COMP: BaseComponent<Message = M>, M: 'static>(
     link: &Scope<COMP>,
@@ -49,6 +54,27 @@ pub fn DateTime(props: &DateTimeProps) -> Html {
         .collect::<Html>()
 }
 
+#[derive(Properties, PartialEq)]
+pub struct StaleNoticeProps {
+    fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Flags results that are older than the cache allows, e.g. while working offline.
+#[function_component]
+pub fn StaleNotice(props: &StaleNoticeProps) -> Html {
+    props
+        .fetched_at
+        .iter()
+        .map(|fetched_at| {
+            html! {
+                <p class="stale" role="status">
+                    {format!("Showing cached data from {}, which may be out of date", fetched_at.format("%Y-%m-%d %H:%M:%S UTC"))}
+                </p>
+            }
+        })
+        .collect::<Html>()
+}
+
 #[derive(Properties, PartialEq)]
 pub struct GenericPropertiesProps {
     properties: IArray<(IString, IString)>,
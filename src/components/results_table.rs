@@ -0,0 +1,508 @@
+//! A table of entities, e.g. the results of a search, that can be kept in step with a map.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use implicit_clone::unsync::{IArray, IString};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::models;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Column {
+    Label,
+    Property(IString),
+}
+
+impl Column {
+    fn name(&self) -> IString {
+        match self {
+            Column::Label => IString::from("Label"),
+            Column::Property(label) => label.clone(),
+        }
+    }
+
+    /// The text shown for an entity, with multiple values of a property separated by commas.
+    fn cell(&self, id: &IString, entity: &models::Entity) -> String {
+        match self {
+            Column::Label => entity.label.as_deref().unwrap_or(id).to_string(),
+            Column::Property(label) => entity
+                .properties
+                .iter()
+                .filter(|(property, _)| property == label)
+                .map(|(_, value)| value.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+/// The label column followed by a column for each property, either those given or all of them
+/// in the order they are first seen.
+fn columns(entities: &[(IString, models::Entity)], only: Option<&[IString]>) -> Vec<Column> {
+    let mut labels = Vec::<IString>::new();
+    match only {
+        Some(only) => labels.extend(only.iter().cloned()),
+        None => {
+            for (_, entity) in entities {
+                for (label, _) in entity.properties.iter() {
+                    if !labels.contains(&label) {
+                        labels.push(label);
+                    }
+                }
+            }
+        }
+    }
+    std::iter::once(Column::Label)
+        .chain(labels.into_iter().map(Column::Property))
+        .collect()
+}
+
+/// Compares cells as numbers when both are numbers, and otherwise as text ignoring case.
+fn compare(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// The positions of the entities matching every filter, in the order sorted by a column.
+fn rows(
+    entities: &[(IString, models::Entity)],
+    columns: &[Column],
+    filters: &HashMap<usize, String>,
+    sort: Option<(usize, bool)>,
+) -> Vec<usize> {
+    let filters = filters
+        .iter()
+        .filter(|(_, filter)| !filter.trim().is_empty())
+        .filter_map(|(i, filter)| Some((columns.get(*i)?, filter.trim().to_lowercase())))
+        .collect::<Vec<_>>();
+    let mut rows = entities
+        .iter()
+        .enumerate()
+        .filter(|(_, (id, entity))| {
+            filters
+                .iter()
+                .all(|(column, filter)| column.cell(id, entity).to_lowercase().contains(filter))
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if let Some((column, descending)) =
+        sort.and_then(|(i, descending)| Some((columns.get(i)?, descending)))
+    {
+        // The sort is stable, so ties keep the order the entities were given in.
+        rows.sort_by(|a, b| {
+            let (a, b) = (&entities[*a], &entities[*b]);
+            let ordering = compare(&column.cell(&a.0, &a.1), &column.cell(&b.0, &b.1));
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    rows
+}
+
+/// The page of a number of rows that is shown when a page is chosen, with the number of pages.
+/// There is always a page, if empty.
+fn paging(rows: usize, page: usize, page_size: usize) -> (usize, usize) {
+    let pages = rows.div_ceil(page_size.max(1)).max(1);
+    (page.min(pages - 1), pages)
+}
+
+pub enum Message {
+    /// Sorts by a column, or reverses the sort if it is already sorted by it.
+    SortBy(usize),
+    Filter(usize, String),
+    Page(usize),
+    ToggleSelected(IString),
+    /// Selects or deselects every row on the current page.
+    SelectPage(bool),
+    RowClicked(IString),
+    RowHovered(Option<IString>),
+}
+
+#[derive(PartialEq, Properties)]
+pub struct Props {
+    pub entities: IArray<(IString, models::Entity)>,
+    /// The property labels to show as columns after the entity label, in order. Every property
+    /// is shown if not given.
+    #[prop_or_default]
+    pub columns: Option<IArray<IString>>,
+    #[prop_or(20)]
+    pub page_size: usize,
+    /// The IDs of the selected entities. When given, the selection only changes through this
+    /// prop, so `on_selection_change` should be used to keep it up to date.
+    #[prop_or_default]
+    pub selected: Option<IArray<IString>>,
+    /// Called with the IDs of the selected entities whenever rows are selected or deselected.
+    #[prop_or_default]
+    pub on_selection_change: Option<Callback<IArray<IString>>>,
+    /// An entity to highlight, e.g. when it is hovered on a map.
+    #[prop_or_default]
+    pub highlighted: Option<IString>,
+    /// Called with the ID of an entity when its row is clicked or chosen with the keyboard.
+    #[prop_or_default]
+    pub on_entity_click: Option<Callback<IString>>,
+    /// Called with the ID of an entity when the pointer or focus moves onto its row, and with
+    /// `None` when it leaves.
+    #[prop_or_default]
+    pub on_entity_hover: Option<Callback<Option<IString>>>,
+}
+
+pub struct ResultsTable {
+    columns: Vec<Column>,
+    /// The column sorted by and whether it is sorted in descending order.
+    sort: Option<(usize, bool)>,
+    filters: HashMap<usize, String>,
+    page: usize,
+    /// The selection when it is not controlled by the `selected` prop.
+    selected: Vec<IString>,
+    hovered: Option<IString>,
+}
+
+impl ResultsTable {
+    fn selected<'a>(&'a self, ctx: &'a Context<Self>) -> &'a [IString] {
+        match &ctx.props().selected {
+            Some(selected) => selected,
+            None => &self.selected,
+        }
+    }
+
+    fn set_selected(&mut self, ctx: &Context<Self>, selected: Vec<IString>) -> bool {
+        if let Some(on_selection_change) = &ctx.props().on_selection_change {
+            on_selection_change.emit(IArray::from(selected.clone()));
+        }
+        if ctx.props().selected.is_some() {
+            return false;
+        }
+        self.selected = selected;
+        true
+    }
+
+    fn rows(&self, ctx: &Context<Self>) -> Vec<usize> {
+        rows(
+            &ctx.props().entities,
+            &self.columns,
+            &self.filters,
+            self.sort,
+        )
+    }
+
+    /// The page shown, which is the last when the rows no longer reach the page chosen, with the
+    /// number of pages.
+    fn page(&self, ctx: &Context<Self>, rows: &[usize]) -> (usize, usize) {
+        paging(rows.len(), self.page, ctx.props().page_size)
+    }
+
+    fn page_rows(&self, ctx: &Context<Self>, rows: &[usize]) -> Vec<usize> {
+        let page_size = ctx.props().page_size.max(1);
+        let (page, _) = self.page(ctx, rows);
+        rows.iter()
+            .skip(page * page_size)
+            .take(page_size)
+            .copied()
+            .collect()
+    }
+}
+
+impl Component for ResultsTable {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let props = ctx.props();
+        Self {
+            columns: columns(&props.entities, props.columns.as_deref()),
+            sort: None,
+            filters: HashMap::new(),
+            page: 0,
+            selected: Vec::new(),
+            hovered: None,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        if props.entities != old_props.entities || props.columns != old_props.columns {
+            let columns = columns(&props.entities, props.columns.as_deref());
+            if columns != self.columns {
+                self.columns = columns;
+                self.sort = None;
+                self.filters.clear();
+            }
+            self.page = 0;
+            self.selected
+                .retain(|id| props.entities.iter().any(|(entity_id, _)| entity_id == *id));
+        }
+        if props.page_size != old_props.page_size {
+            self.page = 0;
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::SortBy(column) => {
+                self.sort = match self.sort {
+                    Some((sorted, descending)) if sorted == column => Some((column, !descending)),
+                    _ => Some((column, false)),
+                };
+                self.page = 0;
+                true
+            }
+            Message::Filter(column, filter) => {
+                self.filters.insert(column, filter);
+                self.page = 0;
+                true
+            }
+            Message::Page(page) => {
+                self.page = page;
+                true
+            }
+            Message::ToggleSelected(id) => {
+                let mut selected = self.selected(ctx).to_vec();
+                match selected.iter().position(|selected_id| *selected_id == id) {
+                    Some(i) => {
+                        selected.remove(i);
+                    }
+                    None => selected.push(id),
+                }
+                self.set_selected(ctx, selected)
+            }
+            Message::SelectPage(select) => {
+                let entities = &ctx.props().entities;
+                let page = self
+                    .page_rows(ctx, &self.rows(ctx))
+                    .into_iter()
+                    .filter_map(|i| entities.get(i).map(|(id, _)| id))
+                    .collect::<Vec<_>>();
+                let mut selected = self.selected(ctx).to_vec();
+                selected.retain(|id| !page.contains(id));
+                if select {
+                    selected.extend(page);
+                }
+                self.set_selected(ctx, selected)
+            }
+            Message::RowClicked(id) => {
+                if let Some(on_entity_click) = &ctx.props().on_entity_click {
+                    on_entity_click.emit(id);
+                }
+                false
+            }
+            Message::RowHovered(id) => {
+                if id == self.hovered {
+                    return false;
+                }
+                if let Some(on_entity_hover) = &ctx.props().on_entity_hover {
+                    on_entity_hover.emit(id.clone());
+                }
+                self.hovered = id;
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let rows = self.rows(ctx);
+        let (page, pages) = self.page(ctx, &rows);
+        let page_rows = self.page_rows(ctx, &rows);
+        let selected = self.selected(ctx);
+        let page_selected = !page_rows.is_empty()
+            && page_rows.iter().all(|i| {
+                props
+                    .entities
+                    .get(*i)
+                    .is_some_and(|(id, _)| selected.contains(&id))
+            });
+
+        let headers = self.columns.iter().enumerate().map(|(i, column)| {
+            let sort = match self.sort {
+                Some((sorted, false)) if sorted == i => "ascending",
+                Some((sorted, true)) if sorted == i => "descending",
+                _ => "none",
+            };
+            html! {
+                <th scope="col" aria-sort={sort}>
+                    <button type="button" onclick={ctx.link().callback(move |_| Message::SortBy(i))}>
+                        {column.name()}
+                    </button>
+                </th>
+            }
+        });
+        let filters = self.columns.iter().enumerate().map(|(i, column)| {
+            let oninput = ctx.link().callback(move |event: InputEvent| {
+                Message::Filter(i, event.target_unchecked_into::<HtmlInputElement>().value())
+            });
+            html! {
+                <td>
+                    <input
+                        type="search"
+                        aria-label={format!("Filter {}", column.name())}
+                        value={self.filters.get(&i).cloned().unwrap_or_default()}
+                        {oninput}
+                    />
+                </td>
+            }
+        });
+        let body = page_rows.iter().filter_map(|i| props.entities.get(*i)).map(|(id, entity)| {
+            let is_selected = selected.contains(&id);
+            let class = classes!(
+                is_selected.then_some("selected"),
+                (props.highlighted.as_ref() == Some(&id)).then_some("highlighted"),
+            );
+            let onclick = ctx.link().callback({
+                let id = id.clone();
+                move |_| Message::RowClicked(id.clone())
+            });
+            let onkeydown = ctx.link().batch_callback({
+                let id = id.clone();
+                move |event: KeyboardEvent| {
+                    (event.key() == "Enter").then(|| Message::RowClicked(id.clone()))
+                }
+            });
+            let hovered = ctx.link().callback({
+                let id = id.clone();
+                move |_: ()| Message::RowHovered(Some(id.clone()))
+            });
+            let onmouseenter = hovered.reform(|_: MouseEvent| ());
+            let onfocus = hovered.reform(|_: FocusEvent| ());
+            let onchange = ctx.link().callback({
+                let id = id.clone();
+                move |_| Message::ToggleSelected(id.clone())
+            });
+            let label = Column::Label.cell(&id, &entity);
+            html! {
+                <tr key={id.to_string()} {class} tabindex="0" aria-selected={is_selected.to_string()} {onclick} {onkeydown} {onmouseenter} {onfocus}>
+                    <td onclick={Callback::from(|event: MouseEvent| event.stop_propagation())}>
+                        <input type="checkbox" aria-label={format!("Select {label}")} checked={is_selected} {onchange} />
+                    </td>
+                    {
+                        self.columns.iter().map(|column| html! {
+                            <td>{column.cell(&id, &entity)}</td>
+                        }).collect::<Html>()
+                    }
+                </tr>
+            }
+        });
+        let onmouseleave = ctx.link().callback(|_| Message::RowHovered(None));
+        let onfocusout = ctx
+            .link()
+            .callback(|_: FocusEvent| Message::RowHovered(None));
+
+        html! {
+            <div class="results-table">
+                <table>
+                    <thead>
+                        <tr>
+                            <th scope="col">
+                                <input
+                                    type="checkbox"
+                                    aria-label="Select all on this page"
+                                    checked={page_selected}
+                                    onchange={ctx.link().callback(move |_| Message::SelectPage(!page_selected))}
+                                />
+                            </th>
+                            {for headers}
+                        </tr>
+                        <tr class="filters">
+                            <td></td>
+                            {for filters}
+                        </tr>
+                    </thead>
+                    <tbody {onmouseleave} {onfocusout}>
+                        {for body}
+                    </tbody>
+                </table>
+                <nav class="pagination" aria-label="Pages">
+                    <button type="button" disabled={page == 0} onclick={ctx.link().callback(move |_| Message::Page(page.saturating_sub(1)))}>
+                        {"Previous"}
+                    </button>
+                    <span>{format!("Page {} of {pages} ({} results)", page + 1, rows.len())}</span>
+                    <button type="button" disabled={page + 1 >= pages} onclick={ctx.link().callback(move |_| Message::Page(page + 1))}>
+                        {"Next"}
+                    </button>
+                </nav>
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entity(label: &str, properties: &[(&'static str, &'static str)]) -> models::Entity {
+        models::Entity {
+            label: Some(IString::from(label.to_string())),
+            properties: properties
+                .iter()
+                .map(|(label, value)| (IString::from(*label), IString::from(*value)))
+                .collect(),
+            ..models::Entity::default()
+        }
+    }
+
+    #[test]
+    fn test_rows() {
+        let entities = [
+            (
+                IString::from("http://example.com/a"),
+                entity("Route A", &[("Depth", "30"), ("Status", "Planned")]),
+            ),
+            (
+                IString::from("http://example.com/b"),
+                entity("Route B", &[("Depth", "100"), ("Vessel", "Sirius")]),
+            ),
+            (
+                IString::from("http://example.com/c"),
+                entity("route c", &[("Depth", "9"), ("Status", "Done")]),
+            ),
+        ];
+        let columns = columns(&entities, None);
+        assert_eq!(
+            columns.iter().map(Column::name).collect::<Vec<_>>(),
+            ["Label", "Depth", "Status", "Vessel"]
+        );
+
+        let sorted = |sort| rows(&entities, &columns, &HashMap::new(), sort);
+        assert_eq!(sorted(None), [0, 1, 2]);
+        assert_eq!(
+            sorted(Some((1, false))),
+            [2, 0, 1],
+            "depths sort as numbers"
+        );
+        assert_eq!(sorted(Some((1, true))), [1, 0, 2]);
+        assert_eq!(
+            sorted(Some((0, true))),
+            [2, 1, 0],
+            "labels sort ignoring case"
+        );
+        assert_eq!(
+            sorted(Some((2, false))),
+            [1, 2, 0],
+            "missing values sort first"
+        );
+
+        let filters = HashMap::from([(0, "route ".to_string()), (2, "Don".to_string())]);
+        assert_eq!(rows(&entities, &columns, &filters, None), [2]);
+
+        assert_eq!(
+            self::columns(&entities, Some(&[IString::from("Vessel")]))
+                .iter()
+                .map(Column::name)
+                .collect::<Vec<_>>(),
+            ["Label", "Vessel"]
+        );
+
+        assert_eq!(paging(3, 1, 2), (1, 2));
+        // After the rows are filtered down, or the pages grow.
+        assert_eq!(paging(3, 4, 2), (1, 2));
+        assert_eq!(paging(3, 1, 10), (0, 1));
+        assert_eq!(paging(0, 0, 10), (0, 1));
+    }
+}
@@ -0,0 +1,380 @@
+//! Drawing entities on HTML canvases, which is much faster than SVG for many entities.
+//!
+//! Tiles and the base image are still drawn as SVG beneath. Entities are drawn on one canvas,
+//! which is only redrawn when the view changes, and highlights on another over it, which is
+//! redrawn as the pointer moves. What is under the pointer is found by [`Layout::hit`].
+
+use std::{
+    collections::HashMap,
+    f64::consts::{PI, TAU},
+};
+
+use geo::{Coord, Geometry, LineString, Polygon};
+use implicit_clone::unsync::IString;
+use web_sys::{
+    js_sys,
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    CanvasRenderingContext2d, CanvasWindingRule, HtmlCanvasElement, HtmlImageElement,
+};
+use yew::Callback;
+
+use super::{
+    scene::{Cluster, Layout, Placed, CLUSTER_COLOR},
+    EntityStyle, Symbol,
+};
+
+const LABEL_FONT: &str = "12px sans-serif";
+
+const CLUSTER_FONT: &str = "11px sans-serif";
+
+/// The size of the box in pixels that SVG markup symbols are drawn in.
+const MARKUP_BOX: f64 = 64.0;
+
+const HOVER_COLOR: &str = "#ff7f00";
+
+const SELECTED_COLOR: &str = "#1f78b4";
+
+/// How many entities a map can show before [`Renderer::for_count`] chooses a canvas.
+pub const CANVAS_THRESHOLD: usize = 1000;
+
+/// How entities are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Renderer {
+    /// As SVG elements, which can be styled with CSS and reached with the keyboard one by one.
+    #[default]
+    Svg,
+    /// On a canvas, which is much faster for thousands of entities. Entities are reached with
+    /// the arrow keys once the map has focus.
+    Canvas,
+}
+
+impl Renderer {
+    /// The renderer suited to a number of entities: a canvas for more than
+    /// [`CANVAS_THRESHOLD`], otherwise SVG.
+    pub fn for_count(count: usize) -> Self {
+        if count > CANVAS_THRESHOLD {
+            Renderer::Canvas
+        } else {
+            Renderer::Svg
+        }
+    }
+}
+
+/// Images for symbols by URL, loaded when they are first drawn.
+pub(super) type Images = HashMap<String, HtmlImageElement>;
+
+/// Clears a canvas and sizes it to a layout, returning its context with pixels as units.
+fn prepare(canvas: &HtmlCanvasElement, layout: &Layout) -> Option<CanvasRenderingContext2d> {
+    let width = layout.viewport.width() / layout.units_per_pixel;
+    let height = layout.viewport.height() / layout.units_per_pixel;
+    let ratio = web_sys::window()
+        .map(|window| window.device_pixel_ratio())
+        .unwrap_or(1.0);
+    canvas.set_width((width * ratio).round() as u32);
+    canvas.set_height((height * ratio).round() as u32);
+    let _ = canvas.set_attribute(
+        "style",
+        &format!("position: absolute; left: 0; top: 0; width: {width}px; height: {height}px"),
+    );
+    let context = canvas
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<CanvasRenderingContext2d>()
+        .ok()?;
+    let _ = context.set_transform(ratio, 0.0, 0.0, ratio, 0.0, 0.0);
+    Some(context)
+}
+
+/// Draws the entities and clusters of a layout. `on_load` is called when an image for a symbol
+/// loads, after which the canvas should be drawn again.
+pub(super) fn draw(
+    canvas: &HtmlCanvasElement,
+    layout: &Layout,
+    images: &mut Images,
+    on_load: &Callback<()>,
+) {
+    let Some(context) = prepare(canvas, layout) else {
+        return;
+    };
+    for placed in &layout.entities {
+        draw_entity(&context, layout, placed, images, on_load);
+    }
+    for cluster in &layout.clusters {
+        draw_cluster(&context, layout, cluster);
+    }
+}
+
+/// Outlines the selected entities and those that are hovered or highlighted, as the SVG
+/// renderer does with CSS.
+pub(super) fn draw_highlights(
+    canvas: &HtmlCanvasElement,
+    layout: &Layout,
+    selected: &[IString],
+    highlighted: &[IString],
+) {
+    let Some(context) = prepare(canvas, layout) else {
+        return;
+    };
+    for placed in &layout.entities {
+        let (color, width) = if selected.contains(&placed.id) {
+            (SELECTED_COLOR, 4.0)
+        } else if highlighted.contains(&placed.id) {
+            (HOVER_COLOR, 3.0)
+        } else {
+            continue;
+        };
+        context.set_stroke_style_str(color);
+        context.set_line_width(width);
+        let (points, geometry) = placed.drawn.as_ref();
+        context.begin_path();
+        if let Some(geometry) = geometry {
+            trace(&context, layout, geometry, true);
+            trace(&context, layout, geometry, false);
+        }
+        let size = placed.symbol_size() as f64;
+        let offset = placed.symbol().center(size as f32);
+        for point in points {
+            let center = layout.to_pixels(point.0) + offset;
+            context.move_to(center.x + size / 2.0 + 2.0, center.y);
+            let _ = context.arc(center.x, center.y, size / 2.0 + 2.0, 0.0, TAU);
+        }
+        context.stroke();
+    }
+}
+
+fn draw_entity(
+    context: &CanvasRenderingContext2d,
+    layout: &Layout,
+    placed: &Placed,
+    images: &mut Images,
+    on_load: &Callback<()>,
+) {
+    let style = &placed.style;
+    let (points, geometry) = placed.drawn.as_ref();
+    let dashes = style
+        .stroke_dasharray
+        .as_deref()
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|dash| dash.parse::<f64>().ok())
+        .map(JsValue::from_f64)
+        .collect::<js_sys::Array>();
+    let _ = context.set_line_dash(&dashes);
+
+    if let Some(geometry) = geometry {
+        context.begin_path();
+        trace(context, layout, geometry, true);
+        fill(context, style);
+        stroke(context, style, None);
+        // Lines are drawn in black unless styled otherwise, as they would not show at all.
+        context.begin_path();
+        trace(context, layout, geometry, false);
+        stroke(context, style, Some("black"));
+    }
+
+    let (symbol, size) = (placed.symbol(), placed.symbol_size() as f64);
+    for point in points {
+        let at = layout.to_pixels(point.0);
+        draw_symbol(context, &symbol, size, at, style, images, on_load);
+    }
+    if let Some((point, label)) = points.first().zip(placed.label()) {
+        let at = layout.to_pixels(point.0);
+        let (x, y) = symbol.label_offset(size as f32);
+        let _ = context.set_line_dash(&js_sys::Array::new());
+        context.set_global_alpha(style.opacity.unwrap_or(1.0) as f64);
+        context.set_font(LABEL_FONT);
+        context.set_text_align("left");
+        context.set_text_baseline("middle");
+        context.set_line_width(3.0);
+        context.set_stroke_style_str("white");
+        let _ = context.stroke_text(label, at.x + x as f64, at.y + y as f64);
+        context.set_fill_style_str("#222");
+        let _ = context.fill_text(label, at.x + x as f64, at.y + y as f64);
+    }
+}
+
+/// Adds the areas or the lines of a geometry to the current path.
+fn trace(context: &CanvasRenderingContext2d, layout: &Layout, geometry: &Geometry, areas: bool) {
+    match geometry {
+        Geometry::Line(line) if !areas => trace_line(
+            context,
+            layout,
+            &LineString::from(vec![line.start, line.end]),
+        ),
+        Geometry::LineString(line) if !areas => trace_line(context, layout, line),
+        Geometry::MultiLineString(lines) if !areas => {
+            for line in lines {
+                trace_line(context, layout, line);
+            }
+        }
+        Geometry::Polygon(polygon) if areas => trace_polygon(context, layout, polygon),
+        Geometry::MultiPolygon(polygons) if areas => {
+            for polygon in polygons {
+                trace_polygon(context, layout, polygon);
+            }
+        }
+        Geometry::Rect(rect) if areas => trace_polygon(context, layout, &rect.to_polygon()),
+        Geometry::Triangle(triangle) if areas => {
+            trace_polygon(context, layout, &triangle.to_polygon())
+        }
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                trace(context, layout, geometry, areas);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn trace_line(context: &CanvasRenderingContext2d, layout: &Layout, line: &LineString) {
+    for (i, coord) in line.coords().enumerate() {
+        let Coord { x, y } = layout.to_pixels(*coord);
+        if i == 0 {
+            context.move_to(x, y);
+        } else {
+            context.line_to(x, y);
+        }
+    }
+}
+
+fn trace_polygon(context: &CanvasRenderingContext2d, layout: &Layout, polygon: &Polygon) {
+    for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+        trace_line(context, layout, ring);
+        context.close_path();
+    }
+}
+
+/// Fills the current path as SVG would, in black unless styled otherwise.
+fn fill(context: &CanvasRenderingContext2d, style: &EntityStyle) {
+    let color = style.fill.as_deref().unwrap_or("black");
+    if color == "none" {
+        return;
+    }
+    context.set_global_alpha(
+        (style.opacity.unwrap_or(1.0) * style.fill_opacity.unwrap_or(1.0)) as f64,
+    );
+    context.set_fill_style_str(color);
+    context.fill_with_canvas_winding_rule(CanvasWindingRule::Evenodd);
+}
+
+/// Strokes the current path as SVG would, with no stroke unless styled or given a default.
+fn stroke(context: &CanvasRenderingContext2d, style: &EntityStyle, default: Option<&str>) {
+    let Some(color) = style.stroke.as_deref().or(default) else {
+        return;
+    };
+    if color == "none" {
+        return;
+    }
+    context.set_global_alpha(
+        (style.opacity.unwrap_or(1.0) * style.stroke_opacity.unwrap_or(1.0)) as f64,
+    );
+    context.set_stroke_style_str(color);
+    context.set_line_width(style.stroke_width.unwrap_or(1.0) as f64);
+    context.stroke();
+}
+
+fn draw_symbol(
+    context: &CanvasRenderingContext2d,
+    symbol: &Symbol,
+    size: f64,
+    at: Coord,
+    style: &EntityStyle,
+    images: &mut Images,
+    on_load: &Callback<()>,
+) {
+    let half = size / 2.0;
+    context.begin_path();
+    match symbol {
+        Symbol::Circle => {
+            let _ = context.arc(at.x, at.y, half, 0.0, TAU);
+        }
+        Symbol::Square => context.rect(at.x - half, at.y - half, size, size),
+        Symbol::Pin => {
+            let top = at.y - size;
+            context.move_to(at.x, at.y);
+            context.line_to(at.x - half, top);
+            let _ = context.arc(at.x, top, half, PI, TAU);
+            context.close_path();
+            fill(context, style);
+            stroke(context, style, None);
+            context.begin_path();
+            let _ = context.arc(at.x, top, half / 2.5, 0.0, TAU);
+            context.set_fill_style_str("white");
+            context.fill();
+            return;
+        }
+        Symbol::Icon(href) => {
+            if let Some(image) = image(images, href, on_load) {
+                let _ = context.draw_image_with_html_image_element_and_dw_and_dh(
+                    image,
+                    at.x - half,
+                    at.y - half,
+                    size,
+                    size,
+                );
+            }
+            return;
+        }
+        Symbol::Svg(markup) => {
+            let half_box = MARKUP_BOX / 2.0;
+            let svg = format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{MARKUP_BOX}\" height=\"{MARKUP_BOX}\" viewBox=\"{} {} {MARKUP_BOX} {MARKUP_BOX}\">{markup}</svg>",
+                -half_box, -half_box,
+            );
+            let src = format!(
+                "data:image/svg+xml,{}",
+                String::from(js_sys::encode_uri_component(&svg))
+            );
+            if let Some(image) = image(images, &src, on_load) {
+                let _ = context.draw_image_with_html_image_element_and_dw_and_dh(
+                    image,
+                    at.x - half_box,
+                    at.y - half_box,
+                    MARKUP_BOX,
+                    MARKUP_BOX,
+                );
+            }
+            return;
+        }
+    }
+    fill(context, style);
+    stroke(context, style, None);
+}
+
+/// An image once it has loaded, starting to load it if it has not been asked for before.
+fn image<'a>(
+    images: &'a mut Images,
+    src: &str,
+    on_load: &Callback<()>,
+) -> Option<&'a HtmlImageElement> {
+    if !images.contains_key(src) {
+        let image = HtmlImageElement::new().ok()?;
+        let on_load = on_load.clone();
+        let closure = Closure::once_into_js(move || on_load.emit(()));
+        image.set_onload(Some(closure.unchecked_ref()));
+        image.set_src(src);
+        images.insert(src.to_string(), image);
+    }
+    images
+        .get(src)
+        .filter(|image| image.complete() && image.natural_width() > 0)
+}
+
+fn draw_cluster(context: &CanvasRenderingContext2d, layout: &Layout, cluster: &Cluster) {
+    let at = layout.to_pixels(cluster.center);
+    let _ = context.set_line_dash(&js_sys::Array::new());
+    context.begin_path();
+    let _ = context.arc(at.x, at.y, cluster.radius, 0.0, TAU);
+    context.set_global_alpha(0.8);
+    context.set_fill_style_str(CLUSTER_COLOR);
+    context.fill();
+    context.set_global_alpha(1.0);
+    context.set_stroke_style_str("white");
+    context.set_line_width(2.0);
+    context.stroke();
+    context.set_font(CLUSTER_FONT);
+    context.set_text_align("center");
+    context.set_text_baseline("middle");
+    context.set_fill_style_str("white");
+    let _ = context.fill_text(&cluster.count.to_string(), at.x, at.y);
+}
@@ -0,0 +1,385 @@
+//! Cartographic furniture drawn over the map: a legend, a scale bar, a north arrow and an
+//! attribution line.
+//!
+//! Controls are drawn as SVG in pixels over the entities, so that they keep their size whatever
+//! the zoom. Text can't be measured outside the browser, so its width is estimated from the
+//! number of characters.
+
+use geo::{coord, Coord, GeodesicDistance, Point, Rect};
+use implicit_clone::ImplicitClone;
+
+use super::{scene::escape_text, LegendEntry, DEFAULT_SYMBOL_SIZE};
+use crate::crs::Crs;
+
+/// The space between controls and the edge of the map, and between stacked controls.
+const MARGIN: f64 = 10.0;
+
+/// The longest a scale bar is drawn, in pixels.
+const MAX_SCALE_BAR_WIDTH: f64 = 120.0;
+
+const FONT_SIZE: f64 = 12.0;
+
+/// The rough width of a character at [`FONT_SIZE`].
+const CHAR_WIDTH: f64 = 6.5;
+
+/// The height of a row of the legend, and the size of its swatches.
+const ROW_HEIGHT: f64 = 20.0;
+const SWATCH_SIZE: f64 = 14.0;
+
+/// A white outline that keeps text readable over the map.
+const HALO: &str = "stroke=\"white\" stroke-width=\"3\" paint-order=\"stroke\"";
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl ImplicitClone for Corner {}
+
+impl Corner {
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+
+    /// CSS placing an absolutely positioned element in the corner.
+    pub(super) fn position_style(self) -> String {
+        format!(
+            "{}: {MARGIN}px; {}: {MARGIN}px",
+            if self.is_top() { "top" } else { "bottom" },
+            if self.is_left() { "left" } else { "right" },
+        )
+    }
+}
+
+/// Which controls are drawn over the map, and in which corner. Controls sharing a corner are
+/// stacked in the order of these fields from the top, or from the bottom in reverse order, so
+/// that the attribution stays nearest the edge. The layer switcher is not stacked with the
+/// others, so it needs a corner of its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapControls {
+    pub legend: Option<Corner>,
+    pub north_arrow: Option<Corner>,
+    pub scale_bar: Option<Corner>,
+    pub attribution: Option<Corner>,
+    /// Shown only when the map has layers.
+    pub layer_switcher: Option<Corner>,
+}
+
+impl ImplicitClone for MapControls {}
+
+impl Default for MapControls {
+    /// Only the attribution, which tile providers require, and the layer switcher.
+    fn default() -> Self {
+        Self {
+            legend: None,
+            north_arrow: None,
+            scale_bar: None,
+            attribution: Some(Corner::BottomRight),
+            layer_switcher: Some(Corner::TopRight),
+        }
+    }
+}
+
+impl MapControls {
+    /// Every control, with the north arrow over the scale bar to leave the top right corner to
+    /// the layer switcher.
+    pub fn all() -> Self {
+        Self {
+            legend: Some(Corner::TopLeft),
+            north_arrow: Some(Corner::BottomLeft),
+            scale_bar: Some(Corner::BottomLeft),
+            attribution: Some(Corner::BottomRight),
+            layer_switcher: Some(Corner::TopRight),
+        }
+    }
+}
+
+/// What the controls show.
+pub(super) struct Furniture<'a> {
+    pub legend: Vec<LegendEntry>,
+    pub attribution: String,
+    pub crs: &'a Crs,
+}
+
+/// A control drawn from the origin, with its size in pixels.
+struct Control {
+    class: &'static str,
+    corner: Corner,
+    size: Coord,
+    svg: String,
+}
+
+impl MapControls {
+    /// The controls over a map `width` pixels wide showing a viewport, as an SVG element in
+    /// pixels, or an empty string if there are none.
+    pub(super) fn to_svg(self, furniture: &Furniture, viewport: &Rect, width: f64) -> String {
+        let height = width * viewport.height() / viewport.width();
+        let controls = [
+            self.legend
+                .filter(|_| !furniture.legend.is_empty())
+                .map(|corner| legend_control(corner, &furniture.legend)),
+            self.north_arrow
+                .map(|corner| north_arrow_control(corner, north_angle(furniture.crs, viewport))),
+            self.scale_bar.and_then(|corner| {
+                let scale_bar = ScaleBar::new(furniture.crs, viewport, width, MAX_SCALE_BAR_WIDTH)?;
+                Some(scale_bar_control(corner, &scale_bar))
+            }),
+            self.attribution
+                .filter(|_| !furniture.attribution.is_empty())
+                .map(|corner| attribution_control(corner, &furniture.attribution)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        if controls.is_empty() {
+            return String::new();
+        }
+
+        let mut offsets = [0.0; 4];
+        let mut placed = vec![String::new(); controls.len()];
+        let order = (0..controls.len()).chain((0..controls.len()).rev());
+        for (pass, i) in order.enumerate() {
+            let control = &controls[i];
+            // Top corners are filled on the first pass, bottom ones on the second.
+            if control.corner.is_top() != (pass < controls.len()) {
+                continue;
+            }
+            let offset = &mut offsets[control.corner as usize];
+            let x = if control.corner.is_left() {
+                MARGIN
+            } else {
+                width - MARGIN - control.size.x
+            };
+            let y = if control.corner.is_top() {
+                MARGIN + *offset
+            } else {
+                height - MARGIN - *offset - control.size.y
+            };
+            *offset += control.size.y + MARGIN;
+            placed[i] = format!(
+                "<g class=\"{}\" transform=\"translate({x} {y})\">{}</g>",
+                control.class, control.svg
+            );
+        }
+        format!(
+            "<svg class=\"map-controls\" viewBox=\"0 0 {width} {height}\" font-family=\"sans-serif\" font-size=\"{FONT_SIZE}\" style=\"position: absolute; left: 0; top: 0; width: 100%; height: 100%; pointer-events: none\">{}</svg>",
+            placed.concat()
+        )
+    }
+}
+
+fn legend_control(corner: Corner, entries: &[LegendEntry]) -> Control {
+    let padding = 6.0;
+    let characters = entries
+        .iter()
+        .map(|entry| entry.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let size = coord! {
+        x: padding * 3.0 + SWATCH_SIZE + characters as f64 * CHAR_WIDTH,
+        y: padding * 2.0 + entries.len() as f64 * ROW_HEIGHT,
+    };
+    let rows = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let middle = padding + (i as f64 + 0.5) * ROW_HEIGHT;
+            let center = padding + SWATCH_SIZE / 2.0;
+            let swatch = if entry.point {
+                let symbol = entry.style.symbol.clone().unwrap_or_default();
+                let size = entry
+                    .style
+                    .symbol_size
+                    .unwrap_or(DEFAULT_SYMBOL_SIZE)
+                    .min(SWATCH_SIZE as f32);
+                let symbol_center = symbol.center(size);
+                format!(
+                    "<g transform=\"translate({} {})\">{}</g>",
+                    center - symbol_center.x,
+                    middle - symbol_center.y,
+                    symbol.to_svg(size)
+                )
+            } else {
+                format!(
+                    "<rect x=\"{padding}\" y=\"{}\" width=\"{SWATCH_SIZE}\" height=\"{}\" />",
+                    middle - SWATCH_SIZE / 3.0,
+                    SWATCH_SIZE * 2.0 / 3.0,
+                )
+            };
+            format!(
+                "<g{}>{swatch}</g><text x=\"{}\" y=\"{middle}\" dominant-baseline=\"middle\" fill=\"#222\">{}</text>",
+                entry.style.to_attributes(),
+                padding * 2.0 + SWATCH_SIZE,
+                escape_text(&entry.label),
+            )
+        })
+        .collect::<String>();
+    Control {
+        class: "legend",
+        corner,
+        size,
+        svg: format!(
+            "<rect width=\"{}\" height=\"{}\" rx=\"3\" fill=\"white\" fill-opacity=\"0.85\" stroke=\"#ccc\" />{rows}",
+            size.x, size.y
+        ),
+    }
+}
+
+/// The direction of true north at the centre of a viewport, in degrees clockwise from up.
+pub(super) fn north_angle(crs: &Crs, viewport: &Rect) -> f64 {
+    let center = viewport.center();
+    let lon_lat = crs.unproject(center);
+    // Step towards the pole, or away from it if too close to step past it.
+    let step = if lon_lat.y > 89.0 { -0.01 } else { 0.01 };
+    let toward = crs.project(coord! { x: lon_lat.x, y: lon_lat.y + step }) - center;
+    let angle = toward.x.atan2(toward.y).to_degrees();
+    if step < 0.0 {
+        angle + 180.0
+    } else {
+        angle
+    }
+}
+
+fn north_arrow_control(corner: Corner, angle: f64) -> Control {
+    Control {
+        class: "north-arrow",
+        corner,
+        size: coord! { x: 28.0, y: 40.0 },
+        svg: format!(
+            "<g transform=\"rotate({angle} 14 24)\"><path d=\"M 14 14 L 22 38 L 14 32 L 6 38 Z\" fill=\"#222\" stroke=\"white\" stroke-width=\"1.5\" /><text x=\"14\" y=\"10\" text-anchor=\"middle\" font-weight=\"bold\" fill=\"#222\" {HALO}>N</text></g>",
+        ),
+    }
+}
+
+/// A round ground distance and its length on screen.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct ScaleBar {
+    pub metres: f64,
+    pub pixels: f64,
+}
+
+impl ScaleBar {
+    /// The longest distance of one, two or five times a power of ten that is at most
+    /// `max_pixels` long at the centre of a viewport shown `width` pixels wide.
+    pub fn new(crs: &Crs, viewport: &Rect, width: f64, max_pixels: f64) -> Option<Self> {
+        // Measured across the middle tenth of the viewport, where the scale is taken to be
+        // constant.
+        let half = viewport.width() / 20.0;
+        let center = viewport.center();
+        let ends =
+            [-half, half].map(|dx| Point::from(crs.unproject(center + coord! { x: dx, y: 0.0 })));
+        let metres_per_pixel = ends[0].geodesic_distance(&ends[1]) / (width / 10.0);
+        if !metres_per_pixel.is_finite() || metres_per_pixel <= 0.0 {
+            return None;
+        }
+        let longest = metres_per_pixel * max_pixels;
+        let magnitude = 10f64.powi(longest.log10().floor() as i32);
+        let metres = [5.0, 2.0, 1.0]
+            .into_iter()
+            .map(|multiple| multiple * magnitude)
+            .find(|metres| *metres <= longest)?;
+        Some(Self {
+            metres,
+            pixels: metres / metres_per_pixel,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        if self.metres >= 1000.0 {
+            format!("{} km", self.metres / 1000.0)
+        } else {
+            format!("{} m", self.metres)
+        }
+    }
+}
+
+fn scale_bar_control(corner: Corner, scale_bar: &ScaleBar) -> Control {
+    let width = scale_bar.pixels;
+    let bar = format!("M 1 16 V 24 H {} V 16", width + 1.0);
+    Control {
+        class: "scale-bar",
+        corner,
+        size: coord! { x: width + 2.0, y: 26.0 },
+        svg: format!(
+            "<path d=\"{bar}\" fill=\"none\" stroke=\"white\" stroke-width=\"4\" /><path d=\"{bar}\" fill=\"none\" stroke=\"#222\" stroke-width=\"2\" /><text x=\"1\" y=\"11\" fill=\"#222\" {HALO}>{}</text>",
+            scale_bar.label(),
+        ),
+    }
+}
+
+fn attribution_control(corner: Corner, attribution: &str) -> Control {
+    let font_size = FONT_SIZE - 1.0;
+    let width = attribution.chars().count() as f64 * CHAR_WIDTH * font_size / FONT_SIZE;
+    let (x, anchor) = if corner.is_left() {
+        (0.0, "start")
+    } else {
+        (width, "end")
+    };
+    Control {
+        class: "attribution",
+        corner,
+        size: coord! { x: width, y: font_size + 3.0 },
+        svg: format!(
+            "<text x=\"{x}\" y=\"{font_size}\" text-anchor=\"{anchor}\" font-size=\"{font_size}\" fill=\"#444\" {HALO}>{}</text>",
+            escape_text(attribution),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scale_bar() {
+        // 2.4 km across 800 pixels in MGA zone 55, so 120 pixels are 360 m.
+        let viewport = Rect::new(
+            coord! { x: 500_000.0, y: 8_000_000.0 },
+            coord! { x: 502_400.0, y: 8_001_800.0 },
+        );
+        let scale_bar =
+            ScaleBar::new(&Crs::mga(55), &viewport, 800.0, MAX_SCALE_BAR_WIDTH).unwrap();
+        assert_eq!(scale_bar.label(), "200 m");
+        // Grid distances on the central meridian are 0.9996 of those on the ground.
+        assert!((scale_bar.pixels - 200.0 / 3.0 * 0.9996).abs() < 0.1);
+
+        let degrees = Rect::new(coord! { x: 140.0, y: -20.0 }, coord! { x: 150.0, y: -10.0 });
+        let scale_bar = ScaleBar::new(&Crs::Crs84, &degrees, 800.0, 100.0).unwrap();
+        assert_eq!(scale_bar.label(), "100 km");
+
+        assert!(north_angle(&Crs::Crs84, &degrees).abs() < 1e-9);
+        let latitude_first =
+            Rect::new(coord! { x: -20.0, y: 140.0 }, coord! { x: -10.0, y: 150.0 });
+        assert!((north_angle(&Crs::Epsg4326, &latitude_first) - 90.0).abs() < 1e-9);
+        // Meridians converge on the south pole, so in the south true north is east of grid north
+        // east of the central meridian.
+        assert!(north_angle(&Crs::mga(55), &viewport) > 0.0);
+    }
+
+    #[test]
+    fn test_stacking() {
+        let controls = MapControls {
+            scale_bar: Some(Corner::BottomRight),
+            ..MapControls::default()
+        };
+        let viewport = Rect::new(coord! { x: 140.0, y: -20.0 }, coord! { x: 150.0, y: -10.0 });
+        let furniture = Furniture {
+            legend: Vec::new(),
+            attribution: "© OpenStreetMap".into(),
+            crs: &Crs::Crs84,
+        };
+        let svg = controls.to_svg(&furniture, &viewport, 400.0);
+        assert!(svg.contains("<g class=\"attribution\" transform=\"translate(300.625 376)\">"));
+        assert!(svg.contains("<g class=\"scale-bar\" transform=\"translate("));
+        assert!(svg.find("scale-bar") < svg.find("attribution"));
+        assert!(svg.contains(" 340)\">"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Drawing shapes on the map, e.g. to search for the entities within them, and measuring
+//! distances with a ruler.
+//!
+//! Shapes are sketched in the CRS of the map. A rectangle is dragged out from one corner to the
+//! other, and a polygon is clicked out vertex by vertex, closing when its first vertex is clicked
+//! again, on a double click or with Enter. A ruler is clicked out the same way, and stays on the
+//! map once finished until the next one is started.
+
+use geo::{Coord, Geometry, LineString, Polygon, Rect};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawTool {
+    Rectangle,
+    Polygon,
+    /// A line of one or more segments, shown with its geodesic length.
+    Ruler,
+}
+
+/// A shape being drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Sketch {
+    pub tool: DrawTool,
+    /// The corner the rectangle was started from, or the vertices of the polygon so far.
+    pub vertices: Vec<Coord>,
+    /// Where the pointer is, which the shape is drawn out to.
+    pub cursor: Option<Coord>,
+    /// Whether a ruler is finished, and only kept to be shown.
+    pub finished: bool,
+}
+
+impl Sketch {
+    pub fn new(tool: DrawTool, start: Coord) -> Self {
+        Self {
+            tool,
+            vertices: vec![start],
+            cursor: Some(start),
+            finished: false,
+        }
+    }
+
+    /// Adds a vertex to a polygon or ruler where it was clicked, unless it is within `tolerance`
+    /// of the last one, as with the second click of a double click. Returns whether the click
+    /// was on the first vertex of a polygon, which closes it.
+    pub fn click(&mut self, point: Coord, tolerance: f64) -> bool {
+        let near = |vertex: Option<&Coord>| {
+            vertex.is_some_and(|vertex| (*vertex - point).x.hypot((*vertex - point).y) <= tolerance)
+        };
+        if self.tool == DrawTool::Polygon && self.vertices.len() >= 3 && near(self.vertices.first())
+        {
+            return true;
+        }
+        if !near(self.vertices.last()) {
+            self.vertices.push(point);
+        }
+        false
+    }
+
+    /// Removes the last vertex of a polygon, returning whether any are left.
+    pub fn undo(&mut self) -> bool {
+        self.vertices.pop();
+        !self.vertices.is_empty()
+    }
+
+    /// The line measured by a ruler so far, out to the pointer while it is being drawn.
+    pub fn measured(&self) -> LineString {
+        self.vertices
+            .iter()
+            .copied()
+            .chain(self.cursor.filter(|_| !self.finished))
+            .collect()
+    }
+
+    /// The shape drawn, or `None` if it has no area or, for a ruler, no length.
+    pub fn geometry(&self) -> Option<Geometry> {
+        let polygon = match self.tool {
+            DrawTool::Rectangle => {
+                let rect = Rect::new(*self.vertices.first()?, self.cursor?);
+                if rect.width() <= 0.0 || rect.height() <= 0.0 {
+                    return None;
+                }
+                rect.to_polygon()
+            }
+            DrawTool::Polygon => {
+                if self.vertices.len() < 3 {
+                    return None;
+                }
+                Polygon::new(LineString::from(self.vertices.clone()), Vec::new())
+            }
+            DrawTool::Ruler => {
+                return (self.vertices.len() >= 2)
+                    .then(|| Geometry::LineString(LineString::from(self.vertices.clone())));
+            }
+        };
+        Some(Geometry::Polygon(polygon))
+    }
+
+    /// The outline of the shape so far as SVG path data, in SVG coordinates.
+    pub fn path(&self) -> String {
+        let corners = match (self.tool, self.vertices.first(), self.cursor) {
+            (DrawTool::Rectangle, Some(start), Some(end)) => vec![
+                *start,
+                Coord {
+                    x: end.x,
+                    y: start.y,
+                },
+                end,
+                Coord {
+                    x: start.x,
+                    y: end.y,
+                },
+            ],
+            (DrawTool::Rectangle, _, _) => Vec::new(),
+            (DrawTool::Polygon, _, cursor) => self.vertices.iter().copied().chain(cursor).collect(),
+            (DrawTool::Ruler, _, _) => self.measured().0,
+        };
+        let mut path = corners
+            .iter()
+            .enumerate()
+            .map(|(i, Coord { x, y })| format!("{} {x} {}", if i == 0 { "M" } else { "L" }, -y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !path.is_empty() && self.tool != DrawTool::Ruler {
+            path.push_str(" Z");
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{coord, polygon};
+
+    use super::*;
+
+    #[test]
+    fn test_sketch() {
+        let mut rectangle = Sketch::new(DrawTool::Rectangle, coord! { x: 2.0, y: 1.0 });
+        assert_eq!(rectangle.geometry(), None);
+        rectangle.cursor = Some(coord! { x: 0.0, y: 3.0 });
+        assert_eq!(
+            rectangle.geometry(),
+            Some(Geometry::Polygon(
+                Rect::new(coord! { x: 0.0, y: 1.0 }, coord! { x: 2.0, y: 3.0 }).to_polygon()
+            ))
+        );
+        assert_eq!(rectangle.path(), "M 2 -1 L 0 -1 L 0 -3 L 2 -3 Z");
+
+        let mut triangle = Sketch::new(DrawTool::Polygon, coord! { x: 0.0, y: 0.0 });
+        assert!(!triangle.click(coord! { x: 0.05, y: 0.0 }, 0.1));
+        assert!(!triangle.click(coord! { x: 4.0, y: 0.0 }, 0.1));
+        assert_eq!(triangle.geometry(), None);
+        assert!(!triangle.click(coord! { x: 4.0, y: 3.0 }, 0.1));
+        assert!(!triangle.click(coord! { x: 4.0, y: 3.05 }, 0.1));
+        assert!(triangle.click(coord! { x: 0.05, y: 0.0 }, 0.1));
+        assert_eq!(
+            triangle.geometry(),
+            Some(Geometry::Polygon(polygon![
+                (x: 0.0, y: 0.0),
+                (x: 4.0, y: 0.0),
+                (x: 4.0, y: 3.0),
+            ]))
+        );
+        assert!(triangle.undo());
+        assert_eq!(triangle.geometry(), None);
+
+        let mut ruler = Sketch::new(DrawTool::Ruler, coord! { x: 0.0, y: 1.0 });
+        assert!(!ruler.click(coord! { x: 4.0, y: 1.0 }, 0.1));
+        assert!(!ruler.click(coord! { x: 4.0, y: 3.0 }, 0.1));
+        // Coming back to the start doesn't close a ruler.
+        assert!(!ruler.click(coord! { x: 0.0, y: 1.0 }, 0.1));
+        ruler.cursor = Some(coord! { x: 1.0, y: 2.0 });
+        assert_eq!(ruler.path(), "M 0 -1 L 4 -1 L 4 -3 L 0 -1 L 1 -2");
+        ruler.finished = true;
+        assert_eq!(ruler.measured().0.len(), 4);
+    }
+}
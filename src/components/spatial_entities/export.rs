@@ -0,0 +1,284 @@
+//! Saving the map as shown, e.g. for reports: as a standalone SVG document with its images
+//! embedded, as a PNG rendered from that document, or the entities shown as GeoJSON.
+//!
+//! [`SpatialEntities`](super::SpatialEntities) does this with its export buttons. Apps can do the
+//! same for a [`Scene`](super::Scene) of their own: wrap [`Scene::to_svg`](super::Scene::to_svg)
+//! in [`svg_document`], pass it through [`embed_images`], then [`rasterise`] it for a PNG, and
+//! save the result with [`download`] or [`download_text`]. [`to_geojson`] takes the entities of
+//! [`Scene::shown`](super::Scene::shown).
+
+use std::collections::HashMap;
+
+use geojson::{feature::Id, Feature, FeatureCollection, JsonObject, JsonValue};
+use implicit_clone::unsync::IString;
+use web_sys::{
+    wasm_bindgen::{closure::Closure, JsCast},
+    CanvasRenderingContext2d, HtmlAnchorElement, HtmlCanvasElement, HtmlImageElement,
+};
+use yew::Callback;
+
+use super::scene::escape_attribute;
+use crate::{crs::Crs, models};
+
+/// What the map can be saved as.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    Svg,
+    Png,
+    GeoJson,
+}
+
+impl ExportFormat {
+    /// The usual extension of files in the format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::GeoJson => "geojson",
+        }
+    }
+}
+
+/// A standalone SVG document of a map `width` by `height` pixels, from the SVG of the map and
+/// of the controls over it. The map is drawn on white, as it is on the page.
+pub fn svg_document(map_svg: &str, controls_svg: &str, width: f64, height: f64) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\"><rect width=\"{width}\" height=\"{height}\" fill=\"white\" />{}{}</svg>",
+        map_svg.trim(),
+        controls_svg,
+    )
+}
+
+/// The URLs of the images in an SVG document that are not yet embedded, each once.
+fn image_urls(svg: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    for part in svg.split(" href=\"").skip(1) {
+        let Some(end) = part.find('"') else {
+            continue;
+        };
+        let url = part[..end]
+            .replace("&quot;", "\"")
+            .replace("&lt;", "<")
+            .replace("&amp;", "&");
+        if !url.starts_with("data:") && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Embeds the images of an SVG document as data URLs, so that it stands alone and can be
+/// rendered to a canvas. Images that can't be fetched, e.g. because their server doesn't allow
+/// it, are left linked.
+pub async fn embed_images(svg: &str) -> String {
+    let mut embedded = HashMap::new();
+    for url in image_urls(svg) {
+        let response = match gloo_net::http::Request::get(&url).send().await {
+            Ok(response) if response.ok() => response,
+            Ok(response) => {
+                log::warn!("Couldn't embed {url}: {}", response.status());
+                continue;
+            }
+            Err(error) => {
+                log::warn!("Couldn't embed {url}: {error}");
+                continue;
+            }
+        };
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .unwrap_or_else(|| "image/png".into());
+        match response.binary().await {
+            Ok(bytes) => {
+                embedded.insert(url, data_url(&content_type, &bytes));
+            }
+            Err(error) => log::warn!("Couldn't embed {url}: {error}"),
+        }
+    }
+    let mut svg = svg.to_string();
+    for (url, data_url) in embedded {
+        svg = svg.replace(
+            &format!(" href=\"{}\"", escape_attribute(&url)),
+            &format!(" href=\"{data_url}\""),
+        );
+    }
+    svg
+}
+
+fn data_url(content_type: &str, bytes: &[u8]) -> String {
+    format!("data:{content_type};base64,{}", base64(bytes))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Renders an SVG document `width` by `height` pixels to a PNG `scale` times the size, calling
+/// `on_done` with it as a data URL, or with `None` if it couldn't be rendered. Images must be
+/// embedded first, as linked ones aren't drawn.
+pub fn rasterise(
+    svg: &str,
+    width: f64,
+    height: f64,
+    scale: f64,
+    on_done: Callback<Option<String>>,
+) {
+    let Ok(image) = HtmlImageElement::new() else {
+        on_done.emit(None);
+        return;
+    };
+    let loaded = image.clone();
+    let on_error = on_done.clone();
+    let onload = Closure::once_into_js(move || {
+        let png = (|| {
+            let canvas = web_sys::window()?
+                .document()?
+                .create_element("canvas")
+                .ok()?
+                .dyn_into::<HtmlCanvasElement>()
+                .ok()?;
+            canvas.set_width((width * scale).round() as u32);
+            canvas.set_height((height * scale).round() as u32);
+            let context = canvas
+                .get_context("2d")
+                .ok()??
+                .dyn_into::<CanvasRenderingContext2d>()
+                .ok()?;
+            context.scale(scale, scale).ok()?;
+            context
+                .draw_image_with_html_image_element_and_dw_and_dh(&loaded, 0.0, 0.0, width, height)
+                .ok()?;
+            canvas.to_data_url_with_type("image/png").ok()
+        })();
+        on_done.emit(png);
+    });
+    let onerror = Closure::once_into_js(move || on_error.emit(None));
+    image.set_onload(Some(onload.unchecked_ref()));
+    image.set_onerror(Some(onerror.unchecked_ref()));
+    image.set_src(&data_url("image/svg+xml", svg.as_bytes()));
+}
+
+/// Entities as a GeoJSON feature collection in CRS84, as GeoJSON requires, with their IDs,
+/// labels and properties. Properties given more than once become arrays.
+pub fn to_geojson<'a>(
+    entities: impl IntoIterator<Item = (&'a IString, &'a models::Entity)>,
+) -> FeatureCollection {
+    let features = entities
+        .into_iter()
+        .map(|(id, entity)| {
+            let mut properties = JsonObject::new();
+            if let Some(label) = &entity.label {
+                properties.insert("label".into(), label.as_str().into());
+            }
+            for (label, value) in entity.properties.iter() {
+                let value = JsonValue::from(value.as_str());
+                match properties.remove(label.as_str()) {
+                    Some(JsonValue::Array(mut values)) => {
+                        values.push(value);
+                        properties.insert(label.to_string(), JsonValue::Array(values));
+                    }
+                    Some(previous) => {
+                        properties
+                            .insert(label.to_string(), JsonValue::Array(vec![previous, value]));
+                    }
+                    None => {
+                        properties.insert(label.to_string(), value);
+                    }
+                }
+            }
+            Feature {
+                bbox: None,
+                geometry: entity.geometry.as_ref().map(|geometry| {
+                    geojson::Geometry::from(&entity.crs.reproject(geometry, &Crs::Crs84))
+                }),
+                id: Some(Id::String(id.to_string())),
+                properties: Some(properties),
+                foreign_members: None,
+            }
+        })
+        .collect();
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+}
+
+/// Saves a file from a URL, such as a data URL, with the browser.
+pub fn download(file_name: &str, url: &str) {
+    let Some(anchor) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("a").ok())
+        .and_then(|element| element.dyn_into::<HtmlAnchorElement>().ok())
+    else {
+        return;
+    };
+    anchor.set_href(url);
+    anchor.set_download(file_name);
+    anchor.click();
+}
+
+/// Saves text as a file with the browser.
+pub fn download_text(file_name: &str, content_type: &str, text: &str) {
+    download(file_name, &data_url(content_type, text.as_bytes()));
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use geo::{point, Geometry};
+    use implicit_clone::unsync::IArray;
+
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+
+        let svg = "<image href=\"http://example.com/tile?x=1&amp;y=2\" /><image href=\"data:image/png;base64,AA==\" /><image href=\"http://example.com/tile?x=1&amp;y=2\" />";
+        assert_eq!(image_urls(svg), ["http://example.com/tile?x=1&y=2"]);
+
+        let id = IString::from("http://example.com/a");
+        let entity = models::Entity {
+            crs: Crs::Epsg4326,
+            geometry: Some(Rc::new(Geometry::Point(point! { x: -16.9, y: 145.8 }))),
+            label: Some("Reef".into()),
+            properties: IArray::from(vec![
+                (IString::from("Zone"), IString::from("MNP")),
+                (IString::from("Zone"), IString::from("HPZ")),
+            ]),
+            ..models::Entity::default()
+        };
+        let geojson = serde_json::to_value(to_geojson([(&id, &entity)])).unwrap();
+        assert_eq!(
+            geojson,
+            serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [{
+                    "type": "Feature",
+                    "id": "http://example.com/a",
+                    "geometry": { "type": "Point", "coordinates": [145.8, -16.9] },
+                    "properties": { "label": "Reef", "Zone": ["MNP", "HPZ"] },
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Layers of entities, which are drawn, hidden and faded together, and the switcher that turns
+//! them on and off.
+
+use std::collections::HashMap;
+
+use implicit_clone::{
+    unsync::{IArray, IString},
+    ImplicitClone,
+};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use super::{Corner, EntityStyler, LayerStyle};
+use crate::models;
+
+#[derive(Clone, PartialEq)]
+pub struct Layer {
+    /// Identifies the layer while its other fields change.
+    pub id: IString,
+    pub name: IString,
+    pub entities: IArray<(IString, models::Entity)>,
+    /// How the entities are drawn, by default as the other entities of the map.
+    pub style: Option<EntityStyler>,
+    pub visible: bool,
+    /// From 0 for transparent to 1 for opaque.
+    pub opacity: f32,
+    /// Layers are drawn from the lowest z-order to the highest, in the order given when equal.
+    pub z_order: i32,
+}
+
+impl ImplicitClone for Layer {}
+
+impl Layer {
+    pub fn new(
+        id: impl Into<IString>,
+        name: impl Into<IString>,
+        entities: IArray<(IString, models::Entity)>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            entities,
+            style: None,
+            visible: true,
+            opacity: 1.0,
+            z_order: 0,
+        }
+    }
+
+    /// The layer as changed with the switcher.
+    pub(super) fn adjusted(&self, changes: &LayerChanges) -> Layer {
+        let (visible, opacity) = changes
+            .get(&self.id)
+            .copied()
+            .unwrap_or((self.visible, self.opacity));
+        Layer {
+            visible,
+            opacity,
+            ..self.clone()
+        }
+    }
+
+    pub(super) fn style(&self) -> LayerStyle {
+        LayerStyle {
+            visible: self.visible,
+            opacity: self.opacity.clamp(0.0, 1.0),
+            entity_style: self.style.clone(),
+        }
+    }
+}
+
+/// The visibility and opacity of layers as changed with the switcher, by layer ID.
+pub(super) type LayerChanges = HashMap<IString, (bool, f32)>;
+
+/// The layers in the order they are drawn.
+pub(super) fn ordered(layers: &[Layer]) -> Vec<&Layer> {
+    let mut ordered = layers.iter().collect::<Vec<_>>();
+    ordered.sort_by_key(|layer| layer.z_order);
+    ordered
+}
+
+/// A list of the layers, topmost first, each with a checkbox and an opacity slider. The base
+/// map, if there is one, is listed last with a checkbox of its own.
+pub(super) fn switcher(
+    layers: &[Layer],
+    base_map: Option<bool>,
+    corner: Corner,
+    on_toggle: Callback<Option<IString>>,
+    on_opacity: Callback<(IString, f32)>,
+) -> Html {
+    let layers = ordered(layers)
+        .into_iter()
+        .rev()
+        .map(|layer| {
+            let onchange = on_toggle.reform({
+                let id = layer.id.clone();
+                move |_: Event| Some(id.clone())
+            });
+            let oninput = on_opacity.reform({
+                let id = layer.id.clone();
+                move |event: InputEvent| {
+                    let opacity = event
+                        .target_dyn_into::<HtmlInputElement>()
+                        .map(|input| input.value_as_number() as f32)
+                        .unwrap_or(1.0);
+                    (id.clone(), opacity)
+                }
+            });
+            html! {
+                <li class="layer">
+                    <label>
+                        <input type="checkbox" checked={layer.visible} {onchange} />
+                        {layer.name.clone()}
+                    </label>
+                    <input
+                        type="range"
+                        min="0"
+                        max="1"
+                        step="0.05"
+                        value={layer.opacity.to_string()}
+                        disabled={!layer.visible}
+                        aria-label={format!("Opacity of {}", layer.name)}
+                        {oninput}
+                    />
+                </li>
+            }
+        })
+        .collect::<Html>();
+    let base_map = base_map.map(|visible| {
+        let onchange = on_toggle.reform(|_: Event| None);
+        html! {
+            <li class="layer base-map">
+                <label>
+                    <input type="checkbox" checked={visible} {onchange} />
+                    {"Base map"}
+                </label>
+            </li>
+        }
+    });
+    let style = format!("position: absolute; {}", corner.position_style());
+    html! {
+        <fieldset class="layer-switcher" {style}>
+            <legend>{"Layers"}</legend>
+            <ul>
+                {layers}
+                {base_map}
+            </ul>
+        </fieldset>
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_adjusted() {
+        let layer = |id: &'static str, z_order| Layer {
+            z_order,
+            ..Layer::new(id, id, IArray::default())
+        };
+        let layers = [
+            layer("observations", 2),
+            layer("routes", 1),
+            layer("zones", 1),
+        ];
+        assert_eq!(
+            ordered(&layers)
+                .iter()
+                .map(|layer| layer.id.as_str())
+                .collect::<Vec<_>>(),
+            ["routes", "zones", "observations"]
+        );
+
+        let changes = LayerChanges::from([(IString::from("zones"), (false, 0.5))]);
+        let zones = layers[2].adjusted(&changes);
+        assert_eq!((zones.visible, zones.opacity), (false, 0.5));
+        assert!(layers[0].adjusted(&changes).style() == LayerStyle::default());
+    }
+}
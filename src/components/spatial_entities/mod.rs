@@ -0,0 +1,1439 @@
+use std::rc::Rc;
+
+use chrono::{DateTime, Utc};
+use geo::{coord, Coord, Geometry, Rect};
+use html::ImplicitClone;
+use implicit_clone::unsync::{IArray, IString};
+use web_sys::{wasm_bindgen::JsCast, Element, EventTarget, HtmlCanvasElement};
+use yew::prelude::*;
+
+use crate::{
+    crs::Crs,
+    measure::{self, LengthUnit, Measurements},
+    models,
+};
+use canvas::Images;
+use controls::Furniture;
+use draw::Sketch;
+use layers::LayerChanges;
+use scene::{Hit, Layout};
+
+mod canvas;
+mod controls;
+mod draw;
+mod export;
+mod layers;
+mod scene;
+mod style;
+mod symbols;
+mod tiles;
+mod viewport;
+
+pub use canvas::{Renderer, CANVAS_THRESHOLD};
+pub use controls::{Corner, MapControls};
+pub use draw::DrawTool;
+pub use export::{
+    download, download_text, embed_images, rasterise, svg_document, to_geojson, ExportFormat,
+};
+pub use layers::Layer;
+pub use scene::{LayerStyle, RenderOptions, Scene, Simplification};
+pub use style::{
+    ColorRamp, Condition, EntityStyle, EntityStyler, LegendEntry, Paint, Rgb, StyleRule, StyleSpec,
+    Thematic, SET1,
+};
+pub use symbols::{Symbol, DEFAULT_SYMBOL_SIZE};
+pub use tiles::{reproject_rect, tile_bounds, TileId, TileLayer, TileScheme};
+
+/// The width assumed for the map until it has been laid out.
+const DEFAULT_WIDTH: f64 = 800.0;
+
+/// How far a pointer may move, in pixels, before a press becomes a drag rather than a click.
+const CLICK_SLOP: f64 = 3.0;
+
+/// How much the map zooms for each pixel of wheel scrolling.
+const WHEEL_ZOOM_RATE: f64 = 0.002;
+
+/// How many times larger than shown the map is drawn when exported as a PNG, for print.
+const PNG_SCALE: f64 = 2.0;
+
+#[derive(Clone, PartialEq)]
+pub struct Map {
+    /// A base image covering the extent, or an empty string for none, e.g. when tiles are used.
+    pub src: IString,
+    pub extent: Rc<Rect>,
+    /// The CRS of the extent. Entities are reprojected to it before they are drawn.
+    pub crs: Crs,
+}
+
+impl ImplicitClone for Map {}
+
+pub enum Message {
+    EntityClicked(IString),
+    /// A cluster of points with the given bounds clicked or chosen with the keyboard.
+    ClusterClicked(Rect),
+    /// An entity chosen with the keyboard.
+    EntityActivated(IString),
+    /// The entity under the pointer or with focus, if any, and where to show its tooltip in
+    /// pixels from the top left of the map.
+    Hovered(Option<(IString, Coord)>),
+    /// A click at a position in pixels on a map drawn on a canvas.
+    Clicked(Coord),
+    /// Moves keyboard focus some entities forwards or backwards on a map drawn on a canvas.
+    FocusStep(isize),
+    /// Activates the entity with keyboard focus on a map drawn on a canvas.
+    ActivateFocused,
+    /// Draws the canvas again, e.g. once an image for a symbol has loaded.
+    Redraw,
+    /// A pointer pressed, moved or released at a position in pixels while drawing a shape.
+    SketchDown(Coord),
+    SketchMoved(Coord),
+    SketchUp(Coord),
+    FinishSketch,
+    CancelSketch,
+    /// Removes the last vertex of a polygon being drawn.
+    UndoSketch,
+    /// Shows or hides the layer with the given ID, or the base map for `None`.
+    ToggleLayer(Option<IString>),
+    SetLayerOpacity(IString, f32),
+    Resized(f64),
+    /// A pointer pressed at a position in pixels within a map of the given size.
+    PointerDown {
+        id: i32,
+        position: Coord,
+        size: Coord,
+    },
+    PointerMoved {
+        id: i32,
+        position: Coord,
+    },
+    PointerUp(i32),
+    /// The wheel scrolled by some pixels at a position given as a fraction of the map size.
+    Wheel {
+        at: Coord,
+        delta: f64,
+    },
+    ResetView,
+    /// Saves the map as shown in a format.
+    Export(ExportFormat),
+    /// A map of the given height in pixels exported as SVG, once its images have been embedded.
+    Exported {
+        format: ExportFormat,
+        svg: String,
+        height: f64,
+    },
+}
+
+#[derive(PartialEq, Properties)]
+pub struct Props {
+    /// Entities drawn beneath those of any layers.
+    pub entities: IArray<(IString, models::Entity)>,
+    pub map: Map,
+    #[prop_or_default]
+    pub on_entity_click: Option<Callback<IString>>,
+    #[prop_or_default]
+    pub dynamic_viewport: bool,
+    /// Drawn beneath the map image, if any.
+    #[prop_or_default]
+    pub tiles: Option<TileLayer>,
+    /// The area shown, in the CRS of the map. When given, the view only changes through this
+    /// prop, so `on_viewport_change` should be used to keep it up to date.
+    #[prop_or_default]
+    pub viewport: Option<Rect>,
+    /// Called with the new viewport whenever the user pans, zooms or resets the view.
+    #[prop_or_default]
+    pub on_viewport_change: Option<Callback<Rect>>,
+    /// Whether the map can be panned and zoomed with a mouse or by touch.
+    #[prop_or(true)]
+    pub interactive: bool,
+    /// Zooms to an entity when it is clicked.
+    #[prop_or_default]
+    pub zoom_to_clicked: bool,
+    /// How each entity is drawn, by default in black.
+    #[prop_or_default]
+    pub entity_style: Option<EntityStyler>,
+    /// The IDs of entities to highlight as selected.
+    #[prop_or_default]
+    pub selected: IArray<IString>,
+    /// An entity to highlight as if hovered, e.g. when it is hovered in a linked view.
+    #[prop_or_default]
+    pub highlighted: Option<IString>,
+    /// Called with the ID of an entity when the pointer or focus moves onto it, and with `None`
+    /// when it leaves.
+    #[prop_or_default]
+    pub on_entity_hover: Option<Callback<Option<IString>>>,
+    /// Whether to show the label of an entity when it is hovered or has focus.
+    #[prop_or(true)]
+    pub tooltips: bool,
+    /// The labels of properties to show in tooltips after the entity label.
+    #[prop_or_default]
+    pub tooltip_properties: IArray<IString>,
+    /// Whether to show the length or area of an entity in its tooltip.
+    #[prop_or(true)]
+    pub tooltip_measurements: bool,
+    /// The units that lengths are shown in, in tooltips and by the ruler.
+    #[prop_or_default]
+    pub length_unit: LengthUnit,
+    /// How lines and polygons are simplified for the current zoom.
+    #[prop_or_default]
+    pub simplification: Simplification,
+    /// Point entities closer than this many pixels are drawn as a cluster, which zooms in to
+    /// them when clicked. Points are not clustered if not given.
+    #[prop_or_default]
+    pub cluster_distance: Option<f64>,
+    /// Whether entities are drawn as SVG or on a canvas, e.g. `Renderer::for_count(n)`.
+    #[prop_or_default]
+    pub renderer: Renderer,
+    /// A shape to draw on the map instead of panning it, e.g. to search within.
+    #[prop_or_default]
+    pub draw_tool: Option<DrawTool>,
+    /// Called with each shape drawn, as a polygon in the CRS of the map, or with each line
+    /// measured with the ruler.
+    #[prop_or_default]
+    pub on_draw: Option<Callback<Geometry>>,
+    /// The legend, scale bar, north arrow and attribution drawn over the map.
+    #[prop_or_default]
+    pub controls: MapControls,
+    /// The entries of the legend, by default those of a [`StyleSpec`] given as `entity_style`
+    /// that apply to some of the entities.
+    #[prop_or_default]
+    pub legend: Option<IArray<LegendEntry>>,
+    /// Credits for the entities or map image, shown after those of the tiles.
+    #[prop_or_default]
+    pub attribution: IString,
+    /// Groups of entities that can be turned on and off with the layer switcher. Entity IDs
+    /// should be unique across all layers and `entities`.
+    #[prop_or_default]
+    pub layers: IArray<Layer>,
+    /// Called with a layer when it is shown, hidden or faded with the layer switcher.
+    #[prop_or_default]
+    pub on_layer_change: Option<Callback<Layer>>,
+    /// Shows only the entities generated at or before this time, e.g. as chosen with a
+    /// [`TimeSlider`](crate::components::TimeSlider). Entities without a time are always shown.
+    #[prop_or_default]
+    pub until: Option<DateTime<Utc>>,
+    /// Whether to show buttons that save the map as SVG or PNG, or the entities shown as
+    /// GeoJSON.
+    #[prop_or_default]
+    pub export_buttons: bool,
+    /// The name of exported files, without the extension.
+    #[prop_or(IString::from("map"))]
+    pub export_name: IString,
+}
+
+/// A pan or pinch in progress.
+struct Gesture {
+    /// The viewport when the current set of pointers went down.
+    viewport: Rect,
+    /// The size of the map in pixels.
+    size: Coord,
+    /// Each pointer with where it started and where it is now, in pixels.
+    pointers: Vec<(i32, Coord, Coord)>,
+}
+
+impl Gesture {
+    fn viewport(&self) -> Rect {
+        let fraction = |c: Coord| coord! { x: c.x / self.size.x, y: c.y / self.size.y };
+        match self.pointers.as_slice() {
+            [(_, start, current)] => viewport::pan(&self.viewport, fraction(*current - *start)),
+            [(_, start_a, current_a), (_, start_b, current_b), ..] => {
+                let start_mid = (*start_a + *start_b) / 2.0;
+                let current_mid = (*current_a + *current_b) / 2.0;
+                let distance = |a: Coord, b: Coord| (a - b).x.hypot((a - b).y);
+                let factor = distance(*current_a, *current_b)
+                    / distance(*start_a, *start_b).max(f64::MIN_POSITIVE);
+                let zoomed = viewport::zoom_at(&self.viewport, fraction(start_mid), factor);
+                viewport::pan(&zoomed, fraction(current_mid - start_mid))
+            }
+            [] => self.viewport,
+        }
+    }
+
+    /// Starts afresh from the current state, e.g. when a pointer is added or removed.
+    fn rebase(&mut self) {
+        self.viewport = self.viewport();
+        for (_, start, current) in &mut self.pointers {
+            *start = *current;
+        }
+    }
+}
+
+pub struct SpatialEntities {
+    scene: Scene,
+    geometry_html: Html,
+    controls_html: Html,
+    container: NodeRef,
+    width: f64,
+    /// The viewport that fits the entities or map, as shown initially.
+    default_viewport: Option<Rect>,
+    /// The viewport chosen by the user when it is not controlled by the `viewport` prop.
+    viewport: Option<Rect>,
+    gesture: Option<Gesture>,
+    /// Whether the latest press moved the map, in which case it is not a click.
+    dragged: bool,
+    /// The entity under the pointer or with focus, and where its tooltip goes.
+    hovered: Option<(IString, Coord)>,
+    /// What is drawn on the canvases, when entities are drawn on a canvas.
+    layout: Option<Layout>,
+    /// Whether the entity canvas is out of date with the layout.
+    redraw: bool,
+    canvas: NodeRef,
+    /// The canvas over the entities that highlights are drawn on.
+    overlay: NodeRef,
+    images: Images,
+    /// The shape being drawn, if any.
+    sketch: Option<Sketch>,
+    /// The layers shown, hidden or faded with the layer switcher since the parent last did so.
+    layer_changes: LayerChanges,
+    base_map_visible: bool,
+}
+
+/// The SVG `viewBox` showing a viewport, flipping y so that north is up.
+fn view_box(viewport: &Rect) -> String {
+    format!(
+        "{} {} {} {}",
+        viewport.min().x,
+        -viewport.max().y,
+        viewport.width(),
+        viewport.height()
+    )
+}
+
+/// The group of the entity that an element is part of, if any.
+fn entity_element(target: Option<EventTarget>) -> Option<Element> {
+    let mut element = target?.dyn_into::<Element>().ok()?;
+    while !element
+        .get_attribute("class")
+        .is_some_and(|class| class.starts_with("entity-region"))
+    {
+        element = element.parent_element()?;
+    }
+    Some(element)
+}
+
+/// The bounds of the cluster that an element is part of, if any.
+fn cluster_bounds(target: Option<EventTarget>) -> Option<Rect> {
+    let mut element = target?.dyn_into::<Element>().ok()?;
+    while element.get_attribute("class").as_deref() != Some("cluster") {
+        element = element.parent_element()?;
+    }
+    let bounds = element
+        .get_attribute("data-bounds")?
+        .split(' ')
+        .map(str::parse)
+        .collect::<Result<Vec<f64>, _>>()
+        .ok()?;
+    match bounds.as_slice() {
+        [min_x, min_y, max_x, max_y] => Some(Rect::new(
+            coord! { x: *min_x, y: *min_y },
+            coord! { x: *max_x, y: *max_y },
+        )),
+        _ => None,
+    }
+}
+
+/// The properties of an entity to show in its tooltip, in the order their labels are given.
+fn tooltip_rows(entity: &models::Entity, labels: &[IString]) -> Vec<(IString, IString)> {
+    labels
+        .iter()
+        .flat_map(|label| {
+            entity
+                .properties
+                .iter()
+                .filter(move |(property, _)| property == label)
+        })
+        .collect()
+}
+
+/// The length or area of an entity, to show in its tooltip.
+fn tooltip_measurements(entity: &models::Entity, unit: LengthUnit) -> Vec<(&'static str, String)> {
+    Measurements::of_entity(entity)
+        .map(|measurements| measurements.rows(unit))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(label, _)| matches!(*label, "Length" | "Area"))
+        .collect()
+}
+
+/// The geometry of an entity in the CRS of the map.
+fn map_geometry(entity: &models::Entity, map: &Map) -> Option<Geometry> {
+    entity
+        .geometry
+        .as_ref()
+        .map(|geometry| entity.crs.reproject(geometry, &map.crs))
+}
+
+/// The entities of each layer in the order they are drawn, beginning with those of no layer.
+fn layer_entities(props: &Props) -> Vec<IArray<(IString, models::Entity)>> {
+    [props.entities.clone()]
+        .into_iter()
+        .chain(
+            layers::ordered(&props.layers)
+                .into_iter()
+                .map(|layer| layer.entities.clone()),
+        )
+        .collect()
+}
+
+/// Whether two lists of entities are the same, without comparing every entity when they are
+/// clones of one list, as when the parent renders again for other reasons.
+fn same_entities(
+    a: &IArray<(IString, models::Entity)>,
+    b: &IArray<(IString, models::Entity)>,
+) -> bool {
+    match (a, b) {
+        (IArray::Rc(a), IArray::Rc(b)) if Rc::ptr_eq(a, b) => true,
+        _ => a == b,
+    }
+}
+
+/// Whether the layers differ in anything that is drawn, including their entities.
+fn layers_changed(props: &Props, old_props: &Props) -> bool {
+    props.layers.len() != old_props.layers.len()
+        || props
+            .layers
+            .iter()
+            .zip(old_props.layers.iter())
+            .any(|(layer, old)| {
+                (
+                    &layer.id,
+                    &layer.name,
+                    &layer.style,
+                    layer.visible,
+                    layer.opacity,
+                    layer.z_order,
+                ) != (
+                    &old.id,
+                    &old.name,
+                    &old.style,
+                    old.visible,
+                    old.opacity,
+                    old.z_order,
+                ) || !same_entities(&layer.entities, &old.entities)
+            })
+}
+
+impl SpatialEntities {
+    /// The viewport around all the entities, whether their layers are visible or not, so that
+    /// the view stays put when layers are turned on and off.
+    fn default_viewport(props: &Props) -> Option<Rect> {
+        let entities = layer_entities(props);
+        let mut entities = entities.iter().flat_map(|entities| entities.iter());
+        if props.dynamic_viewport {
+            let geometries = entities
+                .filter_map(|(_, entity)| map_geometry(&entity, &props.map))
+                .collect::<Vec<_>>();
+            viewport::around(&geometries)
+        } else if entities.next().is_some() {
+            Some(*props.map.extent)
+        } else {
+            None
+        }
+    }
+
+    fn scene(props: &Props) -> Scene {
+        let entities = layer_entities(props);
+        let slices = entities
+            .iter()
+            .map(|entities| &entities[..])
+            .collect::<Vec<_>>();
+        let mut scene = Scene::layered(&slices, &props.map);
+        scene.set_until(props.until);
+        scene
+    }
+
+    /// The layers as changed with the layer switcher.
+    fn layers(&self, ctx: &Context<Self>) -> Vec<Layer> {
+        ctx.props()
+            .layers
+            .iter()
+            .map(|layer| layer.adjusted(&self.layer_changes))
+            .collect()
+    }
+
+    fn apply_layer_styles(&mut self, ctx: &Context<Self>) {
+        let layers = self.layers(ctx);
+        let styles = [LayerStyle::default()]
+            .into_iter()
+            .chain(layers::ordered(&layers).into_iter().map(Layer::style))
+            .collect();
+        self.scene.set_layer_styles(styles);
+    }
+
+    /// Applies a change made with the layer switcher to a layer.
+    fn change_layer(&mut self, ctx: &Context<Self>, id: &IString, change: impl Fn(&mut Layer)) {
+        let Some(mut layer) = self.layers(ctx).into_iter().find(|layer| &layer.id == id) else {
+            return;
+        };
+        change(&mut layer);
+        self.layer_changes
+            .insert(id.clone(), (layer.visible, layer.opacity));
+        if let Some(on_layer_change) = &ctx.props().on_layer_change {
+            on_layer_change.emit(layer);
+        }
+        self.apply_layer_styles(ctx);
+        self.render_geometry(ctx);
+    }
+
+    fn current_viewport(&self, ctx: &Context<Self>) -> Option<Rect> {
+        ctx.props()
+            .viewport
+            .or(self.viewport)
+            .or(self.default_viewport)
+    }
+
+    fn render_options<'a>(&self, ctx: &'a Context<Self>) -> RenderOptions<'a> {
+        let props = ctx.props();
+        RenderOptions {
+            tiles: props.tiles.as_ref(),
+            entity_style: props.entity_style.as_ref(),
+            selected: &props.selected,
+            simplification: props.simplification,
+            cluster_distance: props.cluster_distance,
+            hide_base_map: !self.base_map_visible,
+        }
+    }
+
+    fn render_geometry(&mut self, ctx: &Context<Self>) {
+        let props = ctx.props();
+        let options = self.render_options(ctx);
+        self.layout = None;
+        self.geometry_html = match self.current_viewport(ctx) {
+            Some(viewport) if ctx.props().renderer == Renderer::Canvas => {
+                self.layout = Some(self.scene.layout(&options, self.width, &viewport));
+                self.redraw = true;
+                let svg = self.scene.background_svg(&options, self.width, &viewport);
+                Html::from_html_unchecked(svg.into())
+            }
+            Some(viewport) => {
+                Html::from_html_unchecked(self.scene.to_svg(&options, self.width, &viewport).into())
+            }
+            None => html!(),
+        };
+        self.controls_html = match self.current_viewport(ctx) {
+            Some(viewport) => {
+                let svg = props
+                    .controls
+                    .to_svg(&self.furniture(ctx), &viewport, self.width);
+                Html::from_html_unchecked(svg.into())
+            }
+            None => html!(),
+        };
+    }
+
+    /// The map as shown as a standalone SVG document, with its height in pixels. Entities are
+    /// drawn as SVG even when shown on a canvas.
+    fn export_svg(&self, ctx: &Context<Self>) -> Option<(String, f64)> {
+        let viewport = self.current_viewport(ctx)?;
+        let height = self.width * viewport.height() / viewport.width();
+        let map = self
+            .scene
+            .to_svg(&self.render_options(ctx), self.width, &viewport);
+        let controls = ctx
+            .props()
+            .controls
+            .to_svg(&self.furniture(ctx), &viewport, self.width);
+        Some((svg_document(&map, &controls, self.width, height), height))
+    }
+
+    /// What the controls over the map show.
+    fn furniture<'a>(&self, ctx: &'a Context<Self>) -> Furniture<'a> {
+        let props = ctx.props();
+        let legend = match &props.legend {
+            Some(legend) => legend.to_vec(),
+            None => self.legend(ctx),
+        };
+        let attribution = props
+            .tiles
+            .iter()
+            .map(|tiles| tiles.attribution.clone())
+            .chain([props.attribution.clone()])
+            .filter(|attribution| !attribution.is_empty())
+            .collect::<Vec<_>>()
+            .join(" | ");
+        Furniture {
+            legend,
+            attribution,
+            crs: &props.map.crs,
+        }
+    }
+
+    /// The legend of the styles of the entities, and of each visible layer, topmost first. A
+    /// layer without rules or thematic colours is shown as one entry.
+    fn legend(&self, ctx: &Context<Self>) -> Vec<LegendEntry> {
+        let props = ctx.props();
+        let spec = |styler: Option<&EntityStyler>| match styler {
+            Some(EntityStyler::Spec(spec)) => Some(spec.clone()),
+            Some(EntityStyler::Callback(_)) => None,
+            None => Some(Rc::default()),
+        };
+        let layers = self.layers(ctx);
+        let layer_entries = layers::ordered(&layers)
+            .into_iter()
+            .rev()
+            .filter(|layer| layer.visible)
+            .flat_map(|layer| {
+                let Some(spec) = spec(layer.style.as_ref().or(props.entity_style.as_ref())) else {
+                    return Vec::new();
+                };
+                let entries = spec.legend(&layer.entities);
+                if entries.is_empty() {
+                    spec.summary(layer.name.clone(), &layer.entities)
+                        .into_iter()
+                        .collect()
+                } else {
+                    entries
+                }
+            });
+        let entries = match props.entity_style.as_ref() {
+            Some(EntityStyler::Spec(spec)) => spec.legend(&props.entities),
+            _ => Vec::new(),
+        };
+        layer_entries.chain(entries).collect()
+    }
+
+    /// Draws the entities on the canvas, if they are drawn on one.
+    fn draw(&mut self, ctx: &Context<Self>) {
+        let (Some(layout), Some(canvas)) = (&self.layout, self.canvas.cast::<HtmlCanvasElement>())
+        else {
+            return;
+        };
+        let on_load = ctx.link().callback(|_| Message::Redraw);
+        canvas::draw(&canvas, layout, &mut self.images, &on_load);
+        self.redraw = false;
+    }
+
+    /// Draws the highlights over the canvas, if entities are drawn on one.
+    fn draw_highlights(&self, ctx: &Context<Self>) {
+        let (Some(layout), Some(overlay)) =
+            (&self.layout, self.overlay.cast::<HtmlCanvasElement>())
+        else {
+            return;
+        };
+        let highlighted = self
+            .hovered
+            .iter()
+            .map(|(id, _)| id.clone())
+            .chain(ctx.props().highlighted.clone())
+            .collect::<Vec<_>>();
+        canvas::draw_highlights(&overlay, layout, &ctx.props().selected, &highlighted);
+    }
+
+    /// Changes the entity under the pointer or with focus, returning whether to render again.
+    fn set_hovered(&mut self, ctx: &Context<Self>, hovered: Option<(IString, Coord)>) -> bool {
+        let id = |hovered: &Option<(IString, Coord)>| hovered.as_ref().map(|(id, _)| id.clone());
+        let moved = id(&hovered) != id(&self.hovered);
+        if moved {
+            if let Some(on_entity_hover) = &ctx.props().on_entity_hover {
+                on_entity_hover.emit(id(&hovered));
+            }
+        }
+        let changed = hovered != self.hovered;
+        self.hovered = hovered;
+        if moved {
+            self.draw_highlights(ctx);
+        }
+        changed && ctx.props().tooltips
+    }
+
+    /// Reports a new viewport, and shows it unless the viewport is controlled by the parent.
+    fn set_viewport(&mut self, ctx: &Context<Self>, viewport: Rect) -> bool {
+        if let Some(on_viewport_change) = &ctx.props().on_viewport_change {
+            on_viewport_change.emit(viewport);
+        }
+        if ctx.props().viewport.is_some() {
+            return false;
+        }
+        self.viewport = Some(viewport);
+        self.render_geometry(ctx);
+        true
+    }
+
+    /// Moves the rendered map without rebuilding it, while a gesture is in progress. Entities
+    /// drawn on a canvas are drawn again.
+    fn preview_viewport(&mut self, ctx: &Context<Self>, viewport: &Rect) {
+        if let Some(svg) = self
+            .container
+            .cast::<Element>()
+            .and_then(|container| container.first_element_child())
+        {
+            let _ = svg.set_attribute("viewBox", &view_box(viewport));
+        }
+        if self.layout.is_some() {
+            let options = self.render_options(ctx);
+            self.layout = Some(self.scene.layout(&options, self.width, viewport));
+            self.draw(ctx);
+            self.draw_highlights(ctx);
+        }
+    }
+
+    /// Marks the entity highlighted by the parent, without rebuilding the map.
+    fn mark_highlighted(&self, ctx: &Context<Self>) {
+        let Some(regions) = self
+            .container
+            .cast::<Element>()
+            .and_then(|container| container.query_selector_all("g[class^=entity-region]").ok())
+        else {
+            return;
+        };
+        for i in 0..regions.length() {
+            let Some(region) = regions
+                .item(i)
+                .and_then(|node| node.dyn_into::<Element>().ok())
+            else {
+                continue;
+            };
+            let highlighted = ctx
+                .props()
+                .highlighted
+                .as_ref()
+                .is_some_and(|id| id.as_str() == region.id());
+            let _ = region
+                .class_list()
+                .toggle_with_force("highlighted", highlighted);
+        }
+    }
+
+    /// The point of the map at a position in pixels, in the CRS of the map.
+    fn map_point(&self, ctx: &Context<Self>, position: Coord) -> Option<Coord> {
+        let viewport = self.current_viewport(ctx)?;
+        let height = self.width * viewport.height() / viewport.width();
+        Some(viewport::point_at(
+            &viewport,
+            coord! { x: position.x / self.width, y: position.y / height },
+        ))
+    }
+
+    /// Emits the shape being drawn, if it is complete. A finished ruler is kept to be shown.
+    fn finish_sketch(&mut self, ctx: &Context<Self>) -> bool {
+        // A finished ruler stays until the next sketch starts.
+        let Some(mut sketch) = self.sketch.take_if(|sketch| !sketch.finished) else {
+            return false;
+        };
+        let geometry = sketch.geometry();
+        if let Some((geometry, on_draw)) = geometry.clone().zip(ctx.props().on_draw.as_ref()) {
+            on_draw.emit(geometry);
+        }
+        if sketch.tool == DrawTool::Ruler && geometry.is_some() {
+            sketch.finished = true;
+            sketch.cursor = None;
+            self.sketch = Some(sketch);
+        }
+        true
+    }
+
+    /// Zooms to fit some bounds, e.g. those of an entity.
+    fn zoom_to(&mut self, ctx: &Context<Self>, bounds: &Rect) -> bool {
+        match self.current_viewport(ctx) {
+            Some(current) => self.set_viewport(ctx, viewport::fit(bounds, &current)),
+            None => false,
+        }
+    }
+}
+
+/// The position of a pointer in pixels from the top left of the element it is over, and the
+/// size of that element.
+fn pointer_position(event: &MouseEvent) -> Option<(Coord, Coord)> {
+    let element = event.current_target()?.dyn_into::<Element>().ok()?;
+    let rect = element.get_bounding_client_rect();
+    Some((
+        coord! {
+            x: event.client_x() as f64 - rect.left(),
+            y: event.client_y() as f64 - rect.top(),
+        },
+        coord! { x: rect.width(), y: rect.height() },
+    ))
+}
+
+impl Component for SpatialEntities {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(ctx: &yew::Context<Self>) -> Self {
+        let mut spatial_entities = Self {
+            scene: Self::scene(ctx.props()),
+            geometry_html: html!(),
+            controls_html: html!(),
+            container: NodeRef::default(),
+            width: DEFAULT_WIDTH,
+            default_viewport: Self::default_viewport(ctx.props()),
+            viewport: None,
+            gesture: None,
+            dragged: false,
+            hovered: None,
+            layout: None,
+            redraw: false,
+            canvas: NodeRef::default(),
+            overlay: NodeRef::default(),
+            images: Images::default(),
+            sketch: None,
+            layer_changes: LayerChanges::new(),
+            base_map_visible: true,
+        };
+        spatial_entities.apply_layer_styles(ctx);
+        spatial_entities.render_geometry(ctx);
+        spatial_entities
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        let layers_changed = layers_changed(props, old_props);
+        let entities_changed =
+            layers_changed || !same_entities(&props.entities, &old_props.entities);
+        if entities_changed
+            || props.map != old_props.map
+            || props.dynamic_viewport != old_props.dynamic_viewport
+        {
+            // New content is shown in full, as it was before the map became interactive.
+            self.default_viewport = Self::default_viewport(props);
+            self.viewport = None;
+            self.hovered = None;
+            self.scene = Self::scene(props);
+        }
+        self.scene.set_until(props.until);
+        if layers_changed {
+            // The parent takes back control of layers it shows, hides or fades itself.
+            for layer in props.layers.iter() {
+                let old_layer = old_props.layers.iter().find(|old| old.id == layer.id);
+                if old_layer
+                    .is_none_or(|old| (old.visible, old.opacity) != (layer.visible, layer.opacity))
+                {
+                    self.layer_changes.remove(&layer.id);
+                }
+            }
+            self.apply_layer_styles(ctx);
+        }
+        if props.draw_tool != old_props.draw_tool || props.map != old_props.map {
+            self.sketch = None;
+        }
+        // Selected entities are marked in the SVG, but on a canvas they are drawn over it with
+        // the highlights. Highlights are drawn once rendered, without rebuilding the map.
+        if entities_changed
+            || props.map != old_props.map
+            || props.dynamic_viewport != old_props.dynamic_viewport
+            || props.until != old_props.until
+            || props.viewport != old_props.viewport
+            || props.tiles != old_props.tiles
+            || props.entity_style != old_props.entity_style
+            || props.simplification != old_props.simplification
+            || props.cluster_distance != old_props.cluster_distance
+            || props.renderer != old_props.renderer
+            || props.controls != old_props.controls
+            || props.legend != old_props.legend
+            || props.attribution != old_props.attribution
+            || (props.renderer == Renderer::Svg && props.selected != old_props.selected)
+        {
+            self.render_geometry(ctx);
+        }
+        true
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        if self.layout.is_some() {
+            if self.redraw {
+                self.draw(ctx);
+            }
+            self.draw_highlights(ctx);
+        } else {
+            self.mark_highlighted(ctx);
+        }
+        // The width decides the size of tiles and point symbols.
+        if let Some(container) = self.container.cast::<Element>() {
+            let width = container.client_width() as f64;
+            if width > 0.0 && (width - self.width).abs() >= 1.0 {
+                ctx.link().send_message(Message::Resized(width));
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::EntityClicked(_) | Message::ClusterClicked(_) if self.dragged => false,
+            Message::EntityClicked(entity_id) | Message::EntityActivated(entity_id) => {
+                let bounds = ctx
+                    .props()
+                    .zoom_to_clicked
+                    .then(|| self.scene.bounds(&entity_id))
+                    .flatten();
+                if let Some(on_entity_click) = &ctx.props().on_entity_click {
+                    on_entity_click.emit(entity_id);
+                }
+                bounds.is_some_and(|bounds| self.zoom_to(ctx, &bounds))
+            }
+            Message::ClusterClicked(bounds) => self.zoom_to(ctx, &bounds),
+            Message::Hovered(hovered) => self.set_hovered(ctx, hovered),
+            Message::Clicked(position) => {
+                match self.layout.as_ref().and_then(|layout| layout.hit(position)) {
+                    Some(Hit::Entity(id)) => {
+                        Component::update(self, ctx, Message::EntityClicked(id))
+                    }
+                    Some(Hit::Cluster(bounds)) => {
+                        Component::update(self, ctx, Message::ClusterClicked(bounds))
+                    }
+                    None => false,
+                }
+            }
+            Message::FocusStep(step) => {
+                let Some(layout) = &self.layout else {
+                    return false;
+                };
+                let count = layout.entities.len() as isize;
+                if count == 0 {
+                    return false;
+                }
+                let current = self
+                    .hovered
+                    .as_ref()
+                    .and_then(|(id, _)| layout.entities.iter().position(|placed| &placed.id == id));
+                let next = match current {
+                    Some(current) => (current as isize + step).rem_euclid(count),
+                    None if step > 0 => 0,
+                    None => count - 1,
+                };
+                let id = layout.entities[next as usize].id.clone();
+                let position = self
+                    .scene
+                    .bounds(&id)
+                    .map(|bounds| {
+                        let center = bounds.center();
+                        layout.to_pixels(coord! { x: center.x, y: -center.y })
+                    })
+                    .unwrap_or(Coord::zero());
+                self.set_hovered(ctx, Some((id, position)))
+            }
+            Message::ActivateFocused => match self.hovered.clone() {
+                Some((id, _)) => Component::update(self, ctx, Message::EntityActivated(id)),
+                None => false,
+            },
+            Message::Redraw => {
+                self.draw(ctx);
+                false
+            }
+            Message::SketchDown(position) => {
+                match (ctx.props().draw_tool, self.map_point(ctx, position)) {
+                    (Some(DrawTool::Rectangle), Some(point)) => {
+                        self.sketch = Some(Sketch::new(DrawTool::Rectangle, point));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Message::SketchMoved(position) => {
+                let point = self.map_point(ctx, position);
+                match (&mut self.sketch, point) {
+                    (Some(sketch), Some(point)) if !sketch.finished => {
+                        sketch.cursor = Some(point);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Message::SketchUp(position) => {
+                let (Some(tool), Some(point), Some(viewport)) = (
+                    ctx.props().draw_tool,
+                    self.map_point(ctx, position),
+                    self.current_viewport(ctx),
+                ) else {
+                    return false;
+                };
+                let tolerance = CLICK_SLOP * viewport.width() / self.width;
+                match (tool, &mut self.sketch) {
+                    (DrawTool::Rectangle, Some(sketch)) => {
+                        sketch.cursor = Some(point);
+                        self.finish_sketch(ctx)
+                    }
+                    (DrawTool::Polygon, Some(sketch)) => {
+                        if sketch.click(point, tolerance) {
+                            self.finish_sketch(ctx)
+                        } else {
+                            true
+                        }
+                    }
+                    (DrawTool::Polygon, None) => {
+                        self.sketch = Some(Sketch::new(DrawTool::Polygon, point));
+                        true
+                    }
+                    (DrawTool::Ruler, Some(sketch)) if !sketch.finished => {
+                        sketch.click(point, tolerance);
+                        true
+                    }
+                    (DrawTool::Ruler, _) => {
+                        self.sketch = Some(Sketch::new(DrawTool::Ruler, point));
+                        true
+                    }
+                    (DrawTool::Rectangle, None) => false,
+                }
+            }
+            Message::FinishSketch => self.finish_sketch(ctx),
+            Message::CancelSketch => self.sketch.take().is_some(),
+            Message::UndoSketch => match &mut self.sketch {
+                Some(sketch) if !sketch.finished => {
+                    if !sketch.undo() {
+                        self.sketch = None;
+                    }
+                    true
+                }
+                _ => false,
+            },
+            Message::ToggleLayer(Some(id)) => {
+                self.change_layer(ctx, &id, |layer| layer.visible = !layer.visible);
+                true
+            }
+            Message::ToggleLayer(None) => {
+                self.base_map_visible = !self.base_map_visible;
+                self.render_geometry(ctx);
+                true
+            }
+            Message::SetLayerOpacity(id, opacity) => {
+                self.change_layer(ctx, &id, |layer| layer.opacity = opacity);
+                true
+            }
+            Message::Resized(width) => {
+                self.width = width;
+                self.render_geometry(ctx);
+                true
+            }
+            Message::PointerDown { id, position, size } => {
+                let Some(viewport) = self.current_viewport(ctx) else {
+                    return false;
+                };
+                let gesture = self.gesture.get_or_insert_with(|| Gesture {
+                    viewport,
+                    size,
+                    pointers: Vec::new(),
+                });
+                if gesture.pointers.is_empty() {
+                    self.dragged = false;
+                }
+                gesture.rebase();
+                gesture.pointers.push((id, position, position));
+                false
+            }
+            Message::PointerMoved { id, position } => {
+                if let Some(layout) = self.layout.as_ref().filter(|_| self.gesture.is_none()) {
+                    let hovered = match layout.hit(position) {
+                        Some(Hit::Entity(id)) => Some((id, position)),
+                        _ => None,
+                    };
+                    return self.set_hovered(ctx, hovered);
+                }
+                let tooltip = ctx.props().tooltips && self.hovered.is_some();
+                if let Some((_, at)) = &mut self.hovered {
+                    *at = position;
+                }
+                let Some(gesture) = &mut self.gesture else {
+                    return tooltip;
+                };
+                let was_dragged = self.dragged;
+                if let Some((_, start, current)) = gesture
+                    .pointers
+                    .iter_mut()
+                    .find(|(pointer, _, _)| *pointer == id)
+                {
+                    *current = position;
+                    let moved = *current - *start;
+                    self.dragged |= moved.x.hypot(moved.y) > CLICK_SLOP;
+                }
+                if self.dragged {
+                    let viewport = gesture.viewport();
+                    self.preview_viewport(ctx, &viewport);
+                }
+                // The tooltip is hidden once dragging starts.
+                tooltip && self.dragged && !was_dragged
+            }
+            Message::PointerUp(id) => {
+                let Some(gesture) = &mut self.gesture else {
+                    return false;
+                };
+                gesture.rebase();
+                gesture.pointers.retain(|(pointer, _, _)| *pointer != id);
+                if !gesture.pointers.is_empty() {
+                    return false;
+                }
+                let viewport = gesture.viewport;
+                self.gesture = None;
+                self.dragged && self.set_viewport(ctx, viewport)
+            }
+            Message::Wheel { at, delta } => match self.current_viewport(ctx) {
+                Some(current) => {
+                    let factor = (-delta * WHEEL_ZOOM_RATE).exp();
+                    self.set_viewport(ctx, viewport::zoom_at(&current, at, factor))
+                }
+                None => false,
+            },
+            Message::ResetView => match self.default_viewport {
+                Some(default_viewport) => self.set_viewport(ctx, default_viewport),
+                None => false,
+            },
+            Message::Export(format) => {
+                let file_name = format!("{}.{}", ctx.props().export_name, format.extension());
+                match format {
+                    ExportFormat::GeoJson => {
+                        let Some(viewport) = self.current_viewport(ctx) else {
+                            return false;
+                        };
+                        match serde_json::to_string(&to_geojson(self.scene.shown(&viewport))) {
+                            Ok(geojson) => {
+                                download_text(&file_name, "application/geo+json", &geojson)
+                            }
+                            Err(error) => log::warn!("Couldn't export {file_name}: {error}"),
+                        }
+                    }
+                    ExportFormat::Svg | ExportFormat::Png => {
+                        if let Some((svg, height)) = self.export_svg(ctx) {
+                            ctx.link().send_future(async move {
+                                let svg = embed_images(&svg).await;
+                                Message::Exported {
+                                    format,
+                                    svg,
+                                    height,
+                                }
+                            });
+                        }
+                    }
+                }
+                false
+            }
+            Message::Exported {
+                format,
+                svg,
+                height,
+            } => {
+                let file_name = format!("{}.{}", ctx.props().export_name, format.extension());
+                if format == ExportFormat::Png {
+                    let on_done = Callback::from(move |png: Option<String>| match png {
+                        Some(png) => download(&file_name, &png),
+                        None => log::warn!("Couldn't render {file_name}"),
+                    });
+                    rasterise(&svg, self.width, height, PNG_SCALE, on_done);
+                } else {
+                    download_text(&file_name, "image/svg+xml", &svg);
+                }
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let canvas = ctx.props().renderer == Renderer::Canvas;
+        let drawing = ctx.props().draw_tool.is_some() && self.current_viewport(ctx).is_some();
+        let onclick = ctx.link().batch_callback(move |event: MouseEvent| {
+            if drawing {
+                return None;
+            }
+            if canvas {
+                return pointer_position(&event).map(|(position, _)| Message::Clicked(position));
+            }
+            cluster_bounds(event.target())
+                .map(Message::ClusterClicked)
+                .or_else(|| {
+                    entity_element(event.target())
+                        .map(|element| Message::EntityClicked(element.id().into()))
+                })
+        });
+        let ondblclick =
+            drawing.then(|| ctx.link().callback(|_: MouseEvent| Message::FinishSketch));
+        let onkeydown = ctx.link().batch_callback(move |event: KeyboardEvent| {
+            let message = if drawing {
+                match event.key().as_str() {
+                    "Enter" => Message::FinishSketch,
+                    "Escape" => Message::CancelSketch,
+                    "Backspace" => Message::UndoSketch,
+                    _ => return None,
+                }
+            } else if canvas {
+                match event.key().as_str() {
+                    "ArrowRight" | "ArrowDown" => Message::FocusStep(1),
+                    "ArrowLeft" | "ArrowUp" => Message::FocusStep(-1),
+                    "Enter" | " " => Message::ActivateFocused,
+                    "Escape" => Message::Hovered(None),
+                    _ => return None,
+                }
+            } else if matches!(event.key().as_str(), "Enter" | " ") {
+                cluster_bounds(event.target())
+                    .map(Message::ClusterClicked)
+                    .or_else(|| {
+                        entity_element(event.target())
+                            .map(|element| Message::EntityActivated(element.id().into()))
+                    })?
+            } else {
+                return None;
+            };
+            event.prevent_default();
+            Some(message)
+        });
+        // Entities drawn on a canvas are found by hit-testing as the pointer moves instead.
+        let onpointerover = (!canvas).then(|| {
+            ctx.link().callback(|event: PointerEvent| {
+                let hovered = entity_element(event.target())
+                    .zip(pointer_position(&event))
+                    .map(|(element, (position, _))| (element.id().into(), position));
+                Message::Hovered(hovered)
+            })
+        });
+        let onpointerleave = ctx
+            .link()
+            .callback(|_: PointerEvent| Message::Hovered(None));
+        let onfocusin = (!canvas).then(|| {
+            ctx.link().callback(|event: FocusEvent| {
+                let container = event
+                    .current_target()
+                    .and_then(|target| target.dyn_into::<Element>().ok());
+                let hovered =
+                    entity_element(event.target())
+                        .zip(container)
+                        .map(|(element, container)| {
+                            let (rect, origin) = (
+                                element.get_bounding_client_rect(),
+                                container.get_bounding_client_rect(),
+                            );
+                            let position = coord! {
+                                x: rect.left() + rect.width() / 2.0 - origin.left(),
+                                y: rect.top() + rect.height() / 2.0 - origin.top(),
+                            };
+                            (element.id().into(), position)
+                        });
+                Message::Hovered(hovered)
+            })
+        });
+        let onfocusout = ctx.link().callback(|_: FocusEvent| Message::Hovered(None));
+        let interactive = ctx.props().interactive && self.current_viewport(ctx).is_some();
+        let onwheel = ctx.link().batch_callback(move |event: WheelEvent| {
+            if !interactive {
+                return None;
+            }
+            event.prevent_default();
+            let (position, size) = pointer_position(&event)?;
+            // Lines and pages are taken to be roughly 16 and 800 pixels.
+            let scale = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => 16.0,
+                WheelEvent::DOM_DELTA_PAGE => 800.0,
+                _ => 1.0,
+            };
+            Some(Message::Wheel {
+                at: coord! { x: position.x / size.x, y: position.y / size.y },
+                delta: event.delta_y() * scale,
+            })
+        });
+        let onpointerdown = ctx.link().batch_callback(move |event: PointerEvent| {
+            if !(interactive || drawing) || event.button() > 0 {
+                return None;
+            }
+            if let Some(element) = event
+                .current_target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+            {
+                let _ = element.set_pointer_capture(event.pointer_id());
+            }
+            let (position, size) = pointer_position(&event)?;
+            if drawing {
+                return Some(Message::SketchDown(position));
+            }
+            Some(Message::PointerDown {
+                id: event.pointer_id(),
+                position,
+                size,
+            })
+        });
+        let onpointermove = ctx.link().batch_callback(move |event: PointerEvent| {
+            let (position, _) = pointer_position(&event)?;
+            if drawing {
+                return Some(Message::SketchMoved(position));
+            }
+            Some(Message::PointerMoved {
+                id: event.pointer_id(),
+                position,
+            })
+        });
+        let onpointerup = ctx.link().batch_callback(move |event: PointerEvent| {
+            if drawing {
+                let (position, _) = pointer_position(&event)?;
+                return Some(Message::SketchUp(position));
+            }
+            Some(Message::PointerUp(event.pointer_id()))
+        });
+        let onpointercancel = ctx.link().callback(move |event: PointerEvent| {
+            if drawing {
+                Message::CancelSketch
+            } else {
+                Message::PointerUp(event.pointer_id())
+            }
+        });
+        let style = [
+            canvas.then_some("position: relative"),
+            (interactive || drawing).then_some("touch-action: none"),
+            drawing.then_some("cursor: crosshair"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("; ");
+        let style = (!style.is_empty()).then_some(style);
+        let sketch = self
+            .sketch
+            .as_ref()
+            .zip(self.current_viewport(ctx))
+            .map(|(sketch, viewport)| {
+                let ruler = sketch.tool == DrawTool::Ruler;
+                // The length of a ruler is shown by its last point.
+                let distance = ruler.then(|| {
+                    let line = sketch.measured();
+                    let end = line.0.last().copied().unwrap_or(Coord::zero());
+                    let height = self.width * viewport.height() / viewport.width();
+                    let at = viewport::position_of(&viewport, end);
+                    let style = format!(
+                        "position: absolute; left: {}px; top: {}px; pointer-events: none",
+                        at.x * self.width + 8.0,
+                        at.y * height + 8.0
+                    );
+                    let length = measure::line_length(&line, &ctx.props().map.crs);
+                    html! {
+                        <div class="ruler-distance" role="status" {style}>
+                            {measure::format_length(length, ctx.props().length_unit)}
+                        </div>
+                    }
+                });
+                html! {
+                    <>
+                    <svg
+                        class="sketch"
+                        viewBox={view_box(&viewport)}
+                        style="position: absolute; left: 0; top: 0; width: 100%; height: 100%; pointer-events: none"
+                    >
+                        <path
+                            d={sketch.path()}
+                            fill={if ruler { "none" } else { "#1f78b4" }}
+                            fill-opacity="0.15"
+                            stroke="#1f78b4"
+                            stroke-width="2"
+                            stroke-dasharray="6 4"
+                            vector-effect="non-scaling-stroke"
+                        />
+                    </svg>
+                    {distance}
+                    </>
+                }
+            });
+        let layer_switcher = ctx
+            .props()
+            .controls
+            .layer_switcher
+            .filter(|_| !ctx.props().layers.is_empty())
+            .map(|corner| {
+                let base_map = (ctx.props().tiles.is_some() || !ctx.props().map.src.is_empty())
+                    .then_some(self.base_map_visible);
+                layers::switcher(
+                    &self.layers(ctx),
+                    base_map,
+                    corner,
+                    ctx.link().callback(Message::ToggleLayer),
+                    ctx.link()
+                        .callback(|(id, opacity)| Message::SetLayerOpacity(id, opacity)),
+                )
+            });
+        let dragging = self.dragged && self.gesture.is_some();
+        let tooltip = self
+            .hovered
+            .as_ref()
+            .filter(|_| ctx.props().tooltips && !dragging)
+            .and_then(|(id, at)| {
+                let entity = self.scene.entity(id)?;
+                let style = format!(
+                    "position: absolute; left: {}px; top: {}px; pointer-events: none",
+                    at.x + 12.0,
+                    at.y + 12.0
+                );
+                let measurements = if ctx.props().tooltip_measurements {
+                    tooltip_measurements(entity, ctx.props().length_unit)
+                } else {
+                    Vec::new()
+                };
+                Some(html! {
+                    <div class="entity-tooltip" role="tooltip" {style}>
+                        <div class="entity-tooltip-label">{entity.label.clone().unwrap_or(id.clone())}</div>
+                        {
+                            measurements.into_iter().map(|(label, value)| html! {
+                                <div class="entity-tooltip-measurement">{label}{": "}{value}</div>
+                            }).collect::<Html>()
+                        }
+                        {
+                            tooltip_rows(entity, &ctx.props().tooltip_properties).into_iter().map(|(label, value)| html! {
+                                <div>{label}{": "}{value}</div>
+                            }).collect::<Html>()
+                        }
+                    </div>
+                })
+            });
+        html! {
+            <>
+            <div style="position: relative">
+                <div
+                    ref={self.container.clone()}
+                    {style}
+                    tabindex={drawing.then_some("0")}
+                    {onclick}
+                    {ondblclick}
+                    {onkeydown}
+                    {onwheel}
+                    {onpointerdown}
+                    {onpointermove}
+                    {onpointerup}
+                    {onpointercancel}
+                    {onpointerover}
+                    {onpointerleave}
+                    {onfocusin}
+                    {onfocusout}
+                >
+                    {self.geometry_html.clone()}
+                    if canvas {
+                        <canvas ref={self.canvas.clone()} />
+                        <canvas ref={self.overlay.clone()} class="entity-overlay" tabindex="0" role="application" aria-label="Map. Use the arrow keys to move between entities." />
+                    }
+                </div>
+                {self.controls_html.clone()}
+                {layer_switcher}
+                {sketch}
+                {tooltip}
+            </div>
+            if interactive {
+                <button type="button" class="reset-view" onclick={ctx.link().callback(|_| Message::ResetView)}>
+                    {"Reset view"}
+                </button>
+            }
+            if ctx.props().export_buttons {
+                <div class="map-export" role="group" aria-label="Save the map">
+                    <button type="button" onclick={ctx.link().callback(|_| Message::Export(ExportFormat::Svg))}>
+                        {"Save as SVG"}
+                    </button>
+                    <button type="button" onclick={ctx.link().callback(|_| Message::Export(ExportFormat::Png))}>
+                        {"Save as PNG"}
+                    </button>
+                    <button type="button" onclick={ctx.link().callback(|_| Message::Export(ExportFormat::GeoJson))}>
+                        {"Save as GeoJSON"}
+                    </button>
+                </div>
+            }
+            </>
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tooltip_rows() {
+        let entity = models::Entity {
+            properties: IArray::from(vec![
+                (IString::from("Depth"), IString::from("30 m")),
+                (IString::from("Score"), IString::from("4")),
+            ]),
+            ..models::Entity::default()
+        };
+        assert_eq!(
+            tooltip_rows(&entity, &[IString::from("Score"), IString::from("Speed")]),
+            vec![(IString::from("Score"), IString::from("4"))]
+        );
+    }
+}
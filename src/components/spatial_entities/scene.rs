@@ -0,0 +1,880 @@
+//! Drawing entities as SVG.
+//!
+//! A [`Scene`] holds the entities in the CRS of the map with their bounds, so that each view
+//! only draws the entities within it, simplified to the detail that shows at its scale.
+//! Entities may be grouped in layers, which are drawn in order and can be hidden or faded
+//! without preparing the scene again. Likewise, entities generated after a time can be left
+//! out, e.g. to replay how they appeared.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use chrono::{DateTime, Utc};
+use geo::{
+    coord, BoundingRect, Coord, EuclideanDistance, Geometry, GeometryCollection, Intersects,
+    MapCoords, Point, Rect, Scale, Simplify, SimplifyVw,
+};
+use geo_svg::{Style, ToSvg};
+use implicit_clone::unsync::IString;
+
+use super::{
+    map_geometry, reproject_rect, symbols, tile_bounds, view_box, EntityStyle, EntityStyler, Map,
+    Symbol, TileLayer, DEFAULT_SYMBOL_SIZE,
+};
+use crate::{crs::Crs, models};
+
+/// The class of the SVG of a map, which its styles are scoped to.
+const MAP_CLASS: &str = "spatial-entities";
+
+/// How hovered, focused, highlighted and selected entities are drawn. CSS takes precedence over
+/// the presentation attributes of entity styles. A `<style>` in inline SVG applies to the whole
+/// page, so every rule is scoped to [`MAP_CLASS`].
+const HIGHLIGHT_STYLE: &str = "
+    svg.spatial-entities g[class^=entity-region] * { vector-effect: non-scaling-stroke; }
+    svg.spatial-entities g[class^=entity-region] { cursor: pointer; outline: none; }
+    svg.spatial-entities g[class^=entity-region]:hover :not(text),
+    svg.spatial-entities g[class^=entity-region]:focus-visible :not(text),
+    svg.spatial-entities g.highlighted :not(text) {
+        stroke: #ff7f00; stroke-width: 3px;
+    }
+    svg.spatial-entities g.selected :not(text) { stroke: #1f78b4; stroke-width: 4px; }
+    svg.spatial-entities g.cluster { cursor: zoom-in; }
+";
+
+/// How much larger than the viewport the area drawn is, so that entities just outside it show
+/// while the map is dragged.
+const CULL_SCALE: f64 = 2.0;
+
+/// The colour of clusters of points.
+pub(super) const CLUSTER_COLOR: &str = "#1f78b4";
+
+/// How many pixels a pointer may be from an entity and still be over it.
+const HIT_SLOP: f64 = 3.0;
+
+/// How lines and polygons are simplified before they are drawn, with tolerances in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Simplification {
+    None,
+    /// Drops vertices that are within the tolerance of the simplified line.
+    DouglasPeucker(f64),
+    /// Drops vertices that make triangles of less than the square of the tolerance with their
+    /// neighbours.
+    Visvalingam(f64),
+}
+
+impl Default for Simplification {
+    /// A tolerance of half a pixel, which is not visible.
+    fn default() -> Self {
+        Simplification::DouglasPeucker(0.5)
+    }
+}
+
+impl Simplification {
+    /// The geometry without the detail that would not show at a scale.
+    pub fn apply(&self, geometry: &Geometry, units_per_pixel: f64) -> Geometry {
+        match *self {
+            Simplification::None => geometry.clone(),
+            Simplification::DouglasPeucker(tolerance) => {
+                simplify(geometry, tolerance * units_per_pixel, false)
+            }
+            Simplification::Visvalingam(tolerance) => {
+                simplify(geometry, (tolerance * units_per_pixel).powi(2), true)
+            }
+        }
+    }
+}
+
+fn simplify(geometry: &Geometry, epsilon: f64, visvalingam: bool) -> Geometry {
+    macro_rules! simplified {
+        ($variant:ident, $geometry:expr) => {
+            Geometry::$variant(if visvalingam {
+                $geometry.simplify_vw(&epsilon)
+            } else {
+                $geometry.simplify(&epsilon)
+            })
+        };
+    }
+    match geometry {
+        Geometry::LineString(line_string) => simplified!(LineString, line_string),
+        Geometry::MultiLineString(line_strings) => simplified!(MultiLineString, line_strings),
+        Geometry::Polygon(polygon) => simplified!(Polygon, polygon),
+        Geometry::MultiPolygon(polygons) => simplified!(MultiPolygon, polygons),
+        Geometry::GeometryCollection(collection) => {
+            Geometry::GeometryCollection(GeometryCollection::new_from(
+                collection
+                    .iter()
+                    .map(|geometry| simplify(geometry, epsilon, visvalingam))
+                    .collect(),
+            ))
+        }
+        geometry => geometry.clone(),
+    }
+}
+
+/// What is drawn besides the entities, and how.
+#[derive(Clone, Copy, Default)]
+pub struct RenderOptions<'a> {
+    pub tiles: Option<&'a TileLayer>,
+    pub entity_style: Option<&'a EntityStyler>,
+    pub selected: &'a [IString],
+    pub simplification: Simplification,
+    /// Points closer than this many pixels are drawn as one cluster, if given.
+    pub cluster_distance: Option<f64>,
+    /// Leaves out the tiles and the map image.
+    pub hide_base_map: bool,
+}
+
+/// How the entities of a layer are drawn.
+#[derive(Clone, PartialEq)]
+pub struct LayerStyle {
+    pub visible: bool,
+    /// Applied to each entity of the layer on top of its own opacity.
+    pub opacity: f32,
+    /// How each entity of the layer is drawn, instead of the `entity_style` of the options.
+    pub entity_style: Option<EntityStyler>,
+}
+
+impl Default for LayerStyle {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+            entity_style: None,
+        }
+    }
+}
+
+/// The points of a geometry as drawn and the rest of it, if any.
+pub(super) type Drawn = (Vec<Point>, Option<Geometry>);
+
+/// The geometries already prepared for drawing, by the position of their entity. These are kept
+/// while the simplification and zoom level stay the same.
+#[derive(Default)]
+struct Simplified {
+    key: Option<(Simplification, i32)>,
+    drawn: HashMap<usize, Rc<Drawn>>,
+}
+
+struct SceneEntity {
+    /// The position of the entity when sorted by layer and then ID, which its group is named
+    /// by.
+    index: usize,
+    layer: usize,
+    id: IString,
+    entity: models::Entity,
+    /// In the CRS of the map.
+    geometry: Geometry,
+    bounds: Rect,
+}
+
+/// Entities prepared for drawing on a map.
+pub struct Scene {
+    map: Map,
+    entities: Vec<SceneEntity>,
+    /// The styles of the layers by their position, which default to [`LayerStyle::default`].
+    layers: Vec<LayerStyle>,
+    /// Entities generated after this time are not drawn.
+    until: Option<DateTime<Utc>>,
+    simplified: RefCell<Simplified>,
+}
+
+impl Scene {
+    pub fn new(entities: &[(IString, models::Entity)], map: &Map) -> Self {
+        Self::layered(&[entities], map)
+    }
+
+    /// A scene of layers of entities, each drawn over those before it.
+    pub fn layered(layers: &[&[(IString, models::Entity)]], map: &Map) -> Self {
+        let mut entities = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(layer, entities)| entities.iter().map(move |entity| (layer, entity)))
+            .collect::<Vec<_>>();
+        entities.sort_by_key(|(layer, (id, _))| (*layer, id.clone()));
+        let entities = entities
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, (layer, (id, entity)))| {
+                let geometry = map_geometry(entity, map)?;
+                let bounds = geometry.bounding_rect()?;
+                Some(SceneEntity {
+                    index,
+                    layer,
+                    id: id.clone(),
+                    entity: entity.clone(),
+                    geometry,
+                    bounds,
+                })
+            })
+            .collect();
+        Self {
+            map: map.clone(),
+            entities,
+            layers: Vec::new(),
+            until: None,
+            simplified: RefCell::default(),
+        }
+    }
+
+    /// Changes how the layers are drawn, by their position.
+    pub fn set_layer_styles(&mut self, layers: Vec<LayerStyle>) {
+        self.layers = layers;
+    }
+
+    fn layer_style(&self, layer: usize) -> Option<&LayerStyle> {
+        self.layers.get(layer)
+    }
+
+    /// Leaves out the entities generated after a time, or none for `None`. Entities without a
+    /// time are always drawn.
+    pub fn set_until(&mut self, until: Option<DateTime<Utc>>) {
+        self.until = until;
+    }
+
+    /// Whether an entity is drawn at all, being in a visible layer and generated by the time
+    /// shown.
+    fn is_shown(&self, entity: &SceneEntity) -> bool {
+        self.layer_style(entity.layer)
+            .is_none_or(|layer| layer.visible)
+            && self
+                .until
+                .zip(entity.entity.generated_at)
+                .is_none_or(|(until, generated_at)| generated_at <= until)
+    }
+
+    /// The geometry of an entity simplified for a scale, flipped for SVG and split into points
+    /// and the rest.
+    ///
+    /// Geometries are simplified for the scale rounded down to a power of two, so that the
+    /// same ones serve while the map is panned or zoomed a little.
+    fn simplified(
+        &self,
+        position: usize,
+        simplification: Simplification,
+        units_per_pixel: f64,
+    ) -> Rc<Drawn> {
+        let level = units_per_pixel.log2().floor() as i32;
+        let mut simplified = self.simplified.borrow_mut();
+        if simplified.key != Some((simplification, level)) {
+            *simplified = Simplified {
+                key: Some((simplification, level)),
+                drawn: HashMap::new(),
+            };
+        }
+        simplified
+            .drawn
+            .entry(position)
+            .or_insert_with(|| {
+                Rc::new(symbols::split_points(
+                    simplification
+                        .apply(&self.entities[position].geometry, 2f64.powi(level))
+                        .map_coords(|Coord { x, y }| Coord { x, y: -y }),
+                ))
+            })
+            .clone()
+    }
+
+    pub fn entity(&self, id: &str) -> Option<&models::Entity> {
+        self.entities
+            .iter()
+            .find(|entity| entity.id.as_str() == id)
+            .map(|entity| &entity.entity)
+    }
+
+    /// The entities drawn within a viewport, in the order they are drawn.
+    pub fn shown<'a>(
+        &'a self,
+        viewport: &'a Rect,
+    ) -> impl Iterator<Item = (&'a IString, &'a models::Entity)> {
+        self.entities
+            .iter()
+            .filter(|entity| self.is_shown(entity) && entity.bounds.intersects(viewport))
+            .map(|entity| (&entity.id, &entity.entity))
+    }
+
+    /// The bounds of an entity in the CRS of the map.
+    pub fn bounds(&self, id: &str) -> Option<Rect> {
+        self.entities
+            .iter()
+            .find(|entity| entity.id.as_str() == id)
+            .map(|entity| entity.bounds)
+    }
+
+    /// The map as an SVG document showing a viewport at a width in pixels.
+    pub fn to_svg(&self, options: &RenderOptions, width: f64, viewport: &Rect) -> String {
+        let layout = self.layout(options, width, viewport);
+        let content = layout
+            .entities
+            .iter()
+            .map(|placed| entity_svg(placed, options.selected, layout.units_per_pixel))
+            .chain(
+                layout
+                    .clusters
+                    .iter()
+                    .map(|cluster| cluster_svg(cluster, layout.units_per_pixel)),
+            )
+            .collect::<String>();
+        self.svg(options, width, viewport, &content)
+    }
+
+    /// The map as an SVG document with only the tiles and base image, for drawing entities over.
+    pub(super) fn background_svg(
+        &self,
+        options: &RenderOptions,
+        width: f64,
+        viewport: &Rect,
+    ) -> String {
+        self.svg(options, width, viewport, "")
+    }
+
+    fn svg(&self, options: &RenderOptions, width: f64, viewport: &Rect, content: &str) -> String {
+        let view_box = view_box(viewport);
+        let tiles = options
+            .tiles
+            .filter(|_| !options.hide_base_map)
+            .map(|layer| tiles_svg(layer, &self.map, viewport, width))
+            .unwrap_or_default();
+        let image = if self.map.src.is_empty() || options.hide_base_map {
+            String::new()
+        } else {
+            let map_extent = self
+                .map
+                .extent
+                .map_coords(|Coord { x, y }| Coord { x, y: -y });
+            let map_x = map_extent.min().x;
+            let map_y = map_extent.min().y;
+            let map_width = map_extent.width();
+            let map_height = map_extent.height();
+            let map_src = escape_attribute(&self.map.src);
+            format!("<image x=\"{map_x}\" y=\"{map_y}\" width=\"{map_width}\" height=\"{map_height}\" href=\"{map_src}\" />")
+        };
+        format!(
+            "
+                <svg class=\"{MAP_CLASS}\" viewBox=\"{view_box}\">
+                    <style>{HIGHLIGHT_STYLE}</style>
+                    {tiles}
+                    {image}
+                    {content}
+                </svg>
+            "
+        )
+    }
+
+    /// What a view shows: the entities near it, simplified for its scale, with nearby points
+    /// clustered if asked.
+    pub(super) fn layout(&self, options: &RenderOptions, width: f64, viewport: &Rect) -> Layout {
+        let units_per_pixel = viewport.width() / width;
+        let area = viewport.scale(CULL_SCALE);
+        let visible = self
+            .entities
+            .iter()
+            .enumerate()
+            .filter(|(_, entity)| self.is_shown(entity) && entity.bounds.intersects(&area));
+        let (points, mut drawn): (Vec<_>, Vec<_>) = visible.partition(|(_, entity)| {
+            options.cluster_distance.is_some() && matches!(entity.geometry, Geometry::Point(_))
+        });
+
+        let mut clusters = Vec::new();
+        if let Some(distance) = options.cluster_distance {
+            for members in cluster(&points, distance * units_per_pixel) {
+                if let [entity] = members.as_slice() {
+                    drawn.push(*entity);
+                } else {
+                    clusters.push(Cluster::new(&members));
+                }
+            }
+            drawn.sort_by_key(|(position, _)| *position);
+        }
+
+        let entities = drawn
+            .into_iter()
+            .map(|(position, entity)| {
+                let layer = self.layer_style(entity.layer);
+                let mut style = layer
+                    .and_then(|layer| layer.entity_style.as_ref())
+                    .or(options.entity_style)
+                    .map(|styler| styler.style(&entity.id, &entity.entity))
+                    .unwrap_or_default();
+                if let Some(opacity) = layer.map(|layer| layer.opacity).filter(|o| *o < 1.0) {
+                    style.opacity = Some(style.opacity.unwrap_or(1.0) * opacity);
+                }
+                Placed {
+                    index: entity.index,
+                    id: entity.id.clone(),
+                    entity: entity.entity.clone(),
+                    drawn: self.simplified(position, options.simplification, units_per_pixel),
+                    style,
+                }
+            })
+            .collect();
+        Layout {
+            viewport: *viewport,
+            units_per_pixel,
+            entities,
+            clusters,
+        }
+    }
+}
+
+/// An entity as drawn in a view.
+pub(super) struct Placed {
+    /// The position of the entity when sorted by layer and then ID, which its group is named by.
+    pub index: usize,
+    pub id: IString,
+    pub entity: models::Entity,
+    pub drawn: Rc<Drawn>,
+    pub style: EntityStyle,
+}
+
+impl Placed {
+    /// The label shown for the entity, if it is labelled.
+    pub fn label(&self) -> Option<&str> {
+        self.style
+            .show_label
+            .unwrap_or_default()
+            .then(|| self.entity.label.as_deref().unwrap_or(&self.id))
+    }
+
+    pub fn symbol(&self) -> Symbol {
+        self.style.symbol.clone().unwrap_or_default()
+    }
+
+    pub fn symbol_size(&self) -> f32 {
+        self.style.symbol_size.unwrap_or(DEFAULT_SYMBOL_SIZE)
+    }
+}
+
+/// A cluster of point entities as drawn in a view.
+pub(super) struct Cluster {
+    /// The centre of the points in SVG coordinates.
+    pub center: Coord,
+    /// The bounds of the points in the CRS of the map.
+    pub bounds: Rect,
+    pub count: usize,
+    /// The radius of its circle in pixels.
+    pub radius: f64,
+}
+
+impl Cluster {
+    fn new(members: &[(usize, &SceneEntity)]) -> Self {
+        let count = members.len();
+        let bounds = members
+            .iter()
+            .map(|(_, entity)| entity.bounds)
+            .reduce(|a, b| {
+                Rect::new(
+                    coord! { x: a.min().x.min(b.min().x), y: a.min().y.min(b.min().y) },
+                    coord! { x: a.max().x.max(b.max().x), y: a.max().y.max(b.max().y) },
+                )
+            })
+            .unwrap_or(Rect::new(Coord::zero(), Coord::zero()));
+        let center = members
+            .iter()
+            .map(|(_, entity)| entity.bounds.center())
+            .fold(Coord::zero(), |sum, point| sum + point)
+            / count as f64;
+        Self {
+            center: coord! { x: center.x, y: -center.y },
+            bounds,
+            count,
+            radius: (8.0 + 3.0 * (count as f64).log2()).min(24.0),
+        }
+    }
+}
+
+/// What is under a position on the map.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Hit {
+    Entity(IString),
+    /// A cluster of points with the given bounds.
+    Cluster(Rect),
+}
+
+/// What a view of a scene draws, in SVG coordinates, which are those of the map with y
+/// increasing southwards.
+pub(super) struct Layout {
+    pub viewport: Rect,
+    pub units_per_pixel: f64,
+    /// The entities in the order they are drawn.
+    pub entities: Vec<Placed>,
+    /// The clusters, drawn over the entities.
+    pub clusters: Vec<Cluster>,
+}
+
+impl Layout {
+    /// The position in pixels of a point in SVG coordinates.
+    pub fn to_pixels(&self, point: Coord) -> Coord {
+        coord! {
+            x: (point.x - self.viewport.min().x) / self.units_per_pixel,
+            y: (point.y + self.viewport.max().y) / self.units_per_pixel,
+        }
+    }
+
+    /// The point in SVG coordinates at a position in pixels.
+    pub fn point_at(&self, position: Coord) -> Coord {
+        coord! {
+            x: self.viewport.min().x + position.x * self.units_per_pixel,
+            y: position.y * self.units_per_pixel - self.viewport.max().y,
+        }
+    }
+
+    /// The cluster or entity drawn topmost at a position in pixels, allowing for a few pixels
+    /// of error.
+    pub fn hit(&self, position: Coord) -> Option<Hit> {
+        let within = |a: Coord, b: Coord, distance: f64| (a - b).x.hypot((a - b).y) <= distance;
+        if let Some(cluster) = self.clusters.iter().rev().find(|cluster| {
+            within(
+                self.to_pixels(cluster.center),
+                position,
+                cluster.radius + HIT_SLOP,
+            )
+        }) {
+            return Some(Hit::Cluster(cluster.bounds));
+        }
+        let point = Point::from(self.point_at(position));
+        self.entities
+            .iter()
+            .rev()
+            .find(|placed| {
+                let (points, geometry) = placed.drawn.as_ref();
+                let size = placed.symbol_size() as f64;
+                let offset = placed.symbol().center(size as f32);
+                let on_symbol = points.iter().any(|symbol_point| {
+                    let center = self.to_pixels(symbol_point.0) + offset;
+                    within(center, position, size / 2.0 + HIT_SLOP)
+                });
+                let on_geometry = geometry.as_ref().is_some_and(|geometry| {
+                    let stroke = placed.style.stroke_width.unwrap_or(1.0) as f64;
+                    geometry.euclidean_distance(&point) / self.units_per_pixel
+                        <= stroke / 2.0 + HIT_SLOP
+                });
+                on_symbol || on_geometry
+            })
+            .map(|placed| Hit::Entity(placed.id.clone()))
+    }
+}
+
+/// An entity with its geometry as drawn.
+fn entity_svg(placed: &Placed, selected: &[IString], units_per_pixel: f64) -> String {
+    let Placed {
+        index,
+        id,
+        entity,
+        drawn,
+        style,
+    } = placed;
+    let (points, geometry) = drawn.as_ref();
+
+    let geometry_svg_str = geometry
+        .iter()
+        .flat_map(|geometry| geometry.to_svg().items)
+        .map(|item| item.to_svg_str(&Style::default()))
+        .collect::<String>();
+    let points_svg_str = symbols::points_svg(
+        points,
+        &placed.symbol(),
+        placed.symbol_size(),
+        placed.label(),
+        units_per_pixel,
+    );
+
+    let is_selected = selected.contains(id);
+    format!(
+        "
+            <g id=\"{}\" class=\"entity-region-{}{}\" tabindex=\"0\" role=\"button\" aria-label=\"{}\" aria-pressed=\"{}\"{}>
+                {}{}
+            </g>
+        ",
+        escape_attribute(id),
+        index,
+        if is_selected { " selected" } else { "" },
+        escape_attribute(entity.label.as_deref().unwrap_or(id)),
+        is_selected,
+        style.to_attributes(),
+        geometry_svg_str,
+        points_svg_str,
+    )
+}
+
+/// Groups point entities by the cells of a grid, keeping the order in which cells are first
+/// seen.
+fn cluster<'a>(
+    entities: &[(usize, &'a SceneEntity)],
+    cell: f64,
+) -> Vec<Vec<(usize, &'a SceneEntity)>> {
+    let mut cells = HashMap::new();
+    let mut clusters: Vec<Vec<_>> = Vec::new();
+    for &(position, entity) in entities {
+        let Geometry::Point(point) = entity.geometry else {
+            continue;
+        };
+        let key = (
+            (point.x() / cell).floor() as i64,
+            (point.y() / cell).floor() as i64,
+        );
+        let i = *cells.entry(key).or_insert_with(|| {
+            clusters.push(Vec::new());
+            clusters.len() - 1
+        });
+        clusters[i].push((position, entity));
+    }
+    clusters
+}
+
+/// A circle with the number of entities in a cluster, which zooms to them when clicked.
+fn cluster_svg(cluster: &Cluster, units_per_pixel: f64) -> String {
+    let Cluster {
+        center,
+        bounds,
+        count,
+        radius,
+    } = cluster;
+    format!(
+        "<g class=\"cluster\" data-bounds=\"{} {} {} {}\" transform=\"translate({} {}) scale({units_per_pixel})\" tabindex=\"0\" role=\"button\" aria-label=\"{count} entities\"><circle r=\"{radius}\" fill=\"{CLUSTER_COLOR}\" fill-opacity=\"0.8\" stroke=\"white\" stroke-width=\"2\" vector-effect=\"non-scaling-stroke\" /><text text-anchor=\"middle\" dominant-baseline=\"central\" font-size=\"11\" fill=\"white\">{count}</text></g>",
+        bounds.min().x,
+        bounds.min().y,
+        bounds.max().x,
+        bounds.max().y,
+        center.x,
+        center.y,
+    )
+}
+
+/// Escapes a value for an SVG attribute in double quotes.
+pub(super) fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+/// Escapes a value for the text of an SVG element.
+pub(super) fn escape_text(value: &str) -> String {
+    escape_attribute(value).replace('>', "&gt;")
+}
+
+/// The images of the tiles covering a viewport in the CRS of the map.
+fn tiles_svg(layer: &TileLayer, map: &Map, viewport: &Rect, width: f64) -> String {
+    let lon_lat_viewport = reproject_rect(viewport, &map.crs, &Crs::Crs84);
+    let zoom = layer.zoom(&lon_lat_viewport, width);
+    layer
+        .visible_tiles(&lon_lat_viewport, zoom)
+        .into_iter()
+        .map(|tile| {
+            let bounds = reproject_rect(&tile_bounds(tile), &Crs::Crs84, &map.crs);
+            format!(
+                "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" href=\"{}\" />",
+                bounds.min().x,
+                -bounds.max().y,
+                bounds.width(),
+                bounds.height(),
+                escape_attribute(&layer.url(tile)),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use geo::{line_string, point};
+
+    use super::*;
+
+    fn point_entity(label: &str, point: Point) -> models::Entity {
+        models::Entity {
+            label: Some(IString::from(label.to_string())),
+            geometry: Some(Rc::new(Geometry::Point(point))),
+            ..models::Entity::default()
+        }
+    }
+
+    fn map() -> Map {
+        Map {
+            src: IString::default(),
+            extent: Rc::new(Rect::new(
+                coord! { x: 145.0, y: -17.0 },
+                coord! { x: 146.0, y: -16.0 },
+            )),
+            crs: Crs::Crs84,
+        }
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let entities = [
+            (
+                IString::from("http://example.com/a"),
+                point_entity("Route A", point! { x: 145.8, y: -16.9 }),
+            ),
+            (
+                IString::from("http://example.com/b"),
+                point_entity("Route \"B\"", point! { x: 145.8001, y: -16.9 }),
+            ),
+            (
+                IString::from("http://example.com/c"),
+                point_entity("Elsewhere", point! { x: 10.0, y: 10.0 }),
+            ),
+        ];
+        let map = map();
+        let scene = Scene::new(&entities, &map);
+        let selected = [IString::from("http://example.com/b")];
+        let options = RenderOptions {
+            selected: &selected,
+            ..RenderOptions::default()
+        };
+
+        let svg = scene.to_svg(&options, 800.0, &map.extent);
+        assert_eq!(escape_text("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+        assert!(svg.contains(&format!("<svg class=\"{MAP_CLASS}\"")));
+        // The styles of the map leave the other SVG of the page alone.
+        assert!(HIGHLIGHT_STYLE
+            .split('}')
+            .filter_map(|rule| rule.split_once('{'))
+            .flat_map(|(selectors, _)| selectors.split(','))
+            .all(|selector| selector.trim().starts_with(&format!("svg.{MAP_CLASS} "))));
+        assert!(svg.contains("<g id=\"http://example.com/a\" class=\"entity-region-0\" tabindex=\"0\" role=\"button\" aria-label=\"Route A\" aria-pressed=\"false\">"));
+        assert!(svg.contains("<g id=\"http://example.com/b\" class=\"entity-region-1 selected\" tabindex=\"0\" role=\"button\" aria-label=\"Route &quot;B&quot;\" aria-pressed=\"true\">"));
+        assert!(
+            !svg.contains("http://example.com/c"),
+            "entities far outside the viewport should not be drawn"
+        );
+
+        let clustered = scene.to_svg(
+            &RenderOptions {
+                cluster_distance: Some(20.0),
+                ..options
+            },
+            800.0,
+            &map.extent,
+        );
+        assert!(!clustered.contains("<g id="));
+        assert!(
+            clustered.contains("<g class=\"cluster\" data-bounds=\"145.8 -16.9 145.8001 -16.9\"")
+        );
+        assert!(clustered.contains(">2</text>"));
+    }
+
+    #[test]
+    fn test_layers() {
+        let observations = [(
+            IString::from("http://example.com/a"),
+            models::Entity {
+                generated_at: "2029-01-02T09:30:00Z".parse().ok(),
+                ..point_entity("Sighting", point! { x: 145.5, y: -16.5 })
+            },
+        )];
+        let zones = [(
+            IString::from("http://example.com/z"),
+            point_entity("Zone", point! { x: 145.6, y: -16.5 }),
+        )];
+        let map = map();
+        let mut scene = Scene::layered(&[&zones, &observations], &map);
+        let options = RenderOptions::default();
+
+        // Layers are drawn in order whatever the IDs of their entities.
+        let svg = scene.to_svg(&options, 800.0, &map.extent);
+        assert!(svg.find("http://example.com/z") < svg.find("http://example.com/a"));
+        assert!(svg.contains("class=\"entity-region-1\""));
+
+        scene.set_layer_styles(vec![
+            LayerStyle {
+                visible: false,
+                ..LayerStyle::default()
+            },
+            LayerStyle {
+                opacity: 0.5,
+                ..LayerStyle::default()
+            },
+        ]);
+        let svg = scene.to_svg(&options, 800.0, &map.extent);
+        assert!(!svg.contains("http://example.com/z"));
+        assert!(svg.contains("aria-pressed=\"false\" opacity=\"0.5\">"));
+
+        // Entities generated later than the time shown are left out, and those without a time
+        // kept.
+        scene.set_layer_styles(Vec::new());
+        scene.set_until("2029-01-01T00:00:00Z".parse().ok());
+        assert_eq!(
+            scene
+                .shown(&map.extent)
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            ["http://example.com/z"]
+        );
+    }
+
+    #[test]
+    fn test_hit() {
+        let track = models::Entity {
+            geometry: Some(Rc::new(Geometry::LineString(line_string![
+                (x: 145.1, y: -16.8),
+                (x: 145.9, y: -16.8),
+            ]))),
+            ..models::Entity::default()
+        };
+        let entities = [
+            (
+                IString::from("http://example.com/a"),
+                point_entity("A", point! { x: 145.5, y: -16.5 }),
+            ),
+            (IString::from("http://example.com/track"), track),
+            (
+                IString::from("http://example.com/b"),
+                point_entity("B", point! { x: 145.25, y: -16.25 }),
+            ),
+            (
+                IString::from("http://example.com/c"),
+                point_entity("C", point! { x: 145.2501, y: -16.25 }),
+            ),
+        ];
+        let map = map();
+        let scene = Scene::new(&entities, &map);
+        let options = RenderOptions {
+            cluster_distance: Some(20.0),
+            ..RenderOptions::default()
+        };
+        let layout = scene.layout(&options, 100.0, &map.extent);
+
+        assert_eq!(
+            layout.to_pixels(coord! { x: 145.5, y: 16.5 }),
+            coord! { x: 50.0, y: 50.0 }
+        );
+        assert_eq!(
+            layout.point_at(coord! { x: 50.0, y: 50.0 }),
+            coord! { x: 145.5, y: 16.5 }
+        );
+        let hit = |x, y| layout.hit(coord! { x: x, y: y });
+        let a = Some(Hit::Entity(IString::from("http://example.com/a")));
+        assert_eq!(hit(50.0, 50.0), a);
+        assert_eq!(hit(56.0, 50.0), a, "points are hit within their symbol");
+        assert_eq!(
+            hit(50.0, 83.0),
+            Some(Hit::Entity(IString::from("http://example.com/track")))
+        );
+        assert_eq!(hit(50.0, 70.0), None);
+        assert_eq!(
+            hit(25.0, 25.0),
+            Some(Hit::Cluster(Rect::new(
+                coord! { x: 145.25, y: -16.25 },
+                coord! { x: 145.2501, y: -16.25 },
+            )))
+        );
+    }
+
+    #[test]
+    fn test_simplification() {
+        let line = Geometry::LineString(line_string![
+            (x: 0.0, y: 0.0),
+            (x: 1.0, y: 0.001),
+            (x: 2.0, y: 0.0),
+        ]);
+        let simplified = Simplification::default().apply(&line, 0.01);
+        assert_eq!(
+            simplified,
+            Geometry::LineString(line_string![(x: 0.0, y: 0.0), (x: 2.0, y: 0.0)])
+        );
+        assert_eq!(Simplification::default().apply(&line, 0.001), line);
+        assert_eq!(Simplification::None.apply(&line, 0.01), line);
+    }
+}
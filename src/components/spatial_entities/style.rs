@@ -0,0 +1,692 @@
+//! Styling of entities on the map, either by a callback or by a [`StyleSpec`] of rules and a
+//! thematic colour ramp.
+//!
+//! Styles are written as presentation attributes of each entity's group, so that they apply to
+//! all of its parts. Stroke widths are in pixels whatever the zoom.
+
+use std::{fmt, rc::Rc};
+
+use geo::Geometry;
+use implicit_clone::{
+    unsync::{IArray, IString},
+    ImplicitClone,
+};
+use yew::Callback;
+
+use super::{scene::escape_attribute, Symbol};
+use crate::models;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl ImplicitClone for Rgb {}
+
+impl Rgb {
+    /// Mixes two colours, with `t` from 0 for `self` to 1 for `other`.
+    pub fn lerp(self, other: Rgb, t: f64) -> Rgb {
+        let mix =
+            |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t.clamp(0.0, 1.0)).round() as u8;
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// Colours of the default categorical palette, from ColorBrewer's Set1.
+pub const SET1: [&str; 9] = [
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#ffff33", "#a65628", "#f781bf",
+    "#999999",
+];
+
+/// How an entity is drawn. Unset fields are left to the SVG defaults or to a less specific
+/// style.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityStyle {
+    /// Any CSS colour.
+    pub fill: Option<IString>,
+    pub fill_opacity: Option<f32>,
+    pub stroke: Option<IString>,
+    pub stroke_opacity: Option<f32>,
+    /// In pixels.
+    pub stroke_width: Option<f32>,
+    /// An SVG dash array in pixels, e.g. `"4 2"`.
+    pub stroke_dasharray: Option<IString>,
+    pub opacity: Option<f32>,
+    /// How points are drawn, by default as circles.
+    pub symbol: Option<Symbol>,
+    /// The width of point symbols in pixels.
+    pub symbol_size: Option<f32>,
+    /// Whether to label the points of the entity.
+    pub show_label: Option<bool>,
+}
+
+impl ImplicitClone for EntityStyle {}
+
+impl EntityStyle {
+    /// This style with the fields set in `other` replacing its own.
+    pub fn merge(&self, other: &EntityStyle) -> EntityStyle {
+        EntityStyle {
+            fill: other.fill.clone().or_else(|| self.fill.clone()),
+            fill_opacity: other.fill_opacity.or(self.fill_opacity),
+            stroke: other.stroke.clone().or_else(|| self.stroke.clone()),
+            stroke_opacity: other.stroke_opacity.or(self.stroke_opacity),
+            stroke_width: other.stroke_width.or(self.stroke_width),
+            stroke_dasharray: other
+                .stroke_dasharray
+                .clone()
+                .or_else(|| self.stroke_dasharray.clone()),
+            opacity: other.opacity.or(self.opacity),
+            symbol: other.symbol.clone().or_else(|| self.symbol.clone()),
+            symbol_size: other.symbol_size.or(self.symbol_size),
+            show_label: other.show_label.or(self.show_label),
+        }
+    }
+
+    /// The style as SVG presentation attributes, each preceded by a space.
+    pub fn to_attributes(&self) -> String {
+        let strings = [
+            ("fill", &self.fill),
+            ("stroke", &self.stroke),
+            ("stroke-dasharray", &self.stroke_dasharray),
+        ];
+        let numbers = [
+            ("fill-opacity", self.fill_opacity),
+            ("stroke-opacity", self.stroke_opacity),
+            ("stroke-width", self.stroke_width),
+            ("opacity", self.opacity),
+        ];
+        strings
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some(format!(" {name}=\"{}\"", escape_attribute(value.as_ref()?)))
+            })
+            .chain(
+                numbers
+                    .into_iter()
+                    .filter_map(|(name, value)| Some(format!(" {name}=\"{}\"", value?))),
+            )
+            .collect()
+    }
+}
+
+/// Which entities a [`StyleRule`] applies to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    All,
+    Id(IString),
+    Label(IString),
+    /// The entity has a property with this label, whatever its value.
+    HasProperty(IString),
+    /// The entity has a property with this label and value.
+    Property(IString, IString),
+}
+
+impl Condition {
+    /// How the condition is described in a legend.
+    fn legend_label(&self, matching: &[&(IString, models::Entity)]) -> IString {
+        match self {
+            Condition::All => IString::from("All"),
+            // The label of the entity reads better than its ID.
+            Condition::Id(id) => matching
+                .first()
+                .and_then(|(_, entity)| entity.label.clone())
+                .unwrap_or(id.clone()),
+            Condition::Label(label) | Condition::HasProperty(label) => label.clone(),
+            Condition::Property(label, value) => format!("{label}: {value}").into(),
+        }
+    }
+
+    pub fn matches(&self, id: &str, entity: &models::Entity) -> bool {
+        match self {
+            Condition::All => true,
+            Condition::Id(expected) => id == expected.as_str(),
+            Condition::Label(expected) => entity.label.as_ref() == Some(expected),
+            Condition::HasProperty(label) => property(entity, label).is_some(),
+            Condition::Property(label, expected) => {
+                property(entity, label).as_ref() == Some(expected)
+            }
+        }
+    }
+}
+
+fn property(entity: &models::Entity, label: &str) -> Option<IString> {
+    entity
+        .properties
+        .iter()
+        .find(|(property, _)| property.as_str() == label)
+        .map(|(_, value)| value)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleRule {
+    pub condition: Condition,
+    pub style: EntityStyle,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorRamp {
+    /// Gives each distinct value its own colour: the one listed for it, if any, or else one
+    /// picked from the palette by a hash of the value.
+    Categorical {
+        categories: IArray<(IString, IString)>,
+        palette: IArray<IString>,
+    },
+    /// Interpolates between colours at increasing numeric stops. With `classes`, values are
+    /// first grouped into that many equal intervals, each given the colour of its middle.
+    Graduated {
+        stops: IArray<(f64, Rgb)>,
+        classes: Option<usize>,
+    },
+}
+
+impl ColorRamp {
+    /// A categorical ramp with no fixed categories and the default palette.
+    pub fn categorical() -> Self {
+        ColorRamp::Categorical {
+            categories: IArray::default(),
+            palette: SET1.iter().map(|color| IString::from(*color)).collect(),
+        }
+    }
+
+    /// Red for low values through yellow to green for high ones, e.g. for assessment scores.
+    pub fn red_to_green(min: f64, max: f64) -> Self {
+        ColorRamp::Graduated {
+            stops: IArray::from(vec![
+                (min, Rgb(0xd7, 0x30, 0x27)),
+                ((min + max) / 2.0, Rgb(0xfe, 0xe0, 0x8b)),
+                (max, Rgb(0x1a, 0x98, 0x50)),
+            ]),
+            classes: None,
+        }
+    }
+
+    /// The colour of a value, if it has one.
+    pub fn color(&self, value: &str) -> Option<IString> {
+        match self {
+            ColorRamp::Categorical {
+                categories,
+                palette,
+            } => categories
+                .iter()
+                .find(|(category, _)| category.as_str() == value)
+                .map(|(_, color)| color)
+                .or_else(|| {
+                    // FNV-1a, which unlike the std hasher is stable between builds.
+                    let hash = value.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+                        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+                    });
+                    palette.get((hash % palette.len().max(1) as u64) as usize)
+                }),
+            ColorRamp::Graduated { stops, classes } => {
+                let value = value.trim().parse::<f64>().ok()?;
+                Some(graduated(stops, *classes, value)?.to_string().into())
+            }
+        }
+    }
+}
+
+fn graduated(stops: &[(f64, Rgb)], classes: Option<usize>, value: f64) -> Option<Rgb> {
+    let (first, last) = (stops.first()?, stops.last()?);
+    let (min, max) = (first.0, last.0);
+    let value = match classes.filter(|classes| *classes > 0 && max > min) {
+        Some(classes) => {
+            let n = classes as f64;
+            let class = ((value - min) / (max - min) * n)
+                .floor()
+                .clamp(0.0, n - 1.0);
+            min + (class + 0.5) / n * (max - min)
+        }
+        None => value,
+    };
+    if value <= min {
+        return Some(first.1);
+    }
+    stops
+        .windows(2)
+        .find(|pair| value <= pair[1].0)
+        .map(|pair| {
+            let ((a, from), (b, to)) = (pair[0], pair[1]);
+            from.lerp(to, if b > a { (value - a) / (b - a) } else { 1.0 })
+        })
+        .or(Some(last.1))
+}
+
+/// Which paint a thematic colour is applied to.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Paint {
+    #[default]
+    Fill,
+    Stroke,
+    Both,
+}
+
+/// Colours entities by the value of one of their properties.
+///
+/// Values computed by the application, such as assessment scores, can be added to the entities'
+/// properties before they are passed to the map.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thematic {
+    /// The label of the property.
+    pub property: IString,
+    pub ramp: ColorRamp,
+    pub paint: Paint,
+}
+
+impl Thematic {
+    /// A style that paints a colour as the thematic colour.
+    fn paint(&self, color: IString) -> EntityStyle {
+        let (fill, stroke) = match self.paint {
+            Paint::Fill => (Some(color), None),
+            Paint::Stroke => (None, Some(color)),
+            Paint::Both => (Some(color.clone()), Some(color)),
+        };
+        EntityStyle {
+            fill,
+            stroke,
+            ..EntityStyle::default()
+        }
+    }
+}
+
+/// A base style, refined by each matching rule in order and then by a thematic colour.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleSpec {
+    pub base: EntityStyle,
+    pub rules: Vec<StyleRule>,
+    pub thematic: Option<Thematic>,
+}
+
+impl StyleSpec {
+    pub fn style(&self, id: &str, entity: &models::Entity) -> EntityStyle {
+        let style = self
+            .rules
+            .iter()
+            .filter(|rule| rule.condition.matches(id, entity))
+            .fold(self.base.clone(), |style, rule| style.merge(&rule.style));
+        let Some(thematic) = &self.thematic else {
+            return style;
+        };
+        let Some(color) =
+            property(entity, &thematic.property).and_then(|value| thematic.ramp.color(&value))
+        else {
+            return style;
+        };
+        style.merge(&thematic.paint(color))
+    }
+}
+
+/// A style shown in the legend, with what it means.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegendEntry {
+    pub label: IString,
+    pub style: EntityStyle,
+    /// Whether the style is shown as a point symbol rather than a swatch of fill and outline.
+    pub point: bool,
+}
+
+impl ImplicitClone for LegendEntry {}
+
+impl LegendEntry {
+    fn new(label: IString, style: EntityStyle, matching: &[&(IString, models::Entity)]) -> Self {
+        let point = matching.iter().all(|(_, entity)| {
+            matches!(
+                entity.geometry.as_deref(),
+                Some(Geometry::Point(_) | Geometry::MultiPoint(_))
+            )
+        });
+        Self {
+            label,
+            style,
+            point,
+        }
+    }
+}
+
+/// A number shown in a legend, to at most two decimal places.
+fn legend_number(value: f64) -> String {
+    let text = format!("{value:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0" } else { text }.to_string()
+}
+
+impl StyleSpec {
+    /// The base style with the rules for all entities.
+    fn shared_style(&self) -> EntityStyle {
+        self.rules
+            .iter()
+            .filter(|rule| rule.condition == Condition::All)
+            .fold(self.base.clone(), |style, rule| style.merge(&rule.style))
+    }
+
+    /// One entry for all of some entities in the style they share, e.g. for a layer, or `None`
+    /// if there are none.
+    pub fn summary(
+        &self,
+        label: IString,
+        entities: &[(IString, models::Entity)],
+    ) -> Option<LegendEntry> {
+        let matching = entities.iter().collect::<Vec<_>>();
+        (!matching.is_empty()).then(|| LegendEntry::new(label, self.shared_style(), &matching))
+    }
+
+    /// The rules and thematic colours that apply to some of the entities, for a legend.
+    ///
+    /// Rules for all entities are folded into the style of every entry rather than shown.
+    pub fn legend(&self, entities: &[(IString, models::Entity)]) -> Vec<LegendEntry> {
+        let base = self.shared_style();
+        let mut entries = self
+            .rules
+            .iter()
+            .filter(|rule| rule.condition != Condition::All)
+            .filter_map(|rule| {
+                let matching = entities
+                    .iter()
+                    .filter(|(id, entity)| rule.condition.matches(id, entity))
+                    .collect::<Vec<_>>();
+                (!matching.is_empty()).then(|| {
+                    LegendEntry::new(
+                        rule.condition.legend_label(&matching),
+                        base.merge(&rule.style),
+                        &matching,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let Some(thematic) = &self.thematic else {
+            return entries;
+        };
+        let values = entities
+            .iter()
+            .filter_map(|item| Some((property(&item.1, &thematic.property)?, item)))
+            .collect::<Vec<_>>();
+        // Each group of entities is shown with the colour of a representative value.
+        let mut groups = Vec::new();
+        match &thematic.ramp {
+            ColorRamp::Categorical { categories, .. } => {
+                let mut distinct = values.iter().map(|(value, _)| value).collect::<Vec<_>>();
+                // Listed categories come first, in their order, then the rest alphabetically.
+                distinct.sort_by_key(|value| {
+                    (
+                        categories
+                            .iter()
+                            .position(|(category, _)| &category == *value)
+                            .unwrap_or(usize::MAX),
+                        value.to_string(),
+                    )
+                });
+                distinct.dedup();
+                for value in distinct {
+                    let matching = values
+                        .iter()
+                        .filter(|(other, _)| other == value)
+                        .map(|(_, item)| *item)
+                        .collect();
+                    groups.push((value.clone(), value.clone(), matching));
+                }
+            }
+            ColorRamp::Graduated { stops, classes } => {
+                let numbers = values
+                    .iter()
+                    .filter_map(|(value, item)| Some((value.trim().parse::<f64>().ok()?, *item)))
+                    .collect::<Vec<_>>();
+                let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+                    return entries;
+                };
+                let (min, max) = (first.0, last.0);
+                match classes.filter(|classes| *classes > 0 && max > min) {
+                    Some(classes) => {
+                        let width = (max - min) / classes as f64;
+                        for class in 0..classes {
+                            let from = min + class as f64 * width;
+                            let to = from + width;
+                            let matching = numbers
+                                .iter()
+                                .filter(|(number, _)| {
+                                    let index = ((number - min) / width).floor();
+                                    index.clamp(0.0, classes as f64 - 1.0) as usize == class
+                                })
+                                .map(|(_, item)| *item)
+                                .collect();
+                            groups.push((
+                                format!("{} – {}", legend_number(from), legend_number(to)).into(),
+                                ((from + to) / 2.0).to_string().into(),
+                                matching,
+                            ));
+                        }
+                    }
+                    // A continuous ramp is shown by its stops, if any entity is coloured by it.
+                    None if !numbers.is_empty() => {
+                        let matching = numbers.iter().map(|(_, item)| *item).collect::<Vec<_>>();
+                        for (stop, _) in stops.iter() {
+                            let label = IString::from(legend_number(stop));
+                            groups.push((label.clone(), stop.to_string().into(), matching.clone()));
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+        entries.extend(groups.into_iter().filter_map(|(label, value, matching)| {
+            let color = thematic.ramp.color(&value)?;
+            (!matching.is_empty()).then(|| {
+                LegendEntry::new(
+                    format!("{}: {label}", thematic.property).into(),
+                    base.merge(&thematic.paint(color)),
+                    &matching,
+                )
+            })
+        }));
+        entries
+    }
+}
+
+/// Decides how each entity is drawn.
+#[derive(Clone, PartialEq)]
+pub enum EntityStyler {
+    Spec(Rc<StyleSpec>),
+    /// Called with the ID and entity for each entity drawn.
+    Callback(Callback<(IString, models::Entity), EntityStyle>),
+}
+
+impl ImplicitClone for EntityStyler {}
+
+impl From<StyleSpec> for EntityStyler {
+    fn from(spec: StyleSpec) -> Self {
+        EntityStyler::Spec(Rc::new(spec))
+    }
+}
+
+impl EntityStyler {
+    pub fn style(&self, id: &IString, entity: &models::Entity) -> EntityStyle {
+        match self {
+            EntityStyler::Spec(spec) => spec.style(id, entity),
+            EntityStyler::Callback(callback) => callback.emit((id.clone(), entity.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entity(label: &str, properties: &[(&'static str, &'static str)]) -> models::Entity {
+        models::Entity {
+            label: Some(IString::from(label.to_string())),
+            properties: properties
+                .iter()
+                .map(|(label, value)| (IString::from(*label), IString::from(*value)))
+                .collect(),
+            ..models::Entity::default()
+        }
+    }
+
+    #[test]
+    fn test_graduated() {
+        let ramp = ColorRamp::red_to_green(0.0, 5.0);
+        assert_eq!(
+            ["-1", "0", "1.25", "2.5", "5", "9", "n/a"].map(|value| ramp.color(value)),
+            [
+                Some(IString::from("#d73027")),
+                Some(IString::from("#d73027")),
+                Some(IString::from("#eb8859")),
+                Some(IString::from("#fee08b")),
+                Some(IString::from("#1a9850")),
+                Some(IString::from("#1a9850")),
+                None,
+            ]
+        );
+
+        let classed = ColorRamp::Graduated {
+            stops: IArray::from(vec![(0.0, Rgb(0, 0, 0)), (4.0, Rgb(200, 200, 200))]),
+            classes: Some(2),
+        };
+        assert_eq!(
+            ["0", "1.9", "2", "4"].map(|value| classed.color(value).unwrap().to_string()),
+            ["#323232", "#323232", "#969696", "#969696"]
+        );
+    }
+
+    #[test]
+    fn test_spec() {
+        let spec = StyleSpec {
+            base: EntityStyle {
+                stroke: Some("black".into()),
+                stroke_width: Some(1.0),
+                ..EntityStyle::default()
+            },
+            rules: vec![StyleRule {
+                condition: Condition::Label("Path B".into()),
+                style: EntityStyle {
+                    stroke_dasharray: Some("4 2".into()),
+                    stroke_width: Some(3.0),
+                    ..EntityStyle::default()
+                },
+            }],
+            thematic: Some(Thematic {
+                property: "Score".into(),
+                ramp: ColorRamp::red_to_green(0.0, 5.0),
+                paint: Paint::Stroke,
+            }),
+        };
+
+        assert_eq!(
+            spec.style("a", &entity("Path A", &[("Score", "5")]))
+                .to_attributes(),
+            " stroke=\"#1a9850\" stroke-width=\"1\""
+        );
+        assert_eq!(
+            spec.style("b", &entity("Path B", &[])).to_attributes(),
+            " stroke=\"black\" stroke-dasharray=\"4 2\" stroke-width=\"3\""
+        );
+    }
+
+    #[test]
+    fn test_categorical() {
+        let ramp = ColorRamp::Categorical {
+            categories: IArray::from(vec![(IString::from("survey"), IString::from("navy"))]),
+            palette: SET1.iter().map(|color| IString::from(*color)).collect(),
+        };
+        assert_eq!(ramp.color("survey"), Some(IString::from("navy")));
+        assert_eq!(ramp.color("transit"), ramp.color("transit"));
+        assert!(SET1.contains(&ramp.color("transit").unwrap().as_str()));
+    }
+
+    #[test]
+    fn test_legend() {
+        let point = |label: &str, properties| models::Entity {
+            geometry: Some(Rc::new(Geometry::Point(geo::point! { x: 0.0, y: 0.0 }))),
+            ..entity(label, properties)
+        };
+        let entities = [
+            (
+                "a".into(),
+                point("Reef A", &[("Zone", "MNP"), ("Score", "1")]),
+            ),
+            (
+                "b".into(),
+                point("Reef B", &[("Zone", "HPZ"), ("Score", "4")]),
+            ),
+            (
+                "c".into(),
+                entity("Path C", &[("Zone", "MNP"), ("Score", "2.5")]),
+            ),
+        ];
+        let spec = StyleSpec {
+            base: EntityStyle {
+                stroke: Some("black".into()),
+                ..EntityStyle::default()
+            },
+            rules: vec![
+                StyleRule {
+                    condition: Condition::All,
+                    style: EntityStyle {
+                        stroke_width: Some(2.0),
+                        ..EntityStyle::default()
+                    },
+                },
+                StyleRule {
+                    condition: Condition::Id("b".into()),
+                    style: EntityStyle {
+                        symbol: Some(Symbol::Square),
+                        ..EntityStyle::default()
+                    },
+                },
+                StyleRule {
+                    condition: Condition::Label("Reef Z".into()),
+                    style: EntityStyle::default(),
+                },
+            ],
+            thematic: Some(Thematic {
+                property: "Zone".into(),
+                ramp: ColorRamp::Categorical {
+                    categories: IArray::from(vec![(IString::from("MNP"), IString::from("green"))]),
+                    palette: IArray::from(vec![IString::from("blue")]),
+                },
+                paint: Paint::Fill,
+            }),
+        };
+        let legend = spec.legend(&entities);
+        assert_eq!(
+            legend
+                .iter()
+                .map(|entry| (entry.label.as_str(), entry.point))
+                .collect::<Vec<_>>(),
+            [("Reef B", true), ("Zone: MNP", false), ("Zone: HPZ", true)]
+        );
+        assert_eq!(
+            legend[1].style.to_attributes(),
+            " fill=\"green\" stroke=\"black\" stroke-width=\"2\""
+        );
+
+        let spec = StyleSpec {
+            thematic: Some(Thematic {
+                property: "Score".into(),
+                ramp: ColorRamp::Graduated {
+                    stops: IArray::from(vec![(0.0, Rgb(0, 0, 0)), (5.0, Rgb(250, 250, 250))]),
+                    classes: Some(3),
+                },
+                paint: Paint::Fill,
+            }),
+            ..StyleSpec::default()
+        };
+        assert_eq!(
+            spec.legend(&entities)
+                .iter()
+                .map(|entry| (entry.label.as_str(), entry.style.fill.clone().unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                ("Score: 0 – 1.67", IString::from("#2a2a2a")),
+                ("Score: 1.67 – 3.33", IString::from("#7d7d7d")),
+                ("Score: 3.33 – 5", IString::from("#d0d0d0")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Point symbols, drawn at a constant size on screen whatever the zoom.
+//!
+//! Symbols are drawn in pixels around the origin, then placed at each point and scaled by the
+//! number of map units per pixel.
+
+use geo::{coord, Coord, Geometry, GeometryCollection, Point};
+use implicit_clone::{unsync::IString, ImplicitClone};
+
+use super::scene::{escape_attribute, escape_text};
+
+/// The width of point symbols in pixels, unless a style gives another.
+pub const DEFAULT_SYMBOL_SIZE: f32 = 10.0;
+
+/// The size of point labels in pixels.
+const LABEL_FONT_SIZE: f32 = 12.0;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Symbol {
+    #[default]
+    Circle,
+    Square,
+    /// A map pin, with its tip at the point.
+    Pin,
+    /// An image, centred on the point and scaled to the symbol size. SVG icons can be given as
+    /// `data:image/svg+xml,...` URLs.
+    Icon(IString),
+    /// SVG elements drawn in pixels around the point at the origin, e.g.
+    /// `<path d="M -5 5 L 0 -5 L 5 5 Z" />`. These are not scaled to the symbol size.
+    Svg(IString),
+}
+
+impl ImplicitClone for Symbol {}
+
+impl Symbol {
+    /// The symbol in pixels around the origin.
+    pub fn to_svg(&self, size: f32) -> String {
+        let half = size / 2.0;
+        match self {
+            Symbol::Circle => format!("<circle r=\"{half}\" />"),
+            Symbol::Square => {
+                format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" />",
+                    -half, -half
+                )
+            }
+            Symbol::Pin => {
+                // A round head on a point, one and a half times as tall as it is wide.
+                let top = -size * 1.5 + half;
+                format!(
+                    "<path d=\"M 0 0 L {} {top} A {half} {half} 0 1 1 {half} {top} Z\" /><circle cy=\"{top}\" r=\"{}\" fill=\"white\" />",
+                    -half,
+                    half / 2.5,
+                )
+            }
+            Symbol::Icon(href) => format!(
+                "<image x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" href=\"{}\" />",
+                -half,
+                -half,
+                escape_attribute(href),
+            ),
+            Symbol::Svg(markup) => markup.to_string(),
+        }
+    }
+
+    /// Where a label goes beside the symbol, in pixels from the point.
+    pub(super) fn label_offset(&self, size: f32) -> (f32, f32) {
+        match self {
+            Symbol::Pin => (size / 2.0 + 4.0, -size),
+            _ => (size / 2.0 + 4.0, 0.0),
+        }
+    }
+
+    /// The centre of the symbol in pixels from the point, which is the centre of the head of a
+    /// pin.
+    pub(super) fn center(&self, size: f32) -> Coord {
+        match self {
+            Symbol::Pin => coord! { x: 0.0, y: -size as f64 },
+            _ => Coord::zero(),
+        }
+    }
+}
+
+/// The points of a geometry, drawn as symbols, and the rest of it, if any.
+pub fn split_points(geometry: Geometry) -> (Vec<Point>, Option<Geometry>) {
+    match geometry {
+        Geometry::Point(point) => (vec![point], None),
+        Geometry::MultiPoint(points) => (points.0, None),
+        Geometry::GeometryCollection(collection) => {
+            let (mut points, mut rest) = (Vec::new(), Vec::new());
+            for geometry in collection {
+                let (more_points, more_rest) = split_points(geometry);
+                points.extend(more_points);
+                rest.extend(more_rest);
+            }
+            let rest = (!rest.is_empty())
+                .then(|| Geometry::GeometryCollection(GeometryCollection::new_from(rest)));
+            (points, rest)
+        }
+        geometry => (vec![], Some(geometry)),
+    }
+}
+
+/// Symbols at points given in SVG coordinates, with a label beside the first one if any.
+pub fn points_svg(
+    points: &[Point],
+    symbol: &Symbol,
+    size: f32,
+    label: Option<&str>,
+    units_per_pixel: f64,
+) -> String {
+    let symbol_svg = symbol.to_svg(size);
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let label = label.filter(|_| i == 0).map(|label| {
+                let (x, y) = symbol.label_offset(size);
+                format!(
+                    "<text class=\"entity-label\" x=\"{x}\" y=\"{y}\" dominant-baseline=\"middle\" font-size=\"{LABEL_FONT_SIZE}\" fill=\"#222\" stroke=\"white\" stroke-width=\"3\" stroke-dasharray=\"none\" paint-order=\"stroke\">{}</text>",
+                    escape_text(label),
+                )
+            });
+            format!(
+                "<g transform=\"translate({} {}) scale({units_per_pixel})\">{symbol_svg}{}</g>",
+                point.x(),
+                point.y(),
+                label.unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{line_string, point, MultiPoint};
+
+    use super::*;
+
+    #[test]
+    fn test_split_points() {
+        let line = Geometry::LineString(line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 1.0)]);
+        let collection = Geometry::GeometryCollection(GeometryCollection::new_from(vec![
+            Geometry::Point(point! { x: 1.0, y: 2.0 }),
+            Geometry::MultiPoint(MultiPoint::from(vec![(3.0, 4.0), (5.0, 6.0)])),
+            line.clone(),
+        ]));
+        let (points, rest) = split_points(collection);
+        assert_eq!(
+            points,
+            vec![
+                point! { x: 1.0, y: 2.0 },
+                point! { x: 3.0, y: 4.0 },
+                point! { x: 5.0, y: 6.0 },
+            ]
+        );
+        assert_eq!(
+            rest,
+            Some(Geometry::GeometryCollection(GeometryCollection::new_from(
+                vec![line.clone()]
+            )))
+        );
+
+        assert_eq!(split_points(line.clone()), (vec![], Some(line)));
+    }
+
+    #[test]
+    fn test_points_svg() {
+        let points = [point! { x: 10.0, y: -20.0 }, point! { x: 30.0, y: -40.0 }];
+        assert_eq!(
+            points_svg(&points, &Symbol::Square, 8.0, Some("A & B"), 0.5),
+            "<g transform=\"translate(10 -20) scale(0.5)\"><rect x=\"-4\" y=\"-4\" width=\"8\" height=\"8\" /><text class=\"entity-label\" x=\"8\" y=\"0\" dominant-baseline=\"middle\" font-size=\"12\" fill=\"#222\" stroke=\"white\" stroke-width=\"3\" stroke-dasharray=\"none\" paint-order=\"stroke\">A &amp; B</text></g>\
+            <g transform=\"translate(30 -40) scale(0.5)\"><rect x=\"-4\" y=\"-4\" width=\"8\" height=\"8\" /></g>"
+        );
+        assert_eq!(
+            Symbol::Pin.to_svg(10.0),
+            "<path d=\"M 0 0 L -5 -10 A 5 5 0 1 1 5 -10 Z\" /><circle cy=\"-10\" r=\"2\" fill=\"white\" />"
+        );
+    }
+}
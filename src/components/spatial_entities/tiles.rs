@@ -0,0 +1,205 @@
+//! Slippy map tile layers, as served by OpenStreetMap style XYZ and TMS tile servers.
+//!
+//! Tiles are in Web Mercator. On a map in another CRS each tile is placed by the bounds of its
+//! reprojected corners and is not warped, which is accurate for Web Mercator maps and close
+//! enough at street level for the others.
+
+use std::f64::consts::PI;
+
+use geo::{coord, Coord, Rect};
+use implicit_clone::{unsync::IString, ImplicitClone};
+
+use crate::crs::Crs;
+
+/// The most tiles requested for one view, in case a viewport is much larger than expected.
+const MAX_TILES: usize = 256;
+
+const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileScheme {
+    /// Rows are numbered from the north, as used by OpenStreetMap and most tile servers.
+    #[default]
+    Xyz,
+    /// Rows are numbered from the south.
+    Tms,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileLayer {
+    /// The URL of a tile, with `{z}`, `{x}` and `{y}` standing for its zoom, column and row,
+    /// e.g. `http://localhost:8080/tiles/{z}/{x}/{y}.png`. `{-y}` stands for the row counted
+    /// from the other end.
+    pub url_template: IString,
+    /// Shown on the map, as most tile servers require.
+    pub attribution: IString,
+    pub scheme: TileScheme,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// The width and height of a tile in pixels.
+    pub tile_size: u32,
+}
+
+impl ImplicitClone for TileLayer {}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    /// The row, counted from the north whatever the scheme.
+    pub y: u32,
+}
+
+impl TileLayer {
+    pub fn new(url_template: impl Into<IString>) -> Self {
+        Self {
+            url_template: url_template.into(),
+            attribution: IString::default(),
+            scheme: TileScheme::Xyz,
+            min_zoom: 0,
+            max_zoom: 19,
+            tile_size: 256,
+        }
+    }
+
+    pub fn url(&self, tile: TileId) -> String {
+        let flipped = (1u32 << tile.z) - 1 - tile.y;
+        let (y, other_y) = match self.scheme {
+            TileScheme::Xyz => (tile.y, flipped),
+            TileScheme::Tms => (flipped, tile.y),
+        };
+        self.url_template
+            .replace("{z}", &tile.z.to_string())
+            .replace("{x}", &tile.x.to_string())
+            .replace("{-y}", &other_y.to_string())
+            .replace("{y}", &y.to_string())
+    }
+
+    /// The zoom at which a viewport spanning the given longitudes fills the given width in
+    /// pixels.
+    pub fn zoom(&self, lon_lat_viewport: &Rect, width_px: f64) -> u8 {
+        let span = lon_lat_viewport.width().clamp(1e-9, 360.0);
+        let zoom = (width_px * 360.0 / (span * self.tile_size as f64))
+            .log2()
+            .round();
+        zoom.clamp(self.min_zoom as f64, self.max_zoom as f64) as u8
+    }
+
+    /// The tiles that cover a viewport given in longitude and latitude, or none if there would
+    /// be an unreasonable number of them.
+    pub fn visible_tiles(&self, lon_lat_viewport: &Rect, zoom: u8) -> Vec<TileId> {
+        let (min_x, max_y) = tile_position(lon_lat_viewport.min(), zoom);
+        let (max_x, min_y) = tile_position(lon_lat_viewport.max(), zoom);
+        let count = (max_x - min_x + 1) as usize * (max_y - min_y + 1) as usize;
+        if count > MAX_TILES {
+            log::warn!("Not drawing {count} tiles at zoom {zoom}");
+            return Vec::new();
+        }
+        (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| TileId { z: zoom, x, y }))
+            .collect()
+    }
+}
+
+/// The column and row of the tile containing a longitude and latitude.
+fn tile_position(Coord { x, y }: Coord, zoom: u8) -> (u32, u32) {
+    let n = (1u64 << zoom) as f64;
+    let latitude = y.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    let column = (x + 180.0) / 360.0 * n;
+    let row = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0 * n;
+    let clamp = |v: f64| v.floor().clamp(0.0, n - 1.0) as u32;
+    (clamp(column), clamp(row))
+}
+
+/// The longitude and latitude of a tile corner given by column and row.
+fn tile_corner(x: u32, y: u32, zoom: u8) -> Coord {
+    let n = (1u64 << zoom) as f64;
+    coord! {
+        x: x as f64 / n * 360.0 - 180.0,
+        y: (PI * (1.0 - 2.0 * y as f64 / n)).sinh().atan().to_degrees(),
+    }
+}
+
+/// The bounds of a tile in longitude and latitude.
+pub fn tile_bounds(tile: TileId) -> Rect {
+    Rect::new(
+        tile_corner(tile.x, tile.y, tile.z),
+        tile_corner(tile.x + 1, tile.y + 1, tile.z),
+    )
+}
+
+/// The bounds of a rectangle in one CRS after reprojecting its corners and edge midpoints to
+/// another.
+pub fn reproject_rect(rect: &Rect, from: &Crs, to: &Crs) -> Rect {
+    if from == to {
+        return *rect;
+    }
+    let (min, max, center) = (rect.min(), rect.max(), rect.center());
+    let points = [
+        min,
+        max,
+        coord! { x: min.x, y: max.y },
+        coord! { x: max.x, y: min.y },
+        coord! { x: center.x, y: min.y },
+        coord! { x: center.x, y: max.y },
+        coord! { x: min.x, y: center.y },
+        coord! { x: max.x, y: center.y },
+    ]
+    .map(|point| from.transform(to, point));
+    points[1..]
+        .iter()
+        .fold(Rect::new(points[0], points[0]), |bounds, point| {
+            Rect::new(
+                coord! { x: bounds.min().x.min(point.x), y: bounds.min().y.min(point.y) },
+                coord! { x: bounds.max().x.max(point.x), y: bounds.max().y.max(point.y) },
+            )
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_url() {
+        let mut layer = TileLayer::new("http://localhost:8080/{z}/{x}/{y}.png?flipped={-y}");
+        let tile = TileId { z: 3, x: 7, y: 2 };
+        assert_eq!(layer.url(tile), "http://localhost:8080/3/7/2.png?flipped=5");
+
+        layer.scheme = TileScheme::Tms;
+        assert_eq!(layer.url(tile), "http://localhost:8080/3/7/5.png?flipped=2");
+    }
+
+    #[test]
+    fn test_visible_tiles() {
+        let layer = TileLayer::new("{z}/{x}/{y}");
+        // Around Cairns, as in sample_data/a-plus-b.ttl
+        let viewport = Rect::new(
+            coord! { x: 145.7, y: -17.05 },
+            coord! { x: 145.9, y: -16.9 },
+        );
+        let zoom = layer.zoom(&viewport, 800.0);
+        assert_eq!(zoom, 12);
+        assert_eq!(
+            layer.visible_tiles(&viewport, zoom),
+            [2243, 2244]
+                .into_iter()
+                .flat_map(|y| (3705..=3708).map(move |x| TileId { z: 12, x, y }))
+                .collect::<Vec<_>>()
+        );
+
+        let bounds = tile_bounds(TileId {
+            z: 12,
+            x: 3705,
+            y: 2243,
+        });
+        assert!(bounds.min().x <= 145.7 && bounds.max().x > 145.7);
+        assert!(bounds.max().y >= -16.9 && bounds.min().y < -16.9);
+
+        assert_eq!(
+            layer.visible_tiles(&viewport, 19).len(),
+            0,
+            "too many tiles should be refused"
+        );
+    }
+}
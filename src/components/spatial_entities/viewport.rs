@@ -0,0 +1,119 @@
+//! Viewport arithmetic for panning and zooming.
+//!
+//! Viewports are rectangles in the CRS of the map, with y increasing northwards. Positions on
+//! screen are given as fractions of the rendered map, from the top left corner.
+
+use geo::{coord, BoundingRect, Coord, Geometry, Rect, Scale};
+
+/// The margin left around entities when fitting the viewport to them.
+pub const FIT_SCALE: f64 = 1.25;
+
+/// The point of the map at a position on screen.
+pub fn point_at(viewport: &Rect, at: Coord) -> Coord {
+    coord! {
+        x: viewport.min().x + at.x * viewport.width(),
+        y: viewport.max().y - at.y * viewport.height(),
+    }
+}
+
+/// The position on screen of a point of the map, the inverse of [`point_at`].
+pub fn position_of(viewport: &Rect, point: Coord) -> Coord {
+    coord! {
+        x: (point.x - viewport.min().x) / viewport.width(),
+        y: (viewport.max().y - point.y) / viewport.height(),
+    }
+}
+
+/// Zooms in by a factor, or out for factors below one, keeping the point at a position on
+/// screen still.
+pub fn zoom_at(viewport: &Rect, at: Coord, factor: f64) -> Rect {
+    let fixed = point_at(viewport, at);
+    let scale = |c: Coord| fixed + (c - fixed) / factor;
+    Rect::new(scale(viewport.min()), scale(viewport.max()))
+}
+
+/// Moves the contents of the map by a distance on screen, given as fractions of its size.
+pub fn pan(viewport: &Rect, by: Coord) -> Rect {
+    let offset = coord! {
+        x: -by.x * viewport.width(),
+        y: by.y * viewport.height(),
+    };
+    Rect::new(viewport.min() + offset, viewport.max() + offset)
+}
+
+/// A viewport showing the whole of some bounds with a margin, keeping the aspect ratio of
+/// `current`. Points and other degenerate bounds keep the scale of `current` too.
+pub fn fit(bounds: &Rect, current: &Rect) -> Rect {
+    let aspect = current.width() / current.height();
+    let (mut width, mut height) = (bounds.width() * FIT_SCALE, bounds.height() * FIT_SCALE);
+    if width <= 0.0 && height <= 0.0 {
+        (width, height) = (current.width(), current.height());
+    } else if width / height.max(f64::MIN_POSITIVE) > aspect {
+        height = width / aspect;
+    } else {
+        width = height * aspect;
+    }
+    let center = bounds.center();
+    let half = coord! { x: width / 2.0, y: height / 2.0 };
+    Rect::new(center - half, center + half)
+}
+
+/// The bounds of some geometries, enlarged by [`FIT_SCALE`], as shown by a dynamic viewport.
+pub fn around<'a>(geometries: impl IntoIterator<Item = &'a Geometry>) -> Option<Rect> {
+    geometries
+        .into_iter()
+        .filter_map(|geometry| geometry.bounding_rect())
+        .reduce(|a, b| {
+            Rect::new(
+                coord! { x: a.min().x.min(b.min().x), y: a.min().y.min(b.min().y) },
+                coord! { x: a.max().x.max(b.max().x), y: a.max().y.max(b.max().y) },
+            )
+        })
+        .map(|bounds| bounds.scale(FIT_SCALE))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn viewport() -> Rect {
+        Rect::new(coord! { x: 0.0, y: 0.0 }, coord! { x: 100.0, y: 50.0 })
+    }
+
+    #[test]
+    fn test_zoom_at() {
+        let zoomed = zoom_at(&viewport(), coord! { x: 0.25, y: 0.5 }, 2.0);
+        assert_eq!(
+            zoomed,
+            Rect::new(coord! { x: 12.5, y: 12.5 }, coord! { x: 62.5, y: 37.5 })
+        );
+        assert_eq!(
+            point_at(&zoomed, coord! { x: 0.25, y: 0.5 }),
+            point_at(&viewport(), coord! { x: 0.25, y: 0.5 })
+        );
+    }
+
+    #[test]
+    fn test_pan() {
+        // Dragging right and down shows what is to the west and north.
+        assert_eq!(
+            pan(&viewport(), coord! { x: 0.1, y: 0.2 }),
+            Rect::new(coord! { x: -10.0, y: 10.0 }, coord! { x: 90.0, y: 60.0 })
+        );
+    }
+
+    #[test]
+    fn test_fit() {
+        let tall = Rect::new(coord! { x: 10.0, y: 10.0 }, coord! { x: 12.0, y: 20.0 });
+        assert_eq!(
+            fit(&tall, &viewport()),
+            Rect::new(coord! { x: -1.5, y: 8.75 }, coord! { x: 23.5, y: 21.25 })
+        );
+
+        let point = Rect::new(coord! { x: 10.0, y: 10.0 }, coord! { x: 10.0, y: 10.0 });
+        assert_eq!(
+            fit(&point, &viewport()),
+            Rect::new(coord! { x: -40.0, y: -15.0 }, coord! { x: 60.0, y: 35.0 })
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! A slider that steps through the times entities were generated at, e.g. to replay how the
+//! data products of a mission appeared on a map.
+
+use std::{rc::Rc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use web_sys::HtmlInputElement;
+use yew::{platform::time::sleep, prelude::*};
+
+use crate::models;
+
+/// The distinct times at which entities were generated, in order.
+pub fn generation_times<'a>(
+    entities: impl IntoIterator<Item = &'a models::Entity>,
+) -> Rc<[DateTime<Utc>]> {
+    let mut times = entities
+        .into_iter()
+        .filter_map(|entity| entity.generated_at)
+        .collect::<Vec<_>>();
+    times.sort();
+    times.dedup();
+    times.into()
+}
+
+/// The position of the last of the times at or before a time, or of the last time for `None`.
+fn position(times: &[DateTime<Utc>], value: Option<DateTime<Utc>>) -> usize {
+    let end = match value {
+        Some(value) => times.partition_point(|time| *time <= value),
+        None => times.len(),
+    };
+    end.saturating_sub(1)
+}
+
+pub enum Message {
+    /// Moves to the time at a position.
+    Seek(usize),
+    /// Moves some times forwards or backwards.
+    Step(isize),
+    TogglePlaying,
+    /// Moves on while playing, unless playing was stopped or restarted since the tick was
+    /// scheduled.
+    Tick(u32),
+}
+
+#[derive(PartialEq, Properties)]
+pub struct Props {
+    /// The times that can be chosen, in order, e.g. from [`generation_times`].
+    pub times: Rc<[DateTime<Utc>]>,
+    /// The time chosen. When given, it only changes through this prop, so `on_change` should be
+    /// used to keep it up to date. The last time is chosen at first otherwise.
+    #[prop_or_default]
+    pub value: Option<DateTime<Utc>>,
+    /// Called with the time chosen whenever it changes, including while playing.
+    #[prop_or_default]
+    pub on_change: Option<Callback<DateTime<Utc>>>,
+    /// How long each time is shown for while playing, in milliseconds.
+    #[prop_or(1000)]
+    pub step_millis: u64,
+}
+
+pub struct TimeSlider {
+    /// The position of the time chosen when it is not controlled by the `value` prop.
+    position: usize,
+    playing: bool,
+    /// Counts the times playing started, so that ticks from before are ignored.
+    generation: u32,
+}
+
+impl TimeSlider {
+    fn position(&self, ctx: &Context<Self>) -> usize {
+        let props = ctx.props();
+        match props.value {
+            Some(value) => position(&props.times, Some(value)),
+            None => self.position.min(props.times.len().saturating_sub(1)),
+        }
+    }
+
+    fn seek(&mut self, ctx: &Context<Self>, position: usize) -> bool {
+        let props = ctx.props();
+        let Some(time) = props.times.get(position) else {
+            return false;
+        };
+        if let Some(on_change) = &props.on_change {
+            on_change.emit(*time);
+        }
+        self.position = position;
+        props.value.is_none()
+    }
+
+    fn schedule_tick(&self, ctx: &Context<Self>) {
+        let generation = self.generation;
+        let delay = Duration::from_millis(ctx.props().step_millis);
+        ctx.link().send_future(async move {
+            sleep(delay).await;
+            Message::Tick(generation)
+        });
+    }
+}
+
+impl Component for TimeSlider {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            position: position(&ctx.props().times, None),
+            playing: false,
+            generation: 0,
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        if props.times != old_props.times {
+            self.position = position(&props.times, None);
+            self.playing = false;
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::Seek(position) => {
+                self.playing = false;
+                self.seek(ctx, position);
+                true
+            }
+            Message::Step(steps) => {
+                self.playing = false;
+                let last = ctx.props().times.len().saturating_sub(1);
+                let position = self.position(ctx).saturating_add_signed(steps).min(last);
+                self.seek(ctx, position);
+                true
+            }
+            Message::TogglePlaying => {
+                self.playing = !self.playing && ctx.props().times.len() > 1;
+                if self.playing {
+                    self.generation = self.generation.wrapping_add(1);
+                    // Playing from the last time starts again from the first.
+                    if self.position(ctx) + 1 >= ctx.props().times.len() {
+                        self.seek(ctx, 0);
+                    }
+                    self.schedule_tick(ctx);
+                }
+                true
+            }
+            Message::Tick(generation) => {
+                if !self.playing || generation != self.generation {
+                    return false;
+                }
+                let position = self.position(ctx) + 1;
+                self.seek(ctx, position);
+                if position + 1 >= ctx.props().times.len() {
+                    self.playing = false;
+                } else {
+                    self.schedule_tick(ctx);
+                }
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let times = &ctx.props().times;
+        if times.is_empty() {
+            return html!();
+        }
+        let position = self.position(ctx);
+        let last = times.len() - 1;
+        let oninput = ctx.link().batch_callback(|event: InputEvent| {
+            event
+                .target_dyn_into::<HtmlInputElement>()
+                .map(|input| Message::Seek(input.value_as_number() as usize))
+        });
+        let time = times[position].format("%Y-%m-%d %H:%M:%S UTC").to_string();
+        html! {
+            <fieldset class="time-slider">
+                <legend>{"Time"}</legend>
+                <button
+                    type="button"
+                    aria-label="Step back"
+                    disabled={position == 0}
+                    onclick={ctx.link().callback(|_| Message::Step(-1))}
+                >
+                    {"⏮"}
+                </button>
+                <button
+                    type="button"
+                    aria-label={if self.playing { "Pause" } else { "Play" }}
+                    aria-pressed={self.playing.to_string()}
+                    disabled={last == 0}
+                    onclick={ctx.link().callback(|_| Message::TogglePlaying)}
+                >
+                    {if self.playing { "⏸" } else { "▶" }}
+                </button>
+                <button
+                    type="button"
+                    aria-label="Step forward"
+                    disabled={position == last}
+                    onclick={ctx.link().callback(|_| Message::Step(1))}
+                >
+                    {"⏭"}
+                </button>
+                <input
+                    type="range"
+                    min="0"
+                    max={last.to_string()}
+                    step="1"
+                    value={position.to_string()}
+                    aria-label="Time"
+                    aria-valuetext={time.clone()}
+                    {oninput}
+                />
+                <output>{time}</output>
+            </fieldset>
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let time = |time: &str| time.parse::<DateTime<Utc>>().ok();
+        let entities = [
+            models::Entity {
+                generated_at: time("2029-01-02T09:30:00Z"),
+                ..models::Entity::default()
+            },
+            models::Entity::default(),
+            models::Entity {
+                generated_at: time("2029-01-01T20:05:19Z"),
+                ..models::Entity::default()
+            },
+            models::Entity {
+                generated_at: time("2029-01-02T09:30:00Z"),
+                ..models::Entity::default()
+            },
+        ];
+        let times = generation_times(&entities);
+        assert_eq!(
+            times.to_vec(),
+            [time("2029-01-01T20:05:19Z"), time("2029-01-02T09:30:00Z")]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(position(&times, None), 1);
+        assert_eq!(position(&times, time("2029-01-02T00:00:00Z")), 0);
+        assert_eq!(position(&times, time("2029-01-02T09:30:00Z")), 1);
+        assert_eq!(position(&[], None), 0);
+    }
+}
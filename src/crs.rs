@@ -0,0 +1,350 @@
+//! Coordinate reference systems and reprojection between them.
+//!
+//! Only the systems we meet in practice are supported: WGS 84 longitude/latitude as used by
+//! GeoSPARQL and GeoJSON, its latitude/longitude EPSG form, Web Mercator base maps, and UTM
+//! zones. The MGA zones of GDA94 and GDA2020 are treated as the UTM zones they are based on,
+//! as their datums differ from WGS 84 by less than two metres.
+
+use std::{f64::consts::PI, fmt};
+
+use geo::{Coord, Geometry, MapCoords};
+
+/// WGS 84 semi-major axis, in metres.
+const A: f64 = 6_378_137.0;
+/// WGS 84 flattening.
+const F: f64 = 1.0 / 298.257_223_563;
+/// UTM scale factor on the central meridian.
+const K0: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+/// The latitude at which Web Mercator becomes square.
+const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+const EPSG_IRI: &str = "http://www.opengis.net/def/crs/EPSG/0/";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Crs {
+    /// WGS 84 with longitude as x and latitude as y, in degrees.
+    #[default]
+    Crs84,
+    /// WGS 84 with latitude as x and longitude as y, in degrees.
+    Epsg4326,
+    /// EPSG:3857, in metres.
+    WebMercator,
+    /// A UTM zone from 1 to 60, in metres.
+    Utm { zone: u8, south: bool },
+}
+
+impl Crs {
+    /// The MGA zone of GDA94 or GDA2020 with the given number.
+    pub fn mga(zone: u8) -> Self {
+        Self::Utm { zone, south: true }
+    }
+
+    pub fn from_epsg(code: u32) -> Option<Self> {
+        let utm = |zone: u32, south| {
+            (1..=60).contains(&zone).then_some(Self::Utm {
+                zone: zone as u8,
+                south,
+            })
+        };
+        match code {
+            4326 => Some(Self::Epsg4326),
+            3857 | 900913 => Some(Self::WebMercator),
+            32601..=32660 => utm(code - 32600, false),
+            32701..=32760 => utm(code - 32700, true),
+            // GDA94 and GDA2020 MGA zones
+            28348..=28358 => utm(code - 28300, true),
+            7846..=7859 => utm(code - 7800, true),
+            _ => None,
+        }
+    }
+
+    /// Recognises OGC CRS IRIs, such as those given in a `geo:wktLiteral`, as well as
+    /// `EPSG:<code>` and `CRS:84` shorthands.
+    pub fn from_iri(iri: &str) -> Option<Self> {
+        let iri = iri.trim();
+        if iri == "http://www.opengis.net/def/crs/OGC/1.3/CRS84"
+            || iri.eq_ignore_ascii_case("CRS:84")
+            || iri.eq_ignore_ascii_case("OGC:CRS84")
+        {
+            return Some(Self::Crs84);
+        }
+        let code = iri
+            .strip_prefix(EPSG_IRI)
+            .or_else(|| iri.strip_prefix("EPSG:"))
+            .or_else(|| iri.strip_prefix("epsg:"))?;
+        Self::from_epsg(code.parse().ok()?)
+    }
+
+    /// The EPSG code of the CRS, with UTM zones given as their WGS 84 codes.
+    pub fn epsg(&self) -> Option<u32> {
+        match self {
+            Self::Crs84 => None,
+            Self::Epsg4326 => Some(4326),
+            Self::WebMercator => Some(3857),
+            Self::Utm { zone, south: false } => Some(32600 + *zone as u32),
+            Self::Utm { zone, south: true } => Some(32700 + *zone as u32),
+        }
+    }
+
+    pub fn iri(&self) -> String {
+        match self.epsg() {
+            Some(code) => format!("{EPSG_IRI}{code}"),
+            None => "http://www.opengis.net/def/crs/OGC/1.3/CRS84".into(),
+        }
+    }
+
+    /// Whether coordinates are in degrees rather than metres.
+    pub fn is_geographic(&self) -> bool {
+        matches!(self, Self::Crs84 | Self::Epsg4326)
+    }
+
+    /// Converts a coordinate in this CRS to CRS84 longitude and latitude.
+    pub fn unproject(&self, coord: Coord) -> Coord {
+        match *self {
+            Self::Crs84 => coord,
+            Self::Epsg4326 => Coord {
+                x: coord.y,
+                y: coord.x,
+            },
+            Self::WebMercator => Coord {
+                x: (coord.x / A).to_degrees(),
+                y: (2.0 * (coord.y / A).exp().atan() - PI / 2.0).to_degrees(),
+            },
+            Self::Utm { zone, south } => utm_to_lon_lat(zone, south, coord),
+        }
+    }
+
+    /// Converts a CRS84 longitude and latitude to a coordinate in this CRS.
+    pub fn project(&self, coord: Coord) -> Coord {
+        match *self {
+            Self::Crs84 => coord,
+            Self::Epsg4326 => Coord {
+                x: coord.y,
+                y: coord.x,
+            },
+            Self::WebMercator => {
+                let latitude = coord
+                    .y
+                    .clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE)
+                    .to_radians();
+                Coord {
+                    x: A * coord.x.to_radians(),
+                    y: A * (PI / 4.0 + latitude / 2.0).tan().ln(),
+                }
+            }
+            Self::Utm { zone, south } => lon_lat_to_utm(zone, south, coord),
+        }
+    }
+
+    pub fn transform(&self, to: &Crs, coord: Coord) -> Coord {
+        if self == to {
+            coord
+        } else {
+            to.project(self.unproject(coord))
+        }
+    }
+
+    /// Reprojects a geometry from this CRS to another, vertex by vertex.
+    pub fn reproject(&self, geometry: &Geometry, to: &Crs) -> Geometry {
+        if self == to {
+            geometry.clone()
+        } else {
+            geometry.map_coords(|coord| self.transform(to, coord))
+        }
+    }
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.epsg() {
+            Some(code) => write!(f, "EPSG:{code}"),
+            None => write!(f, "CRS:84"),
+        }
+    }
+}
+
+// Transverse Mercator, using the series in n of Krüger as given by Karney (2011), which are
+// accurate to well under a millimetre within a zone.
+
+struct TransverseMercator {
+    n: f64,
+    /// The rectifying radius.
+    a: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+}
+
+fn transverse_mercator() -> TransverseMercator {
+    let n = F / (2.0 - F);
+    let (n2, n3) = (n * n, n * n * n);
+    TransverseMercator {
+        n,
+        a: A / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+        alpha: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+            61.0 * n3 / 240.0,
+        ],
+        beta: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+            n2 / 48.0 + n3 / 15.0,
+            17.0 * n3 / 480.0,
+        ],
+        delta: [
+            2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+            7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+            56.0 * n3 / 15.0,
+        ],
+    }
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    (zone as f64 * 6.0 - 183.0).to_radians()
+}
+
+fn lon_lat_to_utm(zone: u8, south: bool, Coord { x, y }: Coord) -> Coord {
+    let tm = transverse_mercator();
+    let (phi, lambda) = (y.to_radians(), x.to_radians() - central_meridian(zone));
+    let e = 2.0 * tm.n.sqrt() / (1.0 + tm.n);
+    let t = (phi.sin().atanh() - e * (e * phi.sin()).atanh()).sinh();
+    let xi = (t / lambda.cos()).atan();
+    let eta = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+    let (easting, northing) =
+        tm.alpha
+            .iter()
+            .enumerate()
+            .fold((eta, xi), |(easting, northing), (j, alpha)| {
+                let k = 2.0 * (j + 1) as f64;
+                (
+                    easting + alpha * (k * xi).cos() * (k * eta).sinh(),
+                    northing + alpha * (k * xi).sin() * (k * eta).cosh(),
+                )
+            });
+    Coord {
+        x: FALSE_EASTING + K0 * tm.a * easting,
+        y: if south { FALSE_NORTHING_SOUTH } else { 0.0 } + K0 * tm.a * northing,
+    }
+}
+
+fn utm_to_lon_lat(zone: u8, south: bool, Coord { x, y }: Coord) -> Coord {
+    let tm = transverse_mercator();
+    let xi = (y - if south { FALSE_NORTHING_SOUTH } else { 0.0 }) / (K0 * tm.a);
+    let eta = (x - FALSE_EASTING) / (K0 * tm.a);
+    let (xi_prime, eta_prime) =
+        tm.beta
+            .iter()
+            .enumerate()
+            .fold((xi, eta), |(xi_prime, eta_prime), (j, beta)| {
+                let k = 2.0 * (j + 1) as f64;
+                (
+                    xi_prime - beta * (k * xi).sin() * (k * eta).cosh(),
+                    eta_prime - beta * (k * xi).cos() * (k * eta).sinh(),
+                )
+            });
+    let chi = (xi_prime.sin() / eta_prime.cosh()).asin();
+    let phi = tm.delta.iter().enumerate().fold(chi, |phi, (j, delta)| {
+        phi + delta * (2.0 * (j + 1) as f64 * chi).sin()
+    });
+    let lambda = central_meridian(zone) + (eta_prime.sinh() / xi_prime.cos()).atan();
+    Coord {
+        x: lambda.to_degrees(),
+        y: phi.to_degrees(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(actual: Coord, expected: Coord, tolerance: f64) {
+        assert!(
+            (actual.x - expected.x).abs() < tolerance && (actual.y - expected.y).abs() < tolerance,
+            "{actual:?} is not within {tolerance} of {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_from_iri() {
+        assert_eq!(
+            Crs::from_iri("http://www.opengis.net/def/crs/OGC/1.3/CRS84"),
+            Some(Crs::Crs84)
+        );
+        assert_eq!(
+            Crs::from_iri("http://www.opengis.net/def/crs/EPSG/0/7856"),
+            Some(Crs::mga(56))
+        );
+        assert_eq!(
+            Crs::from_iri("EPSG:32617"),
+            Some(Crs::Utm {
+                zone: 17,
+                south: false
+            })
+        );
+        assert_eq!(Crs::from_iri("EPSG:2193"), None);
+        assert_eq!(Crs::mga(55).to_string(), "EPSG:32755");
+    }
+
+    #[test]
+    fn test_utm() {
+        // The CN Tower, as given in the Wikipedia article on UTM.
+        let cn_tower = Coord {
+            x: -(79.0 + 23.0 / 60.0 + 13.7 / 3600.0),
+            y: 43.0 + 38.0 / 60.0 + 33.24 / 3600.0,
+        };
+        let zone_17 = Crs::Utm {
+            zone: 17,
+            south: false,
+        };
+        assert_close(
+            zone_17.project(cn_tower),
+            Coord {
+                x: 630_084.0,
+                y: 4_833_438.0,
+            },
+            1.0,
+        );
+
+        let sydney = Coord {
+            x: 151.2093,
+            y: -33.8688,
+        };
+        let mga_56 = Crs::Crs84.transform(&Crs::mga(56), sydney);
+        assert!(mga_56.y > 6_000_000.0);
+        // Round trips agree to within a centimetre.
+        assert_close(Crs::mga(56).transform(&Crs::Crs84, mga_56), sydney, 1e-7);
+    }
+
+    #[test]
+    fn test_web_mercator() {
+        assert_close(
+            Crs::WebMercator.project(Coord {
+                x: 180.0,
+                y: MAX_MERCATOR_LATITUDE,
+            }),
+            Coord {
+                x: 20_037_508.342_789_244,
+                y: 20_037_508.342_789_244,
+            },
+            1e-3,
+        );
+
+        let darwin = Coord {
+            x: 130.8456,
+            y: -12.4634,
+        };
+        assert_close(
+            Crs::WebMercator.transform(
+                &Crs::Epsg4326,
+                Crs::Crs84.transform(&Crs::WebMercator, darwin),
+            ),
+            Coord {
+                x: darwin.y,
+                y: darwin.x,
+            },
+            1e-9,
+        );
+    }
+}
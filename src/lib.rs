@@ -2,4 +2,7 @@
 
 pub mod apis;
 pub mod components;
+pub mod crs;
+pub mod measure;
 pub mod models;
+pub mod scoring;
@@ -0,0 +1,222 @@
+//! Geodesic measurements of geometries, and their formatting for display.
+//!
+//! Geometries are reprojected to WGS 84 and measured on the ellipsoid, so that lengths and areas
+//! are true whatever the CRS they are given in.
+
+use geo::{
+    BoundingRect, Centroid, Coord, GeodesicArea, GeodesicLength, Geometry, LineString, Rect,
+};
+
+use crate::{crs::Crs, models};
+
+/// Metres in a nautical mile.
+const NAUTICAL_MILE: f64 = 1852.0;
+
+/// The units lengths are shown in, with the larger unit used for lengths of a kilometre or more.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LengthUnit {
+    /// Metres and kilometres.
+    #[default]
+    Metric,
+    /// Nautical miles, with metres for lengths of less than a tenth of a mile.
+    Nautical,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Measurements {
+    /// The length of the lines, in metres.
+    pub length: f64,
+    /// The length of the outlines of the polygons, in metres.
+    pub perimeter: f64,
+    /// The area of the polygons, in square metres.
+    pub area: f64,
+    /// The centroid in CRS84 longitude and latitude, computed in the CRS of the geometry.
+    pub centroid: Option<Coord>,
+    /// The bounds in CRS84 longitude and latitude.
+    pub bounds: Option<Rect>,
+}
+
+impl Measurements {
+    pub fn of(geometry: &Geometry, crs: &Crs) -> Self {
+        let lon_lat = crs.reproject(geometry, &Crs::Crs84);
+        let (length, perimeter, area) = measure(&lon_lat);
+        Self {
+            length,
+            perimeter,
+            area,
+            centroid: geometry
+                .centroid()
+                .map(|centroid| crs.unproject(centroid.into())),
+            bounds: lon_lat.bounding_rect(),
+        }
+    }
+
+    /// The measurements of an entity, if it has a geometry.
+    pub fn of_entity(entity: &models::Entity) -> Option<Self> {
+        Some(Self::of(entity.geometry.as_deref()?, &entity.crs))
+    }
+
+    /// The measurements worth showing for the kind of geometry, as labels and values. Points
+    /// only have a centroid.
+    pub fn rows(&self, unit: LengthUnit) -> Vec<(&'static str, String)> {
+        let mut rows = Vec::new();
+        if self.length > 0.0 {
+            rows.push(("Length", format_length(self.length, unit)));
+        }
+        if self.area > 0.0 {
+            rows.push(("Area", format_area(self.area)));
+            rows.push(("Perimeter", format_length(self.perimeter, unit)));
+        }
+        if let Some(centroid) = self.centroid {
+            rows.push(("Centroid", format_lon_lat(centroid)));
+        }
+        if let Some(bounds) = self.bounds.filter(|bounds| bounds.min() != bounds.max()) {
+            rows.push((
+                "Bounds",
+                format!(
+                    "{} to {}",
+                    format_lon_lat(bounds.min()),
+                    format_lon_lat(bounds.max())
+                ),
+            ));
+        }
+        rows
+    }
+}
+
+/// The length of the lines, the perimeter of the polygons and their area, of a geometry in
+/// CRS84.
+fn measure(geometry: &Geometry) -> (f64, f64, f64) {
+    match geometry {
+        Geometry::Line(line) => (line.geodesic_length(), 0.0, 0.0),
+        Geometry::LineString(line) => (line.geodesic_length(), 0.0, 0.0),
+        Geometry::MultiLineString(lines) => (lines.geodesic_length(), 0.0, 0.0),
+        Geometry::Polygon(_)
+        | Geometry::MultiPolygon(_)
+        | Geometry::Rect(_)
+        | Geometry::Triangle(_) => {
+            let (perimeter, area) = geometry.geodesic_perimeter_area_unsigned();
+            (0.0, perimeter, area)
+        }
+        Geometry::GeometryCollection(collection) => collection
+            .iter()
+            .map(measure)
+            .fold((0.0, 0.0, 0.0), |(a, b, c), (x, y, z)| {
+                (a + x, b + y, c + z)
+            }),
+        Geometry::Point(_) | Geometry::MultiPoint(_) => (0.0, 0.0, 0.0),
+    }
+}
+
+/// The geodesic length of a line in some CRS, in metres.
+pub fn line_length(line: &LineString, crs: &Crs) -> f64 {
+    match crs.reproject(&Geometry::LineString(line.clone()), &Crs::Crs84) {
+        Geometry::LineString(line) => line.geodesic_length(),
+        _ => 0.0,
+    }
+}
+
+/// A number to about three significant figures, with no more decimal places than needed.
+fn rounded(value: f64) -> String {
+    let decimals = if value < 10.0 {
+        2
+    } else if value < 100.0 {
+        1
+    } else {
+        0
+    };
+    let text = format!("{value:.decimals$}");
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// A length given in metres, e.g. `"350 m"`, `"12.4 km"` or `"6.48 nm"`.
+pub fn format_length(metres: f64, unit: LengthUnit) -> String {
+    match unit {
+        LengthUnit::Nautical if metres >= NAUTICAL_MILE / 10.0 => {
+            format!("{} nm", rounded(metres / NAUTICAL_MILE))
+        }
+        _ if metres >= 1000.0 && unit == LengthUnit::Metric => {
+            format!("{} km", rounded(metres / 1000.0))
+        }
+        _ => format!("{} m", rounded(metres)),
+    }
+}
+
+/// An area given in square metres, e.g. `"850 m²"`, `"3.2 ha"` or `"41.5 km²"`.
+pub fn format_area(square_metres: f64) -> String {
+    if square_metres >= 1_000_000.0 {
+        format!("{} km²", rounded(square_metres / 1_000_000.0))
+    } else if square_metres >= 10_000.0 {
+        format!("{} ha", rounded(square_metres / 10_000.0))
+    } else {
+        format!("{} m²", rounded(square_metres))
+    }
+}
+
+/// A CRS84 coordinate as latitude and longitude in degrees, e.g. `"16.9203°S 145.7710°E"`.
+pub fn format_lon_lat(coord: Coord) -> String {
+    format!(
+        "{:.4}°{} {:.4}°{}",
+        coord.y.abs(),
+        if coord.y < 0.0 { 'S' } else { 'N' },
+        coord.x.abs(),
+        if coord.x < 0.0 { 'W' } else { 'E' },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{coord, line_string, polygon};
+
+    use super::*;
+
+    #[test]
+    fn test_measurements() {
+        // A degree of longitude along the equator.
+        let line = Geometry::LineString(line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0)]);
+        let measurements = Measurements::of(&line, &Crs::Crs84);
+        assert!((measurements.length - 111_319.49).abs() < 0.01);
+        assert_eq!(measurements.area, 0.0);
+        assert_eq!(measurements.centroid, Some(coord! { x: 0.5, y: 0.0 }));
+
+        // A square kilometre in a UTM zone.
+        let square = Geometry::Polygon(polygon![
+            (x: 500_000.0, y: 8_000_000.0),
+            (x: 501_000.0, y: 8_000_000.0),
+            (x: 501_000.0, y: 8_001_000.0),
+            (x: 500_000.0, y: 8_001_000.0),
+        ]);
+        let measurements = Measurements::of(&square, &Crs::mga(55));
+        // Grid distances near the central meridian are 0.9996 of those on the ground.
+        assert!((measurements.area / 1_000_000.0 - 1.0 / 0.9996f64.powi(2)).abs() < 1e-3);
+        assert!((measurements.perimeter - 4000.0 / 0.9996).abs() < 1.0);
+        assert_eq!(
+            measurements
+                .rows(LengthUnit::Metric)
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>(),
+            ["Area", "Perimeter", "Centroid", "Bounds"]
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_length(3.456, LengthUnit::Metric), "3.46 m");
+        assert_eq!(format_length(350.0, LengthUnit::Metric), "350 m");
+        assert_eq!(format_length(12_400.0, LengthUnit::Metric), "12.4 km");
+        assert_eq!(format_length(150.0, LengthUnit::Nautical), "150 m");
+        assert_eq!(format_length(12_000.0, LengthUnit::Nautical), "6.48 nm");
+        assert_eq!(format_area(850.0), "850 m²");
+        assert_eq!(format_area(32_000.0), "3.2 ha");
+        assert_eq!(format_area(41_500_000.0), "41.5 km²");
+        assert_eq!(
+            format_lon_lat(coord! { x: 145.771, y: -16.9203 }),
+            "16.9203°S 145.7710°E"
+        );
+    }
+}
@@ -9,6 +9,8 @@ use implicit_clone::{
     ImplicitClone,
 };
 
+use crate::crs::Crs;
+
 pub type ActivityLink = (Option<IString>, IString);
 pub type AgentLink = (Option<IString>, IString);
 pub type EntityLink = (Option<IString>, IString);
@@ -42,7 +44,10 @@ pub struct DimDesc {
     pub name: IString,
     pub order: usize,
     pub range: Range<f32>,
+    /// The values aimed for, within the range.
     pub target_range: Option<Range<f32>>,
+    /// Whether lower values are better, e.g. for risk or cost.
+    pub inverted: bool,
 }
 
 impl ImplicitClone for DimDesc {}
@@ -57,6 +62,11 @@ impl ImplicitClone for DimValue {}
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Entity {
+    /// The CRS of the geometry.
+    pub crs: Crs,
+    /// When the entity came to be: its `prov:atTime`, or when the activity that generated it
+    /// ended.
+    pub generated_at: Option<DateTime<Utc>>,
     pub geometry: Option<Rc<Geometry>>,
     pub label: Option<IString>,
     pub properties: IArray<(IString, IString)>,
@@ -0,0 +1,211 @@
+//! Scoring and ranking of alternatives, e.g. the paths of a mission, by the values of their
+//! dimensions, for multi-criteria decision analysis.
+//!
+//! Values are normalised against the ranges of their dimensions, so that 0 is the worst and 1 the
+//! best whatever their units and whether lower is better. Missing values count as the worst.
+
+use std::cmp::Ordering;
+
+use implicit_clone::unsync::{IArray, IString};
+
+use crate::models;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Method {
+    /// The weighted mean of the normalised values.
+    #[default]
+    WeightedSum,
+    /// The Technique for Order of Preference by Similarity to Ideal Solution: how much closer
+    /// the weighted values are to the best of each dimension among the alternatives than to the
+    /// worst.
+    Topsis,
+}
+
+impl Method {
+    pub const ALL: [Method; 2] = [Method::WeightedSum, Method::Topsis];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::WeightedSum => "Weighted sum",
+            Method::Topsis => "TOPSIS",
+        }
+    }
+}
+
+/// An alternative with its score, from 0 to 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ranked {
+    pub name: IString,
+    pub score: f32,
+}
+
+/// How good a value is, from 0 at the worst end of the range of its dimension to 1 at the best.
+/// Values outside the range are clamped to it.
+pub fn normalised(dd: &models::DimDesc, value: f32) -> f32 {
+    let length = dd.range.end - dd.range.start;
+    let fraction = if length > 0.0 {
+        ((value - dd.range.start) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    if dd.inverted {
+        1.0 - fraction
+    } else {
+        fraction
+    }
+}
+
+/// The weights as fractions of their total, with negative weights ignored.
+fn fractions(weights: &[f32]) -> Vec<f32> {
+    let total: f32 = weights.iter().map(|weight| weight.max(0.0)).sum();
+    weights
+        .iter()
+        .map(|weight| {
+            if total > 0.0 {
+                weight.max(0.0) / total
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+fn topsis(matrix: &[Vec<f32>], weights: &[f32]) -> Vec<f32> {
+    let weighted = matrix
+        .iter()
+        .map(|row| row.iter().zip(weights).map(|(x, w)| x * w).collect())
+        .collect::<Vec<Vec<f32>>>();
+    let extreme = |pick: fn(f32, f32) -> f32| {
+        (0..weights.len())
+            .map(|j| {
+                weighted
+                    .iter()
+                    .map(|row| row[j])
+                    .reduce(pick)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+    };
+    let (best, worst) = (extreme(f32::max), extreme(f32::min));
+    let distance = |row: &[f32], to: &[f32]| {
+        row.iter()
+            .zip(to)
+            .map(|(x, y)| (x - y).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    };
+    weighted
+        .iter()
+        .map(|row| {
+            let (to_best, to_worst) = (distance(row, &best), distance(row, &worst));
+            // Alternatives that are alike in every weighted dimension are neither better nor
+            // worse than each other.
+            if to_best + to_worst > 0.0 {
+                to_worst / (to_best + to_worst)
+            } else {
+                0.5
+            }
+        })
+        .collect()
+}
+
+/// The alternatives from best to worst, with the weight of each dimension in `weights` in the
+/// same order as `dim_desc`. Alternatives with the same score are in order of their names.
+pub fn rank(
+    dim_desc: &[models::DimDesc],
+    dim_values: &[(IString, IArray<models::DimValue>)],
+    weights: &[f32],
+    method: Method,
+) -> Vec<Ranked> {
+    let weights = fractions(weights);
+    let matrix = dim_values
+        .iter()
+        .map(|(_, dvs)| {
+            dim_desc
+                .iter()
+                .map(|dd| {
+                    dvs.iter()
+                        .find(|dv| dv.d == dd.name)
+                        .map_or(0.0, |dv| normalised(dd, dv.v))
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<f32>>>();
+    let scores = match method {
+        Method::WeightedSum => matrix
+            .iter()
+            .map(|row| row.iter().zip(&weights).map(|(x, w)| x * w).sum())
+            .collect(),
+        Method::Topsis => topsis(&matrix, &weights),
+    };
+    let mut ranked = dim_values
+        .iter()
+        .zip(scores)
+        .map(|((name, _), score)| Ranked {
+            name: name.clone(),
+            score,
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let dd = |name: &'static str, inverted| models::DimDesc {
+            name: name.into(),
+            order: 1,
+            range: 0.0..4.0,
+            target_range: None,
+            inverted,
+        };
+        let dim_desc = [dd("Cost", true), dd("Coverage", false)];
+        let dv = |d: &'static str, v| models::DimValue { d: d.into(), v };
+        let dim_values = [
+            (
+                IString::from("Path A"),
+                IArray::from(vec![dv("Cost", 1.0), dv("Coverage", 2.0)]),
+            ),
+            (
+                IString::from("Path B"),
+                IArray::from(vec![dv("Cost", 3.0), dv("Coverage", 3.0)]),
+            ),
+            (IString::from("Path C"), IArray::from(vec![dv("Cost", 4.0)])),
+        ];
+        let names = |ranked: &[Ranked]| {
+            ranked
+                .iter()
+                .map(|ranked| ranked.name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let ranked = rank(&dim_desc, &dim_values, &[1.0, 1.0], Method::WeightedSum);
+        assert_eq!(names(&ranked), ["Path A", "Path B", "Path C"]);
+        assert_eq!(ranked[0].score, 0.625);
+        assert_eq!(ranked[2].score, 0.0);
+        // Only coverage counts.
+        let ranked = rank(&dim_desc, &dim_values, &[0.0, 2.0], Method::WeightedSum);
+        assert_eq!(names(&ranked), ["Path B", "Path A", "Path C"]);
+        assert_eq!(ranked[0].score, 0.75);
+
+        let ranked = rank(&dim_desc, &dim_values, &[1.0, 1.0], Method::Topsis);
+        assert_eq!(names(&ranked), ["Path A", "Path B", "Path C"]);
+        // Path C is the worst in both dimensions.
+        assert_eq!(ranked[2].score, 0.0);
+        let ranked = rank(&dim_desc, &dim_values, &[1.0, 0.0], Method::Topsis);
+        assert_eq!(ranked[0].score, 1.0);
+        assert_eq!(
+            rank(&dim_desc, &dim_values, &[0.0, 0.0], Method::Topsis)[0].score,
+            0.5
+        );
+    }
+}